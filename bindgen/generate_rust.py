@@ -4,6 +4,7 @@ import ufbx_ir as ir
 import argparse
 import os
 import json
+import copy
 
 g_argv = None
 
@@ -296,6 +297,11 @@ default_derive_types = {
     "ufbx_panic",
 }
 
+# Pointers that can be NULL but are not (yet) annotated `ufbx_nullable` in ufbx.h
+nullable_fields = {
+    ("ufbx_shader_texture", "main_texture"),
+}
+
 ignore_types = {
     "ufbx_string",
     "ufbx_blob",
@@ -884,7 +890,23 @@ def init_function(fn: ir.Function):
                 rf.is_raw = True
             rf.args.append(RustArgument(arg, arg.kind, arg_ix))
 
+def apply_nullable_fields():
+    for struct_name, field_name in nullable_fields:
+        for field in file.structs[struct_name].fields:
+            if field.name != field_name: continue
+            typ = file.types[field.type]
+            assert typ.kind == "pointer"
+            if typ.is_nullable: continue
+            key = typ.key[:-1] + "?*"
+            if key not in file.types:
+                nullable = copy.copy(typ)
+                nullable.key = key
+                nullable.is_nullable = True
+                file.types[key] = nullable
+            field.type = key
+
 def init_file():
+    apply_nullable_fields()
     for name in file.types:
         init_type(file.types[name])
     for name in file.structs:
@@ -0,0 +1,63 @@
+use crate::generated::{Scene, BakedAnim, BakedVec3, BakedQuat, BakedKeyFlags, Transform, Result, quat_fix_antipodal};
+
+/// Local transform keys of a node sampled at every key time of a `BakedAnim`,
+/// see `bake_constraints()` and `Retargeter::bake()`.
+pub struct BakedNodeKeys {
+    pub typed_id: u32,
+    pub element_id: u32,
+    pub translation_keys: Vec<BakedVec3>,
+    pub rotation_keys: Vec<BakedQuat>,
+    pub scale_keys: Vec<BakedVec3>,
+}
+
+// Sorted union of all node key times in `baked`
+fn baked_key_times(baked: &BakedAnim) -> Vec<f64> {
+    let mut times: Vec<f64> = baked.nodes.iter().flat_map(|node| {
+        let translation = node.translation_keys.iter().map(|k| k.time);
+        let rotation = node.rotation_keys.iter().map(|k| k.time);
+        let scale = node.scale_keys.iter().map(|k| k.time);
+        translation.chain(rotation).chain(scale)
+    }).collect();
+    times.sort_by(|a, b| a.total_cmp(b));
+    times.dedup();
+    if times.is_empty() {
+        times.push(baked.playback_time_begin);
+    }
+    times
+}
+
+// Samples the local transforms of `node_ids` in `scene` at every key time in `baked`,
+// `evaluate` returns the local transforms of all nodes indexed like `scene.nodes[]`
+#[allow(clippy::result_large_err)]
+pub(crate) fn bake_node_keys<F>(scene: &Scene, node_ids: &[u32], baked: &BakedAnim, mut evaluate: F) -> Result<Vec<BakedNodeKeys>>
+    where F: FnMut(f64) -> Result<Vec<Transform>>
+{
+    let mut node_ids = node_ids.to_vec();
+    node_ids.sort_unstable();
+    node_ids.dedup();
+
+    let times = baked_key_times(baked);
+
+    let mut result: Vec<BakedNodeKeys> = node_ids.iter().map(|&id| BakedNodeKeys {
+        typed_id: id,
+        element_id: scene.nodes[id as usize].element.element_id,
+        translation_keys: Vec::with_capacity(times.len()),
+        rotation_keys: Vec::with_capacity(times.len()),
+        scale_keys: Vec::with_capacity(times.len()),
+    }).collect();
+
+    for &time in &times {
+        let local_transforms = evaluate(time)?;
+        for node in &mut result {
+            let local = local_transforms[node.typed_id as usize];
+            let rotation = match node.rotation_keys.last() {
+                Some(prev) => quat_fix_antipodal(local.rotation, prev.value),
+                None => local.rotation,
+            };
+            node.translation_keys.push(BakedVec3 { time, value: local.translation, flags: BakedKeyFlags::NONE });
+            node.rotation_keys.push(BakedQuat { time, value: rotation, flags: BakedKeyFlags::NONE });
+            node.scale_keys.push(BakedVec3 { time, value: local.scale, flags: BakedKeyFlags::NONE });
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,133 @@
+use std::ops::RangeInclusive;
+use crate::generated::{Scene, Node, Mesh, Anim, Matrix, Vec3, EvaluateOpts, Result, evaluate_scene, transform_position};
+use crate::prelude::Real;
+use crate::math::{vec3_add, vec3_sub, vec3_mul};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Aabb::empty()
+    }
+}
+
+impl Aabb {
+    pub fn empty() -> Aabb {
+        Aabb {
+            min: Vec3 { x: Real::INFINITY, y: Real::INFINITY, z: Real::INFINITY },
+            max: Vec3 { x: Real::NEG_INFINITY, y: Real::NEG_INFINITY, z: Real::NEG_INFINITY },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn add_point(&mut self, p: Vec3) {
+        self.min = Vec3 { x: self.min.x.min(p.x), y: self.min.y.min(p.y), z: self.min.z.min(p.z) };
+        self.max = Vec3 { x: self.max.x.max(p.x), y: self.max.y.max(p.y), z: self.max.z.max(p.z) };
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        let mut result = *self;
+        if !other.is_empty() {
+            result.add_point(other.min);
+            result.add_point(other.max);
+        }
+        result
+    }
+
+    pub fn center(&self) -> Vec3 {
+        vec3_mul(vec3_add(self.min, self.max), 0.5)
+    }
+
+    pub fn size(&self) -> Vec3 {
+        if self.is_empty() { Vec3::default() } else { vec3_sub(self.max, self.min) }
+    }
+
+    /// Bounds of the box corners transformed by `matrix`.
+    pub fn transformed(&self, matrix: &Matrix) -> Aabb {
+        let mut result = Aabb::empty();
+        if self.is_empty() { return result }
+        for i in 0..8 {
+            let corner = Vec3 {
+                x: if i & 1 != 0 { self.max.x } else { self.min.x },
+                y: if i & 2 != 0 { self.max.y } else { self.min.y },
+                z: if i & 4 != 0 { self.max.z } else { self.min.z },
+            };
+            result.add_point(transform_position(matrix, corner));
+        }
+        result
+    }
+}
+
+impl Mesh {
+    /// Bounds of the vertex positions in geometry space.
+    pub fn local_bounds(&self) -> Aabb {
+        let mut bounds = Aabb::empty();
+        for &p in &self.vertices {
+            bounds.add_point(p);
+        }
+        bounds
+    }
+}
+
+fn mesh_world_bounds(node: &Node, mesh: &Mesh) -> Aabb {
+    // Skinned positions are the static ones unless the scene was evaluated with skinning
+    let mut bounds = Aabb::empty();
+    for &p in &mesh.skinned_position.values {
+        bounds.add_point(if mesh.skinned_is_local { transform_position(&node.geometry_to_world, p) } else { p });
+    }
+    bounds
+}
+
+impl Node {
+    /// World space bounds of the mesh attached to this node, empty if there is none.
+    pub fn world_bounds(&self) -> Aabb {
+        match self.mesh.as_ref() {
+            Some(mesh) => mesh_world_bounds(self, mesh),
+            None => Aabb::empty(),
+        }
+    }
+}
+
+impl Scene {
+    /// World space bounds of every mesh instance.
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.iter().fold(Aabb::empty(), |bounds, node| bounds.union(&node.world_bounds()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimatedBounds {
+    pub bounds: Aabb,
+    /// Bounds of each node over the whole range, indexed like `scene.nodes[]`.
+    pub node_bounds: Vec<Aabb>,
+}
+
+/// Evaluates `anim` with skinning at `samples` evenly spaced times in `time_range`
+/// and returns the union of the world bounds at each sample.
+#[allow(clippy::result_large_err)]
+pub fn animated_bounds(scene: &Scene, anim: &Anim, time_range: RangeInclusive<f64>, samples: usize) -> Result<AnimatedBounds> {
+    let mut result = AnimatedBounds {
+        bounds: Aabb::empty(),
+        node_bounds: vec![Aabb::empty(); scene.nodes.len()],
+    };
+    let (begin, end) = (*time_range.start(), *time_range.end());
+    let samples = samples.max(1);
+    for i in 0..samples {
+        let time = if samples > 1 { begin + (end - begin) * i as f64 / (samples - 1) as f64 } else { begin };
+        let opts = EvaluateOpts { evaluate_skinning: true, ..Default::default() };
+        let state = evaluate_scene(scene, anim, time, opts)?;
+        for (node, node_bounds) in state.nodes.iter().zip(result.node_bounds.iter_mut()) {
+            let bounds = node.world_bounds();
+            *node_bounds = node_bounds.union(&bounds);
+            result.bounds = result.bounds.union(&bounds);
+        }
+    }
+    Ok(result)
+}
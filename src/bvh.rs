@@ -0,0 +1,332 @@
+use crate::generated::{Scene, Node, Mesh, Matrix, Vec2, Vec3, matrix_invert, matrix_for_normals, transform_position, transform_direction};
+use crate::prelude::Real;
+use crate::bounds::Aabb;
+use crate::triangulate_face_vec;
+use crate::math::{vec3_add, vec3_sub, vec3_mul, vec3_dot, vec3_cross, vec3_normalize};
+
+/// Returns the entry distance of the ray if it hits the box before `max_t`.
+fn ray_entry(bounds: &Aabb, origin: Vec3, inv_dir: Vec3, max_t: Real) -> Option<Real> {
+    let slab = |min: Real, max: Real, o: Real, inv: Real| {
+        let (a, b) = ((min - o) * inv, (max - o) * inv);
+        (a.min(b), a.max(b))
+    };
+    let (x0, x1) = slab(bounds.min.x, bounds.max.x, origin.x, inv_dir.x);
+    let (y0, y1) = slab(bounds.min.y, bounds.max.y, origin.y, inv_dir.y);
+    let (z0, z1) = slab(bounds.min.z, bounds.max.z, origin.z, inv_dir.z);
+    let t0 = x0.max(y0).max(z0).max(0.0);
+    let t1 = x1.min(y1).min(z1).min(max_t);
+    if t0 <= t1 { Some(t0) } else { None }
+}
+
+#[derive(Clone, Debug)]
+struct BvhNode {
+    bounds: Aabb,
+    /// First item for leaves, first of two children for inner nodes.
+    first: u32,
+    /// Number of items, zero for inner nodes.
+    count: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Bvh {
+    nodes: Vec<BvhNode>,
+    items: Vec<u32>,
+}
+
+const BVH_LEAF_SIZE: usize = 4;
+
+impl Bvh {
+    fn build(bounds: &[Aabb]) -> Bvh {
+        let mut bvh = Bvh { nodes: Vec::new(), items: (0..bounds.len() as u32).collect() };
+        if bounds.is_empty() { return bvh }
+        let centers: Vec<Vec3> = bounds.iter().map(|b| b.center()).collect();
+
+        bvh.nodes.push(BvhNode { bounds: Aabb::empty(), first: 0, count: 0 });
+        let mut stack = vec![(0usize, 0usize, bounds.len())];
+        while let Some((node, begin, end)) = stack.pop() {
+            let items = &mut bvh.items[begin..end];
+            let mut node_bounds = Aabb::empty();
+            let mut center_bounds = Aabb::empty();
+            for &item in items.iter() {
+                node_bounds = node_bounds.union(&bounds[item as usize]);
+                center_bounds.add_point(centers[item as usize]);
+            }
+            bvh.nodes[node].bounds = node_bounds;
+
+            let size = center_bounds.size();
+            let axis = if size.x >= size.y && size.x >= size.z { 0 } else if size.y >= size.z { 1 } else { 2 };
+            let extent = [size.x, size.y, size.z][axis];
+            if items.len() <= BVH_LEAF_SIZE || extent <= 0.0 {
+                bvh.nodes[node].first = begin as u32;
+                bvh.nodes[node].count = items.len() as u32;
+                continue;
+            }
+
+            let key = |item: &u32| {
+                let c = centers[*item as usize];
+                [c.x, c.y, c.z][axis]
+            };
+            let mid = items.len() / 2;
+            items.select_nth_unstable_by(mid, |a, b| key(a).partial_cmp(&key(b)).unwrap_or(std::cmp::Ordering::Equal));
+
+            let children = bvh.nodes.len();
+            bvh.nodes.push(BvhNode { bounds: Aabb::empty(), first: 0, count: 0 });
+            bvh.nodes.push(BvhNode { bounds: Aabb::empty(), first: 0, count: 0 });
+            bvh.nodes[node].first = children as u32;
+            stack.push((children, begin, begin + mid));
+            stack.push((children + 1, begin + mid, end));
+        }
+        bvh
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.nodes.first().map(|n| n.bounds).unwrap_or_default()
+    }
+
+    /// Calls `visit` for items whose bounds the ray enters before `*max_t`,
+    /// `visit` may shrink `max_t` and returns `true` to stop the traversal.
+    fn traverse<F: FnMut(u32, &mut Real) -> bool>(&self, origin: Vec3, dir: Vec3, max_t: &mut Real, mut visit: F) {
+        if self.nodes.is_empty() { return }
+        let inv_dir = Vec3 { x: 1.0 / dir.x, y: 1.0 / dir.y, z: 1.0 / dir.z };
+        let mut stack = vec![0u32];
+        while let Some(ix) = stack.pop() {
+            let node = &self.nodes[ix as usize];
+            if ray_entry(&node.bounds, origin, inv_dir, *max_t).is_none() { continue }
+            if node.count == 0 {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+            } else {
+                for &item in &self.items[node.first as usize..(node.first + node.count) as usize] {
+                    if visit(item, max_t) { return }
+                }
+            }
+        }
+    }
+}
+
+/// Möller-Trumbore intersection, returns `(t, u, v)` for hits from either side.
+fn intersect_triangle(origin: Vec3, dir: Vec3, p: &[Vec3; 3]) -> Option<(Real, Real, Real)> {
+    let e1 = vec3_sub(p[1], p[0]);
+    let e2 = vec3_sub(p[2], p[0]);
+    let pv = vec3_cross(dir, e2);
+    let det = vec3_dot(e1, pv);
+    if det.abs() < 1e-20 { return None }
+    let inv_det = 1.0 / det;
+    let tv = vec3_sub(origin, p[0]);
+    let u = vec3_dot(tv, pv) * inv_det;
+    if !(0.0..=1.0).contains(&u) { return None }
+    let qv = vec3_cross(tv, e1);
+    let v = vec3_dot(dir, qv) * inv_det;
+    if v < 0.0 || u + v > 1.0 { return None }
+    let t = vec3_dot(e2, qv) * inv_det;
+    Some((t, u, v))
+}
+
+struct MeshBlas {
+    bvh: Bvh,
+    positions: Vec<[Vec3; 3]>,
+    normals: Option<Vec<[Vec3; 3]>>,
+    uvs: Option<Vec<[Vec2; 3]>>,
+    indices: Vec<[u32; 3]>,
+    faces: Vec<u32>,
+}
+
+impl MeshBlas {
+    fn new(mesh: &Mesh) -> MeshBlas {
+        let mut blas = MeshBlas {
+            bvh: Bvh::default(),
+            positions: Vec::new(),
+            normals: if mesh.skinned_normal.exists { Some(Vec::new()) } else { None },
+            uvs: if mesh.vertex_uv.exists { Some(Vec::new()) } else { None },
+            indices: Vec::new(),
+            faces: Vec::new(),
+        };
+        let mut tri_indices = Vec::new();
+        for (face_ix, &face) in mesh.faces.iter().enumerate() {
+            triangulate_face_vec(&mut tri_indices, mesh, face);
+            for tri in tri_indices.chunks_exact(3) {
+                let ix = [tri[0], tri[1], tri[2]];
+                let corners = ix.map(|i| i as usize);
+                blas.positions.push(corners.map(|i| mesh.skinned_position[i]));
+                if let Some(normals) = &mut blas.normals {
+                    normals.push(corners.map(|i| mesh.skinned_normal[i]));
+                }
+                if let Some(uvs) = &mut blas.uvs {
+                    uvs.push(corners.map(|i| mesh.vertex_uv[i]));
+                }
+                blas.indices.push(ix);
+                blas.faces.push(face_ix as u32);
+            }
+        }
+
+        let bounds: Vec<Aabb> = blas.positions.iter().map(|p| {
+            let mut b = Aabb::empty();
+            for &v in p {
+                b.add_point(v);
+            }
+            b
+        }).collect();
+        blas.bvh = Bvh::build(&bounds);
+        blas
+    }
+}
+
+struct Instance {
+    blas: usize,
+    node_index: usize,
+    mesh_index: usize,
+    to_world: Matrix,
+    to_local: Matrix,
+    normal_to_world: Matrix,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    /// Ray parameter of the hit, the distance if the direction is normalized.
+    pub t: Real,
+    pub position: Vec3,
+    /// Index into `scene.nodes[]`.
+    pub node_index: usize,
+    /// Index into `scene.meshes[]`.
+    pub mesh_index: usize,
+    /// Index into `mesh.faces[]`, same as `find_face_index()` for any of `indices`.
+    pub face_index: u32,
+    /// Indices into `mesh.vertex_indices[]` of the hit triangle.
+    pub indices: [u32; 3],
+    /// Weights of the triangle corners at the hit position.
+    pub barycentrics: [Real; 3],
+    /// Interpolated UV, zero if the mesh has no UVs.
+    pub uv: Vec2,
+    /// Interpolated world space normal, the geometric normal if the mesh has no normals.
+    pub normal: Vec3,
+    pub geometric_normal: Vec3,
+}
+
+impl RayHit {
+    pub fn node<'a>(&self, scene: &'a Scene) -> &'a Node {
+        &scene.nodes[self.node_index]
+    }
+
+    pub fn mesh<'a>(&self, scene: &'a Scene) -> &'a Mesh {
+        &scene.meshes[self.mesh_index]
+    }
+}
+
+/// Two-level BVH over every mesh instance in a scene.
+///
+/// Uses the skinned vertex positions, so building it from the result of
+/// `evaluate_scene()` with `evaluate_skinning` picks the animated pose.
+pub struct SceneBvh {
+    meshes: Vec<MeshBlas>,
+    instances: Vec<Instance>,
+    top: Bvh,
+}
+
+impl SceneBvh {
+    pub fn new(scene: &Scene) -> SceneBvh {
+        let mut meshes = Vec::new();
+        let mut instances = Vec::new();
+        for mesh in &scene.meshes {
+            if mesh.element.instances.is_empty() { continue }
+            let blas = meshes.len();
+            meshes.push(MeshBlas::new(mesh));
+            for node in &mesh.element.instances {
+                let to_world = if mesh.skinned_is_local { node.geometry_to_world } else { Matrix::identity() };
+                instances.push(Instance {
+                    blas,
+                    node_index: node.element.typed_id as usize,
+                    mesh_index: mesh.element.typed_id as usize,
+                    to_local: matrix_invert(&to_world),
+                    normal_to_world: matrix_for_normals(&to_world),
+                    to_world,
+                });
+            }
+        }
+
+        let bounds: Vec<Aabb> = instances.iter()
+            .map(|inst| meshes[inst.blas].bvh.bounds().transformed(&inst.to_world))
+            .collect();
+        let top = Bvh::build(&bounds);
+        SceneBvh { meshes, instances, top }
+    }
+
+    pub fn num_instances(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// World space bounds of all instances.
+    pub fn bounds(&self) -> Aabb {
+        self.top.bounds()
+    }
+
+    fn trace(&self, origin: Vec3, direction: Vec3, max_t: Real, any_hit: bool) -> Option<(usize, usize, Real, Real, Real)> {
+        let mut closest = None;
+        let mut max_t = max_t;
+        self.top.traverse(origin, direction, &mut max_t, |inst_ix, max_t| {
+            let inst = &self.instances[inst_ix as usize];
+            let blas = &self.meshes[inst.blas];
+            // Affine transforms keep the ray parameter unchanged
+            let local_origin = transform_position(&inst.to_local, origin);
+            let local_dir = transform_direction(&inst.to_local, direction);
+            let mut stop = false;
+            blas.bvh.traverse(local_origin, local_dir, max_t, |tri, max_t| {
+                if let Some((t, u, v)) = intersect_triangle(local_origin, local_dir, &blas.positions[tri as usize]) {
+                    if t >= 0.0 && t < *max_t {
+                        *max_t = t;
+                        closest = Some((inst_ix as usize, tri as usize, t, u, v));
+                        stop = any_hit;
+                    }
+                }
+                stop
+            });
+            stop
+        });
+        closest
+    }
+
+    /// Finds the closest hit along `origin + t * direction` for `t` in `[0, max_t)`.
+    /// Triangles are hit from both sides.
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, max_t: Real) -> Option<RayHit> {
+        let (inst_ix, tri, t, u, v) = self.trace(origin, direction, max_t, false)?;
+        let inst = &self.instances[inst_ix];
+        let blas = &self.meshes[inst.blas];
+        let w = [1.0 - u - v, u, v];
+        let interpolate = |p: &[Vec3; 3]| vec3_add(vec3_add(vec3_mul(p[0], w[0]), vec3_mul(p[1], w[1])), vec3_mul(p[2], w[2]));
+
+        let p = &blas.positions[tri];
+        let geometric_normal = vec3_normalize(transform_direction(&inst.normal_to_world,
+            vec3_cross(vec3_sub(p[1], p[0]), vec3_sub(p[2], p[0]))));
+        let normal = match &blas.normals {
+            Some(normals) => vec3_normalize(transform_direction(&inst.normal_to_world, interpolate(&normals[tri]))),
+            None => geometric_normal,
+        };
+        let uv = match &blas.uvs {
+            Some(uvs) => {
+                let uv = &uvs[tri];
+                Vec2 {
+                    x: uv[0].x * w[0] + uv[1].x * w[1] + uv[2].x * w[2],
+                    y: uv[0].y * w[0] + uv[1].y * w[1] + uv[2].y * w[2],
+                }
+            },
+            None => Vec2::default(),
+        };
+
+        Some(RayHit {
+            t,
+            position: transform_position(&inst.to_world, interpolate(p)),
+            node_index: inst.node_index,
+            mesh_index: inst.mesh_index,
+            face_index: blas.faces[tri],
+            indices: blas.indices[tri],
+            barycentrics: w,
+            uv,
+            normal,
+            geometric_normal,
+        })
+    }
+
+    /// Returns `true` if anything is hit along the ray before `max_t`.
+    pub fn occluded(&self, origin: Vec3, direction: Vec3, max_t: Real) -> bool {
+        self.trace(origin, direction, max_t, true).is_some()
+    }
+}
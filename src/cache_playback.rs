@@ -0,0 +1,360 @@
+use std::collections::{HashMap, HashSet};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use crate::generated::{Mesh, Vec3, CacheChannel, CacheFrame, CacheInterpretation, CacheDataFormat, CacheDataEncoding, MirrorAxis};
+use crate::prelude::Real;
+
+#[derive(Debug)]
+pub enum CachePlaybackError {
+    /// None of the cache deformers of the mesh have a loaded position channel,
+    /// load the scene with `LoadOpts::load_external_files`.
+    NoPositionChannel,
+    UnsupportedFormat { channel: String, frame: usize, format: CacheDataFormat },
+    /// The cache has a different number of points than the mesh has vertices (or `vertex_remap` entries).
+    PointCountMismatch { channel: String, frame: usize, expected: usize, found: usize },
+    /// `vertex_remap` refers to a vertex outside of the mesh.
+    InvalidRemap { index: usize, vertex: u32, num_vertices: usize },
+    FrameOutOfRange { frame: usize, num_frames: usize },
+    Io { filename: String, error: io::Error },
+}
+
+impl Display for CachePlaybackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CachePlaybackError::NoPositionChannel => write!(f, "mesh has no loaded cache channel with vertex positions"),
+            CachePlaybackError::UnsupportedFormat { channel, frame, format } =>
+                write!(f, "channel '{}' frame {} has unsupported data format {:?}", channel, frame, format),
+            CachePlaybackError::PointCountMismatch { channel, frame, expected, found } =>
+                write!(f, "channel '{}' frame {} has {} points but {} were expected", channel, frame, found, expected),
+            CachePlaybackError::InvalidRemap { index, vertex, num_vertices } =>
+                write!(f, "vertex_remap[{}] = {} is out of bounds for {} vertices", index, vertex, num_vertices),
+            CachePlaybackError::FrameOutOfRange { frame, num_frames } =>
+                write!(f, "frame {} is out of range for {} frames", frame, num_frames),
+            CachePlaybackError::Io { filename, error } => write!(f, "failed to read '{}': {}", filename, error),
+        }
+    }
+}
+
+impl error::Error for CachePlaybackError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CachePlaybackError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+pub struct CachePlaybackOpts {
+    /// Number of frames after the requested one to read ahead.
+    pub prefetch_frames: usize,
+    /// Read frames on a background thread, otherwise everything is read on demand.
+    pub background: bool,
+    /// Mesh vertex for each cache point, needed if the cache was written with a different vertex order.
+    /// Vertices without a cache point keep their rest position.
+    pub vertex_remap: Vec<u32>,
+}
+
+impl Default for CachePlaybackOpts {
+    fn default() -> Self {
+        CachePlaybackOpts { prefetch_frames: 4, background: true, vertex_remap: Vec::new() }
+    }
+}
+
+// Owned copy of `CacheFrame` so that it can be read on another thread
+#[derive(Clone, Debug)]
+struct FrameSource {
+    time: f64,
+    filename: String,
+    offset: u64,
+    count: usize,
+    double: bool,
+    big_endian: bool,
+    mirror_axis: MirrorAxis,
+    scale_factor: Real,
+}
+
+impl FrameSource {
+    fn new(channel: &CacheChannel, index: usize, frame: &CacheFrame) -> Result<FrameSource, CachePlaybackError> {
+        let double = match frame.data_format {
+            CacheDataFormat::Vec3Float => false,
+            CacheDataFormat::Vec3Double => true,
+            format => return Err(CachePlaybackError::UnsupportedFormat { channel: channel.name.to_string(), frame: index, format }),
+        };
+        Ok(FrameSource {
+            time: frame.time,
+            filename: frame.filename.to_string(),
+            offset: frame.data_offset,
+            count: frame.data_count as usize,
+            double,
+            big_endian: frame.data_encoding == CacheDataEncoding::BigEndian,
+            mirror_axis: frame.mirror_axis,
+            scale_factor: frame.scale_factor,
+        })
+    }
+
+    fn read(&self) -> Result<Vec<Vec3>, CachePlaybackError> {
+        let io_error = |error| CachePlaybackError::Io { filename: self.filename.clone(), error };
+        let element_size = if self.double { 8 } else { 4 };
+        let mut bytes = vec![0u8; self.count * 3 * element_size];
+        let mut file = File::open(&self.filename).map_err(io_error)?;
+        file.seek(SeekFrom::Start(self.offset)).map_err(io_error)?;
+        file.read_exact(&mut bytes).map_err(io_error)?;
+
+        let values: Vec<Real> = bytes.chunks_exact(element_size).map(|b| {
+            let value = if self.double {
+                let b = [b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]];
+                if self.big_endian { f64::from_be_bytes(b) } else { f64::from_le_bytes(b) }
+            } else {
+                let b = [b[0], b[1], b[2], b[3]];
+                (if self.big_endian { f32::from_be_bytes(b) } else { f32::from_le_bytes(b) }) as f64
+            };
+            value * self.scale_factor
+        }).collect();
+
+        Ok(values.chunks_exact(3).map(|v| {
+            let mut p = Vec3 { x: v[0], y: v[1], z: v[2] };
+            match self.mirror_axis {
+                MirrorAxis::None => {},
+                MirrorAxis::X => p.x = -p.x,
+                MirrorAxis::Y => p.y = -p.y,
+                MirrorAxis::Z => p.z = -p.z,
+            }
+            p
+        }).collect())
+    }
+}
+
+type FrameResult = Result<Vec<Vec3>, CachePlaybackError>;
+
+// Requests and results are tagged with the generation they were made in, bumping
+// `generation` makes the thread skip the queued requests instead of reading them
+struct Worker {
+    requests: Option<Sender<(usize, usize)>>,
+    results: Receiver<(usize, usize, FrameResult)>,
+    generation: Arc<AtomicUsize>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // Closing the request channel stops the thread once the cancelled requests are skipped
+        self.cancel();
+        self.requests = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Reads deformed vertex positions of a mesh from its cache deformers.
+pub struct CachePlayback {
+    channel: String,
+    frames: Arc<Vec<FrameSource>>,
+    rest_positions: Vec<Vec3>,
+    vertex_remap: Vec<u32>,
+    prefetch_frames: usize,
+    worker: Option<Worker>,
+    pending: HashSet<usize>,
+    loaded: HashMap<usize, Arc<Vec<Vec3>>>,
+}
+
+impl CachePlayback {
+    /// Uses the first channel of `mesh.cache_deformers` that contains vertex positions.
+    pub fn new(mesh: &Mesh, opts: CachePlaybackOpts) -> Result<CachePlayback, CachePlaybackError> {
+        let channel = mesh.cache_deformers.iter()
+            .filter_map(|deformer| deformer.external_channel.as_ref())
+            .find(|channel| matches!(channel.interpretation, CacheInterpretation::VertexPosition | CacheInterpretation::Points))
+            .ok_or(CachePlaybackError::NoPositionChannel)?;
+        CachePlayback::from_channel(mesh, channel, opts)
+    }
+
+    pub fn from_channel(mesh: &Mesh, channel: &CacheChannel, opts: CachePlaybackOpts) -> Result<CachePlayback, CachePlaybackError> {
+        let num_vertices = mesh.num_vertices;
+        if let Some((index, &vertex)) = opts.vertex_remap.iter().enumerate().find(|(_, &v)| v as usize >= num_vertices) {
+            return Err(CachePlaybackError::InvalidRemap { index, vertex, num_vertices });
+        }
+        let expected = if opts.vertex_remap.is_empty() { num_vertices } else { opts.vertex_remap.len() };
+
+        let mut frames = Vec::with_capacity(channel.frames.len());
+        for (index, frame) in channel.frames.iter().enumerate() {
+            let source = FrameSource::new(channel, index, frame)?;
+            if source.count != expected {
+                return Err(CachePlaybackError::PointCountMismatch {
+                    channel: channel.name.to_string(), frame: index, expected, found: source.count,
+                });
+            }
+            frames.push(source);
+        }
+        let frames = Arc::new(frames);
+
+        let worker = if opts.background {
+            let (request_tx, request_rx) = mpsc::channel::<(usize, usize)>();
+            let (result_tx, result_rx) = mpsc::channel();
+            let generation = Arc::new(AtomicUsize::new(0));
+            let sources = frames.clone();
+            let current = generation.clone();
+            let thread = thread::spawn(move || {
+                for (generation, index) in request_rx {
+                    if generation != current.load(Ordering::Acquire) { continue }
+                    if result_tx.send((generation, index, sources[index].read())).is_err() { break }
+                }
+            });
+            Some(Worker { requests: Some(request_tx), results: result_rx, generation, thread: Some(thread) })
+        } else {
+            None
+        };
+
+        Ok(CachePlayback {
+            channel: channel.name.to_string(),
+            frames,
+            rest_positions: mesh.vertices.to_vec(),
+            vertex_remap: opts.vertex_remap,
+            prefetch_frames: opts.prefetch_frames,
+            worker,
+            pending: HashSet::new(),
+            loaded: HashMap::new(),
+        })
+    }
+
+    pub fn channel_name(&self) -> &str {
+        &self.channel
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn frame_time(&self, frame: usize) -> f64 {
+        self.frames[frame].time
+    }
+
+    fn to_vertices(&self, points: Vec<Vec3>) -> Vec<Vec3> {
+        if self.vertex_remap.is_empty() { return points }
+        let mut positions = self.rest_positions.clone();
+        for (&vertex, p) in self.vertex_remap.iter().zip(points) {
+            positions[vertex as usize] = p;
+        }
+        positions
+    }
+
+    fn request(&mut self, frame: usize) {
+        if frame >= self.frames.len() || self.loaded.contains_key(&frame) || self.pending.contains(&frame) { return }
+        let worker = match &self.worker { Some(worker) => worker, None => return };
+        if let Some(requests) = &worker.requests {
+            if requests.send((worker.generation(), frame)).is_ok() {
+                self.pending.insert(frame);
+            }
+        }
+    }
+
+    /// Returns the vertex positions stored for `frame`, indexed like `Mesh::vertices`.
+    pub fn frame(&mut self, frame: usize) -> Result<Arc<Vec<Vec3>>, CachePlaybackError> {
+        if frame >= self.frames.len() {
+            return Err(CachePlaybackError::FrameOutOfRange { frame, num_frames: self.frames.len() });
+        }
+
+        // Keep only the frames around the current one
+        let window = frame.saturating_sub(1)..=frame + self.prefetch_frames;
+        self.loaded.retain(|ix, _| window.contains(ix));
+
+        // After a seek the queued reads are for frames that are not needed anymore
+        if let Some(worker) = &self.worker {
+            if self.pending.iter().any(|ix| !window.contains(ix)) {
+                worker.cancel();
+                self.pending.clear();
+            }
+        }
+
+        self.request(frame);
+        for ix in frame + 1..=frame + self.prefetch_frames {
+            self.request(ix);
+        }
+
+        while !self.loaded.contains_key(&frame) {
+            let received = match &self.worker {
+                Some(worker) if self.pending.contains(&frame) => match worker.results.recv() {
+                    Ok((generation, ix, result)) if generation == worker.generation() => Some((ix, result)),
+                    // Read before a seek cancelled it
+                    Ok(_) => continue,
+                    Err(_) => None,
+                },
+                _ => None,
+            };
+            let (ix, result) = match received {
+                Some(received) => received,
+                None => (frame, self.frames[frame].read()),
+            };
+            self.pending.remove(&ix);
+            let positions = match result {
+                Ok(points) => self.to_vertices(points),
+                Err(error) if ix == frame => return Err(error),
+                // Prefetch failures are reported when the frame is requested
+                Err(_) => continue,
+            };
+            if window.contains(&ix) {
+                self.loaded.insert(ix, Arc::new(positions));
+            }
+        }
+        Ok(self.loaded[&frame].clone())
+    }
+
+    /// Returns vertex positions at `time` in seconds, linearly interpolated between frames
+    /// and clamped to the first and last frame.
+    pub fn sample(&mut self, time: f64) -> Result<Vec<Vec3>, CachePlaybackError> {
+        if self.frames.is_empty() { return Ok(self.rest_positions.clone()) }
+        let next = self.frames.partition_point(|f| f.time < time);
+        if next == 0 {
+            return Ok(self.frame(0)?.to_vec());
+        }
+        if next == self.frames.len() {
+            return Ok(self.frame(next - 1)?.to_vec());
+        }
+
+        let (t0, t1) = (self.frames[next - 1].time, self.frames[next].time);
+        let t = if t1 > t0 { (time - t0) / (t1 - t0) } else { 1.0 };
+        let a = self.frame(next - 1)?;
+        let b = self.frame(next)?;
+        Ok(a.iter().zip(b.iter()).map(|(a, b)| Vec3 {
+            x: a.x + (b.x - a.x) * t,
+            y: a.y + (b.y - a.y) * t,
+            z: a.z + (b.z - a.z) * t,
+        }).collect())
+    }
+
+    /// Iterates over all frames as `(time, positions)` pairs.
+    pub fn iter(&mut self) -> CachePlaybackIter<'_> {
+        CachePlaybackIter { playback: self, next: 0 }
+    }
+}
+
+pub struct CachePlaybackIter<'a> {
+    playback: &'a mut CachePlayback,
+    next: usize,
+}
+
+impl Iterator for CachePlaybackIter<'_> {
+    type Item = Result<(f64, Arc<Vec<Vec3>>), CachePlaybackError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.playback.num_frames() { return None }
+        let frame = self.next;
+        self.next += 1;
+        Some(self.playback.frame(frame).map(|positions| (self.playback.frame_time(frame), positions)))
+    }
+}
@@ -0,0 +1,308 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use crate::generated::{Scene, Anim, Mesh, Vec3, EvaluateOpts, Result, evaluate_scene};
+use crate::prelude::Real;
+
+// Maya caches store time in ticks of 1/6000 seconds
+const MC_TICKS_PER_SECOND: Real = 6000.0;
+
+fn check_frames(frames: &[Vec<Vec3>]) -> io::Result<usize> {
+    let count = frames.first().map_or(0, |f| f.len());
+    if frames.iter().any(|f| f.len() != count) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "frames have different vertex counts"));
+    }
+    Ok(count)
+}
+
+/// Evaluates `anim` with skinning and blend shapes at `times` and returns the
+/// per-vertex positions of `mesh`, in world space unless `Mesh::skinned_is_local`.
+#[allow(clippy::result_large_err)]
+pub fn bake_vertex_positions(scene: &Scene, anim: &Anim, mesh: &Mesh, times: &[f64]) -> Result<Vec<Vec<Vec3>>> {
+    let mut frames = Vec::with_capacity(times.len());
+    for &time in times {
+        let opts = EvaluateOpts { evaluate_skinning: true, ..Default::default() };
+        let state = evaluate_scene(scene, anim, time, opts)?;
+        let skinned = &state.meshes[mesh.element.typed_id as usize].skinned_position;
+        let positions = (0..mesh.num_vertices).map(|v| {
+            if skinned.unique_per_vertex {
+                skinned.values[v]
+            } else {
+                skinned.values[skinned.indices[mesh.vertex_first_index[v] as usize] as usize]
+            }
+        }).collect();
+        frames.push(positions);
+    }
+    Ok(frames)
+}
+
+pub struct Pc2Opts {
+    pub start_frame: Real,
+    /// Frames between consecutive samples.
+    pub sample_rate: Real,
+}
+
+impl Default for Pc2Opts {
+    fn default() -> Self {
+        Pc2Opts { start_frame: 0.0, sample_rate: 1.0 }
+    }
+}
+
+/// Writes a 3ds Max point cache, every frame must have the same number of points.
+pub fn write_pc2<W: Write>(writer: &mut W, frames: &[Vec<Vec3>], opts: &Pc2Opts) -> io::Result<()> {
+    let num_points = check_frames(frames)?;
+    let mut data = Vec::with_capacity(32 + frames.len() * num_points * 12);
+    data.extend_from_slice(b"POINTCACHE2\0");
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&(num_points as u32).to_le_bytes());
+    data.extend_from_slice(&(opts.start_frame as f32).to_le_bytes());
+    data.extend_from_slice(&(opts.sample_rate as f32).to_le_bytes());
+    data.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+    for p in frames.iter().flatten() {
+        for c in &[p.x, p.y, p.z] {
+            data.extend_from_slice(&(*c as f32).to_le_bytes());
+        }
+    }
+    writer.write_all(&data)
+}
+
+pub fn write_pc2_file<P: AsRef<Path>>(path: P, frames: &[Vec<Vec3>], opts: &Pc2Opts) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    write_pc2(&mut file, frames, opts)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum McFormat {
+    /// `.mc` files with 32-bit chunk sizes.
+    Mcc,
+    /// `.mcx` files with 64-bit chunk sizes.
+    Mcx,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum McLayout {
+    OneFile,
+    OneFilePerFrame,
+}
+
+pub struct McOpts {
+    pub format: McFormat,
+    pub layout: McLayout,
+    /// Store samples as doubles instead of floats.
+    pub double_precision: bool,
+    pub frames_per_second: Real,
+    pub start_frame: u32,
+    /// Written as `<extra>` tags, readable from `GeometryCache::extra_info`.
+    pub extra_info: Vec<String>,
+}
+
+impl Default for McOpts {
+    fn default() -> Self {
+        McOpts {
+            format: McFormat::Mcc,
+            layout: McLayout::OneFile,
+            double_precision: false,
+            frames_per_second: 24.0,
+            start_frame: 1,
+            extra_info: Vec::new(),
+        }
+    }
+}
+
+pub struct McChannel<'a> {
+    pub name: &'a str,
+    /// Usually "positions", "normals" or "points", see `CacheChannel::interpretation_name`.
+    pub interpretation: &'a str,
+    pub frames: &'a [Vec<Vec3>],
+}
+
+// IFF writer, `wide` chunks (FOR8) have padded tags and 64-bit sizes
+struct IffWriter {
+    data: Vec<u8>,
+    wide: bool,
+}
+
+impl IffWriter {
+    fn alignment(&self) -> usize {
+        if self.wide { 8 } else { 4 }
+    }
+
+    fn tag(&mut self, tag: &[u8; 4]) {
+        self.data.extend_from_slice(tag);
+        if self.wide {
+            self.data.extend_from_slice(&[0; 4]);
+        }
+    }
+
+    fn size(&mut self, size: usize) {
+        if self.wide {
+            self.data.extend_from_slice(&(size as u64).to_be_bytes());
+        } else {
+            self.data.extend_from_slice(&(size as u32).to_be_bytes());
+        }
+    }
+
+    fn chunk(&mut self, tag: &[u8; 4], payload: &[u8]) {
+        self.tag(tag);
+        self.size(payload.len());
+        self.data.extend_from_slice(payload);
+        let padding = (self.alignment() - payload.len() % self.alignment()) % self.alignment();
+        self.data.resize(self.data.len() + padding, 0);
+    }
+
+    // Readers stop at a zero tag, ufbx does not detect the end of the file reliably without one
+    fn finish(mut self) -> Vec<u8> {
+        self.tag(&[0; 4]);
+        self.data
+    }
+
+    fn chunk_u32(&mut self, tag: &[u8; 4], value: u32) {
+        self.chunk(tag, &value.to_be_bytes());
+    }
+
+    fn group(&mut self, kind: &[u8; 4], f: impl FnOnce(&mut IffWriter)) {
+        self.tag(if self.wide { b"FOR8" } else { b"FOR4" });
+        let size_offset = self.data.len();
+        self.size(0);
+        self.data.extend_from_slice(kind);
+        f(self);
+        let size = self.data.len() - size_offset - if self.wide { 8 } else { 4 };
+        if self.wide {
+            self.data[size_offset..size_offset + 8].copy_from_slice(&(size as u64).to_be_bytes());
+        } else {
+            self.data[size_offset..size_offset + 4].copy_from_slice(&(size as u32).to_be_bytes());
+        }
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+struct McWriter<'a, 'b> {
+    channels: &'b [McChannel<'a>],
+    opts: &'b McOpts,
+    ticks_per_frame: u32,
+}
+
+impl McWriter<'_, '_> {
+    fn time(&self, frame: usize) -> u32 {
+        (self.opts.start_frame + frame as u32) * self.ticks_per_frame
+    }
+
+    fn new_file(&self) -> IffWriter {
+        IffWriter { data: Vec::new(), wide: self.opts.format == McFormat::Mcx }
+    }
+
+    fn header(&self, iff: &mut IffWriter, start: u32, end: u32) {
+        iff.group(b"CACH", |iff| {
+            iff.chunk(b"VRSN", b"0.1\0");
+            iff.chunk_u32(b"STIM", start);
+            iff.chunk_u32(b"ETIM", end);
+        });
+    }
+
+    fn frame_data(&self, iff: &mut IffWriter, frame: usize, time: Option<u32>) {
+        iff.group(b"MYCH", |iff| {
+            if let Some(time) = time {
+                iff.chunk_u32(b"TIME", time);
+            }
+            for channel in self.channels {
+                let points = &channel.frames[frame];
+                let mut name = channel.name.as_bytes().to_vec();
+                name.push(0);
+                iff.chunk(b"CHNM", &name);
+                iff.chunk_u32(b"SIZE", points.len() as u32);
+
+                let mut data = Vec::with_capacity(points.len() * 24);
+                for p in points {
+                    for &c in &[p.x, p.y, p.z] {
+                        if self.opts.double_precision {
+                            data.extend_from_slice(&c.to_be_bytes());
+                        } else {
+                            data.extend_from_slice(&(c as f32).to_be_bytes());
+                        }
+                    }
+                }
+                iff.chunk(if self.opts.double_precision { b"DVCA" } else { b"FVCA" }, &data);
+            }
+        });
+    }
+
+    fn xml(&self, num_frames: usize) -> String {
+        let (start, end) = (self.time(0), self.time(num_frames.saturating_sub(1)));
+        let mut xml = String::from("<?xml version=\"1.0\"?>\n<Autodesk_Cache_File>\n");
+        xml += &format!("  <cacheType Type=\"{}\" Format=\"{}\"/>\n",
+            if self.opts.layout == McLayout::OneFile { "OneFile" } else { "OneFilePerFrame" },
+            if self.opts.format == McFormat::Mcc { "mcc" } else { "mcx" });
+        xml += &format!("  <time Range=\"{}-{}\"/>\n", start, end);
+        xml += &format!("  <cacheTimePerFrame TimePerFrame=\"{}\"/>\n", self.ticks_per_frame);
+        xml += "  <cacheVersion Version=\"2.0\"/>\n";
+        for extra in &self.opts.extra_info {
+            xml += &format!("  <extra>{}</extra>\n", xml_escape(extra));
+        }
+        xml += "  <Channels>\n";
+        let channel_type = if self.opts.double_precision { "DoubleVectorArray" } else { "FloatVectorArray" };
+        for (ix, channel) in self.channels.iter().enumerate() {
+            xml += &format!(
+                "    <channel{} ChannelName=\"{}\" ChannelType=\"{}\" ChannelInterpretation=\"{}\" SamplingType=\"Regular\" SamplingRate=\"{}\" StartTime=\"{}\" EndTime=\"{}\"/>\n",
+                ix, xml_escape(channel.name), channel_type, xml_escape(channel.interpretation), self.ticks_per_frame, start, end);
+        }
+        xml += "  </Channels>\n</Autodesk_Cache_File>\n";
+        xml
+    }
+}
+
+/// Writes a Maya geometry cache description to `xml_path` with the data files next to it,
+/// returns the paths of all written files. Channels must have the same number of frames.
+pub fn write_maya_cache<P: AsRef<Path>>(xml_path: P, channels: &[McChannel], opts: &McOpts) -> io::Result<Vec<PathBuf>> {
+    let xml_path = xml_path.as_ref();
+    let num_frames = channels.first().map_or(0, |c| c.frames.len());
+    for channel in channels {
+        check_frames(channel.frames)?;
+        if channel.frames.len() != num_frames {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "channels have different frame counts"));
+        }
+    }
+    if opts.frames_per_second.is_nan() || opts.frames_per_second <= 0.0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "frames_per_second must be positive"));
+    }
+
+    let writer = McWriter {
+        channels,
+        opts,
+        ticks_per_frame: ((MC_TICKS_PER_SECOND / opts.frames_per_second).round() as u32).max(1),
+    };
+    let extension = if opts.format == McFormat::Mcc { "mc" } else { "mcx" };
+    let stem = xml_path.file_stem().and_then(|s| s.to_str()).unwrap_or("cache");
+
+    let mut files: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+    files.push((xml_path.to_path_buf(), writer.xml(num_frames).into_bytes()));
+    match opts.layout {
+        McLayout::OneFile => {
+            let mut iff = writer.new_file();
+            writer.header(&mut iff, writer.time(0), writer.time(num_frames.saturating_sub(1)));
+            for frame in 0..num_frames {
+                writer.frame_data(&mut iff, frame, Some(writer.time(frame)));
+            }
+            files.push((xml_path.with_file_name(format!("{}.{}", stem, extension)), iff.finish()));
+        },
+        McLayout::OneFilePerFrame => {
+            for frame in 0..num_frames {
+                let time = writer.time(frame);
+                let mut iff = writer.new_file();
+                writer.header(&mut iff, time, time);
+                writer.frame_data(&mut iff, frame, None);
+                let name = format!("{}Frame{}.{}", stem, time / writer.ticks_per_frame, extension);
+                files.push((xml_path.with_file_name(name), iff.finish()));
+            }
+        },
+    }
+
+    let mut paths = Vec::with_capacity(files.len());
+    for (path, data) in files {
+        fs::write(&path, data)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
@@ -0,0 +1,156 @@
+use crate::generated::{Camera, Node, Vec3, Vec4, GateFit, ProjectionMode, CoordinateAxis, CoordinateAxes, coordinate_axes_valid, transform_direction};
+use crate::prelude::Real;
+use crate::math::{axis_vector, vec3_cross, vec3_dot, vec3_mul, vec3_normalize, vec3_sub};
+
+/// Column-major 4x4 matrix, `cols[column][row]`.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Matrix4 {
+    pub cols: [[Real; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn identity() -> Matrix4 {
+        let mut cols = [[0.0; 4]; 4];
+        for (ix, col) in cols.iter_mut().enumerate() {
+            col[ix] = 1.0;
+        }
+        Matrix4 { cols }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Real {
+        self.cols[col][row]
+    }
+
+    pub fn mul(&self, rhs: &Matrix4) -> Matrix4 {
+        let mut cols = [[0.0; 4]; 4];
+        for (col, rhs_col) in cols.iter_mut().zip(&rhs.cols) {
+            for (row, value) in col.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.cols[k][row] * rhs_col[k]).sum();
+            }
+        }
+        Matrix4 { cols }
+    }
+
+    /// Transforms `(p, 1)`, returns homogeneous clip coordinates for projection matrices.
+    pub fn transform_point(&self, p: Vec3) -> Vec4 {
+        let v = [p.x, p.y, p.z, 1.0];
+        let row = |r: usize| (0..4).map(|k| self.cols[k][r] * v[k]).sum();
+        Vec4 { x: row(0), y: row(1), z: row(2), w: row(3) }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClipConvention {
+    /// Depth from -1 at the near plane to 1 at the far plane.
+    OpenGl,
+    /// Depth from 0 at the near plane to 1 at the far plane as in Vulkan and Direct3D.
+    /// Vulkan viewports with positive height need Y flipped.
+    ZeroToOne,
+    /// Depth from 1 at the near plane to 0 at the far plane.
+    ReverseZ,
+}
+
+impl Camera {
+    /// Half extents of the view at distance 1 (perspective) or in world units (orthographic)
+    /// after fitting the gate into a viewport of `viewport_aspect` (width / height).
+    /// `GateFit::None` and `GateFit::Stretch` map the gate directly onto the viewport.
+    pub fn viewport_extents(&self, viewport_aspect: Real) -> (Real, Real) {
+        let (x, y) = (self.projection_plane.x, self.projection_plane.y);
+        if viewport_aspect.is_nan() || viewport_aspect <= 0.0 || y <= 0.0 {
+            return (x, y);
+        }
+        let gate_aspect = x / y;
+        let horizontal = match self.gate_fit {
+            GateFit::Horizontal => true,
+            GateFit::Vertical => false,
+            GateFit::Fill => viewport_aspect > gate_aspect,
+            GateFit::Overscan => viewport_aspect < gate_aspect,
+            GateFit::None | GateFit::Stretch => return (x, y),
+        };
+        if horizontal {
+            (x, x / viewport_aspect)
+        } else {
+            (y * viewport_aspect, y)
+        }
+    }
+
+    /// Offset of the view center at distance 1 from the "FilmOffsetX/Y" properties.
+    pub fn film_offset(&self) -> (Real, Real) {
+        if self.projection_mode != ProjectionMode::Perspective || self.focal_length_mm <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let inch = |name: &str| self.element.props.find_prop(name).map_or(0.0, |p| p.value_vec4.x);
+        let scale = 25.4 / self.focal_length_mm;
+        (inch("FilmOffsetX") * scale, inch("FilmOffsetY") * scale)
+    }
+
+    /// Projection from view space (see `view_matrix()`) to clip space.
+    /// Perspective cameras without a far plane use an infinite projection.
+    pub fn projection_matrix(&self, viewport_aspect: Real, convention: ClipConvention) -> Matrix4 {
+        let (half_x, half_y) = self.viewport_extents(viewport_aspect);
+        let (offset_x, offset_y) = self.film_offset();
+        let (n, f) = (self.near_plane, self.far_plane);
+
+        let mut m = Matrix4::default();
+        match self.projection_mode {
+            ProjectionMode::Perspective => {
+                m.cols[0][0] = 1.0 / half_x;
+                m.cols[1][1] = 1.0 / half_y;
+                m.cols[2][0] = offset_x / half_x;
+                m.cols[2][1] = offset_y / half_y;
+                m.cols[2][3] = -1.0;
+                let infinite = f.is_nan() || f <= n || f.is_infinite();
+                let (zz, zw) = match (convention, infinite) {
+                    (ClipConvention::OpenGl, false) => ((f + n) / (n - f), 2.0 * f * n / (n - f)),
+                    (ClipConvention::OpenGl, true) => (-1.0, -2.0 * n),
+                    (ClipConvention::ZeroToOne, false) => (f / (n - f), f * n / (n - f)),
+                    (ClipConvention::ZeroToOne, true) => (-1.0, -n),
+                    (ClipConvention::ReverseZ, false) => (n / (f - n), f * n / (f - n)),
+                    (ClipConvention::ReverseZ, true) => (0.0, n),
+                };
+                m.cols[2][2] = zz;
+                m.cols[3][2] = zw;
+            },
+            ProjectionMode::Orthographic => {
+                m.cols[0][0] = 1.0 / half_x;
+                m.cols[1][1] = 1.0 / half_y;
+                m.cols[3][3] = 1.0;
+                let depth = f - n;
+                let (zz, zw) = match convention {
+                    ClipConvention::OpenGl => (-2.0 / depth, -(f + n) / depth),
+                    ClipConvention::ZeroToOne => (-1.0 / depth, -n / depth),
+                    ClipConvention::ReverseZ => (1.0 / depth, f / depth),
+                };
+                m.cols[2][2] = zz;
+                m.cols[3][2] = zw;
+            },
+        }
+        m
+    }
+
+    /// World to view transform for the camera attached to `node`. The view space is
+    /// right-handed with +X right, +Y up and the camera looking towards -Z.
+    pub fn view_matrix(&self, node: &Node) -> Matrix4 {
+        // `projection_axes` is only filled in when converting with `LoadOpts::target_camera_axes`
+        let axes = if coordinate_axes_valid(self.projection_axes) {
+            self.projection_axes
+        } else {
+            CoordinateAxes { right: CoordinateAxis::PositiveZ, up: CoordinateAxis::PositiveY, front: CoordinateAxis::NegativeX }
+        };
+        let world = &node.node_to_world;
+        let front = vec3_normalize(transform_direction(world, axis_vector(axes.front)));
+        let up = transform_direction(world, axis_vector(axes.up));
+        let up = vec3_normalize(vec3_sub(up, vec3_mul(front, vec3_dot(up, front))));
+        let right = vec3_cross(up, front);
+        let eye = Vec3 { x: world.m03, y: world.m13, z: world.m23 };
+
+        let mut m = Matrix4::identity();
+        for (row, axis) in [right, up, front].iter().enumerate() {
+            m.cols[0][row] = axis.x;
+            m.cols[1][row] = axis.y;
+            m.cols[2][row] = axis.z;
+            m.cols[3][row] = -vec3_dot(*axis, eye);
+        }
+        m
+    }
+}
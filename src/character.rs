@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+use crate::generated::{Scene, Character, Element, Node, DomNode, Transform, Matrix, Vec3, RotationOrder};
+use crate::generated::{as_node, as_unknown, euler_to_quat, get_bone_pose, matrix_mul, quat_mul, transform_to_matrix};
+
+macro_rules! humanoid_slots {
+    ($($slot:ident,)*) => {
+        /// HumanIK template slot, named like the `<Slot>Link` properties of the character.
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+        pub enum HumanoidSlot {
+            $($slot,)*
+        }
+
+        impl HumanoidSlot {
+            pub const ALL: &'static [HumanoidSlot] = &[$(HumanoidSlot::$slot,)*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(HumanoidSlot::$slot => stringify!($slot),)*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<HumanoidSlot> {
+                match name {
+                    $(stringify!($slot) => Some(HumanoidSlot::$slot),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+humanoid_slots! {
+    Reference, Hips, HipsTranslation,
+    LeftUpLeg, LeftLeg, LeftFoot, LeftToeBase,
+    RightUpLeg, RightLeg, RightFoot, RightToeBase,
+    Spine, Spine1, Spine2, Spine3, Spine4, Spine5, Spine6, Spine7, Spine8, Spine9,
+    Neck, Neck1, Neck2, Neck3, Neck4, Neck5, Neck6, Neck7, Neck8, Neck9, Head,
+    LeftShoulder, LeftShoulderExtra, LeftArm, LeftForeArm, LeftHand, LeftFingerBase,
+    RightShoulder, RightShoulderExtra, RightArm, RightForeArm, RightHand, RightFingerBase,
+    LeftUpLegRoll, LeftLegRoll, RightUpLegRoll, RightLegRoll,
+    LeftArmRoll, LeftForeArmRoll, RightArmRoll, RightForeArmRoll,
+    LeftHandThumb1, LeftHandThumb2, LeftHandThumb3, LeftHandThumb4,
+    LeftHandIndex1, LeftHandIndex2, LeftHandIndex3, LeftHandIndex4,
+    LeftHandMiddle1, LeftHandMiddle2, LeftHandMiddle3, LeftHandMiddle4,
+    LeftHandRing1, LeftHandRing2, LeftHandRing3, LeftHandRing4,
+    LeftHandPinky1, LeftHandPinky2, LeftHandPinky3, LeftHandPinky4,
+    LeftHandExtraFinger1, LeftHandExtraFinger2, LeftHandExtraFinger3, LeftHandExtraFinger4,
+    RightHandThumb1, RightHandThumb2, RightHandThumb3, RightHandThumb4,
+    RightHandIndex1, RightHandIndex2, RightHandIndex3, RightHandIndex4,
+    RightHandMiddle1, RightHandMiddle2, RightHandMiddle3, RightHandMiddle4,
+    RightHandRing1, RightHandRing2, RightHandRing3, RightHandRing4,
+    RightHandPinky1, RightHandPinky2, RightHandPinky3, RightHandPinky4,
+    RightHandExtraFinger1, RightHandExtraFinger2, RightHandExtraFinger3, RightHandExtraFinger4,
+    LeftInHandThumb, LeftInHandIndex, LeftInHandMiddle, LeftInHandRing, LeftInHandPinky, LeftInHandExtraFinger,
+    RightInHandThumb, RightInHandIndex, RightInHandMiddle, RightInHandRing, RightInHandPinky, RightInHandExtraFinger,
+    LeftFootThumb1, LeftFootThumb2, LeftFootThumb3, LeftFootThumb4,
+    LeftFootIndex1, LeftFootIndex2, LeftFootIndex3, LeftFootIndex4,
+    LeftFootMiddle1, LeftFootMiddle2, LeftFootMiddle3, LeftFootMiddle4,
+    LeftFootRing1, LeftFootRing2, LeftFootRing3, LeftFootRing4,
+    LeftFootPinky1, LeftFootPinky2, LeftFootPinky3, LeftFootPinky4,
+    LeftFootExtraFinger1, LeftFootExtraFinger2, LeftFootExtraFinger3, LeftFootExtraFinger4,
+    RightFootThumb1, RightFootThumb2, RightFootThumb3, RightFootThumb4,
+    RightFootIndex1, RightFootIndex2, RightFootIndex3, RightFootIndex4,
+    RightFootMiddle1, RightFootMiddle2, RightFootMiddle3, RightFootMiddle4,
+    RightFootRing1, RightFootRing2, RightFootRing3, RightFootRing4,
+    RightFootPinky1, RightFootPinky2, RightFootPinky3, RightFootPinky4,
+    RightFootExtraFinger1, RightFootExtraFinger2, RightFootExtraFinger3, RightFootExtraFinger4,
+    LeftInFootThumb, LeftInFootIndex, LeftInFootMiddle, LeftInFootRing, LeftInFootPinky, LeftInFootExtraFinger,
+    RightInFootThumb, RightInFootIndex, RightInFootMiddle, RightInFootRing, RightInFootPinky, RightInFootExtraFinger,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TPoseSource {
+    /// Stance of the `CharacterPose` of the character, requires `LoadOpts::retain_dom`.
+    CharacterPose,
+    /// `bone_to_world` of the bind pose containing the node.
+    BindPose,
+    /// `node_to_world` of the scene as loaded.
+    Scene,
+}
+
+#[derive(Clone, Copy)]
+pub struct CharacterLink<'a> {
+    pub slot: HumanoidSlot,
+    pub node: &'a Node,
+    /// Characterization offset of the slot, requires `LoadOpts::retain_dom`.
+    pub offset: Transform,
+    /// World transform of the node in the characterization stance. Nodes missing from the
+    /// stance pose fall back to their bind pose or the scene as loaded, see `tpose_source`.
+    pub tpose: Matrix,
+    pub tpose_source: TPoseSource,
+}
+
+pub struct CharacterDefinition<'a> {
+    pub character: &'a Character,
+    /// The character has been characterized, always `false` without `LoadOpts::retain_dom`.
+    pub characterized: bool,
+    /// Linked slots in `HumanoidSlot::ALL` order.
+    pub links: Vec<CharacterLink<'a>>,
+}
+
+impl<'a> CharacterDefinition<'a> {
+    pub fn link(&self, slot: HumanoidSlot) -> Option<&CharacterLink<'a>> {
+        self.links.iter().find(|l| l.slot == slot)
+    }
+
+    pub fn node(&self, slot: HumanoidSlot) -> Option<&'a Node> {
+        self.link(slot).map(|l| l.node)
+    }
+
+    pub fn tpose(&self, slot: HumanoidSlot) -> Option<Matrix> {
+        self.link(slot).map(|l| l.tpose)
+    }
+
+    pub fn slot_of(&self, node: &Node) -> Option<HumanoidSlot> {
+        self.links.iter().find(|l| l.node.element.element_id == node.element.element_id).map(|l| l.slot)
+    }
+}
+
+fn dom_vec3(link: &DomNode, prefix: &str, default: f64) -> Vec3 {
+    let get = |axis: &str| {
+        link.find(&format!("{}{}", prefix, axis))
+            .and_then(|n| n.values.iter().next().map(|v| v.value_float))
+            .unwrap_or(default)
+    };
+    Vec3 { x: get("X"), y: get("Y"), z: get("Z") }
+}
+
+// `LINK: "<Slot>"` nodes are grouped under `BASE`, `SPINE`, `LEFTHAND` etc.
+fn find_dom_link(dom: &DomNode, slot: HumanoidSlot) -> Option<&DomNode> {
+    dom.children.iter().flat_map(|group| group.children.iter()).map(|n| &**n)
+        .find(|n| n.name == "LINK" && n.values.iter().next().is_some_and(|v| v.value_str == slot.name()))
+}
+
+fn link_offset(link: &DomNode) -> Transform {
+    Transform {
+        translation: dom_vec3(link, "TOFFSET", 0.0),
+        rotation: euler_to_quat(dom_vec3(link, "ROFFSET", 0.0), RotationOrder::Xyz),
+        scale: dom_vec3(link, "SOFFSET", 1.0),
+    }
+}
+
+fn dom_prop<'a>(props: Option<&'a DomNode>, name: &str) -> Option<&'a DomNode> {
+    props?.children.iter().map(|n| &**n)
+        .find(|n| n.name == "P" && n.values.iter().next().is_some_and(|v| v.value_str == name))
+}
+
+fn dom_prop_vec3(props: Option<&DomNode>, name: &str, default: f64) -> Vec3 {
+    match dom_prop(props, name).filter(|p| p.values.len() >= 7) {
+        Some(p) => Vec3 { x: p.values[4].value_float, y: p.values[5].value_float, z: p.values[6].value_float },
+        None => Vec3 { x: default, y: default, z: default },
+    }
+}
+
+fn rotation_order(value: i64) -> RotationOrder {
+    match value {
+        1 => RotationOrder::Xzy,
+        2 => RotationOrder::Yzx,
+        3 => RotationOrder::Yxz,
+        4 => RotationOrder::Zxy,
+        5 => RotationOrder::Zyx,
+        _ => RotationOrder::Xyz,
+    }
+}
+
+// Object name without the `Class::` prefix of ASCII files or the `\0\x01Class` suffix of binary ones
+fn object_name(name: &str) -> &str {
+    match name.find("\0\x01") {
+        Some(end) => &name[..end],
+        None => name.split_once("::").map_or(name, |(_, name)| name),
+    }
+}
+
+// World transforms of the models in the `PoseScene` of a `CharacterPose` by name.
+// Only translation, pre-rotation, rotation and scaling are applied, pivots and offsets are ignored.
+fn stance_transforms(pose: &DomNode, root: &Matrix) -> HashMap<String, Matrix> {
+    let pose_scene = match pose.find("PoseScene") {
+        Some(pose_scene) => pose_scene,
+        None => return HashMap::new(),
+    };
+
+    let mut models: HashMap<i64, (&str, Matrix)> = HashMap::new();
+    let objects = pose_scene.find("Objects").into_iter().flat_map(|n| n.children.iter()).map(|n| &**n);
+    for model in objects.filter(|n| n.name == "Model") {
+        let (id, name) = match (model.values.first(), model.values.get(1)) {
+            (Some(id), Some(name)) => (id.value_int, object_name(&name.value_str)),
+            _ => continue,
+        };
+        let props = model.find("Properties70");
+        let order = dom_prop(props, "RotationOrder").and_then(|p| p.values.get(4)).map_or(0, |v| v.value_int);
+        let rotation = quat_mul(
+            euler_to_quat(dom_prop_vec3(props, "PreRotation", 0.0), RotationOrder::Xyz),
+            euler_to_quat(dom_prop_vec3(props, "Lcl Rotation", 0.0), rotation_order(order)));
+        let local = Transform {
+            translation: dom_prop_vec3(props, "Lcl Translation", 0.0),
+            rotation,
+            scale: dom_prop_vec3(props, "Lcl Scaling", 1.0),
+        };
+        models.insert(id, (name, transform_to_matrix(&local)));
+    }
+
+    let mut parents: HashMap<i64, i64> = HashMap::new();
+    let connections = pose_scene.find("Connections").into_iter().flat_map(|n| n.children.iter()).map(|n| &**n);
+    for conn in connections.filter(|n| n.name == "C") {
+        if let (Some(child), Some(parent)) = (conn.values.get(1), conn.values.get(2)) {
+            parents.insert(child.value_int, parent.value_int);
+        }
+    }
+
+    models.iter().map(|(&id, &(name, local))| {
+        let mut world = local;
+        let mut current = id;
+        // Bounded in case the connections loop
+        for _ in 0..models.len() {
+            match parents.get(&current).and_then(|p| Some((*p, models.get(p)?))) {
+                Some((parent, &(_, parent_local))) => {
+                    world = matrix_mul(&parent_local, &world);
+                    current = parent;
+                },
+                None => break,
+            }
+        }
+        (name.to_string(), matrix_mul(root, &world))
+    }).collect()
+}
+
+fn character_pose(element: &Element) -> Option<&DomNode> {
+    as_unknown(element).filter(|u| u.type_ == "CharacterPose")?.element.dom_node.as_deref()
+}
+
+fn scene_root(mut node: &Node) -> &Node {
+    while let Some(parent) = node.parent.as_deref() {
+        node = parent;
+    }
+    node
+}
+
+// World transform from the bind pose of the node, or the scene as loaded
+pub(crate) fn rest_pose(node: &Node) -> (Matrix, TPoseSource) {
+    match node.bind_pose.as_ref().and_then(|pose| get_bone_pose(pose, node)) {
+        Some(bp) => (bp.bone_to_world, TPoseSource::BindPose),
+        None => (node.node_to_world, TPoseSource::Scene),
+    }
+}
+
+impl Character {
+    /// Resolves the HumanIK slots linked to nodes via `<Slot>Link` connections.
+    /// The T-poses are read from a `CharacterPose` connected to the character,
+    /// `character_definitions()` also finds poses named `<character>_Pose`.
+    pub fn definition(&self) -> CharacterDefinition<'_> {
+        self.definition_with_pose(self.stance_pose())
+    }
+
+    fn stance_pose(&self) -> Option<&DomNode> {
+        let dst = self.element.connections_dst.iter().map(|c| &*c.src);
+        let src = self.element.connections_src.iter().map(|c| &*c.dst);
+        dst.chain(src).find_map(character_pose)
+    }
+
+    fn definition_with_pose<'a>(&'a self, pose: Option<&DomNode>) -> CharacterDefinition<'a> {
+        let dom = self.element.dom_node.as_deref();
+        let characterized = dom.and_then(|d| d.find("CHARACTERIZE"))
+            .and_then(|n| n.values.iter().next())
+            .is_some_and(|v| v.value_int != 0);
+
+        let mut stance: Option<HashMap<String, Matrix>> = None;
+        let mut links: Vec<CharacterLink> = Vec::new();
+        for conn in self.element.connections_dst.iter() {
+            let slot = match conn.dst_prop.strip_suffix("Link").and_then(HumanoidSlot::from_name) {
+                Some(slot) => slot,
+                None => continue,
+            };
+            let node = match as_node(&conn.src) {
+                Some(node) => node,
+                None => continue,
+            };
+            if links.iter().any(|l| l.slot == slot) { continue }
+
+            let offset = dom.and_then(|d| find_dom_link(d, slot))
+                .map_or_else(Transform::identity, link_offset);
+            let stance_tpose = pose.and_then(|pose| {
+                let stance = stance.get_or_insert_with(|| stance_transforms(pose, &scene_root(node).node_to_world));
+                stance.get(&*node.element.name).copied()
+            });
+            let (tpose, tpose_source) = match stance_tpose {
+                Some(tpose) => (tpose, TPoseSource::CharacterPose),
+                None => rest_pose(node),
+            };
+            links.push(CharacterLink { slot, node, offset, tpose, tpose_source });
+        }
+        links.sort_by_key(|l| l.slot as usize);
+
+        CharacterDefinition { character: self, characterized, links }
+    }
+}
+
+/// Definitions of every character in the scene, indexed like `scene.characters[]`.
+pub fn character_definitions(scene: &Scene) -> Vec<CharacterDefinition<'_>> {
+    scene.characters.iter().map(|c| {
+        let pose = c.stance_pose().or_else(|| {
+            let name = format!("{}_Pose", c.element.name);
+            scene.unknowns.iter().filter(|u| u.element.name == name.as_str()).find_map(|u| character_pose(&u.element))
+        });
+        c.definition_with_pose(pose)
+    }).collect()
+}
@@ -0,0 +1,422 @@
+use crate::generated::{
+    Scene, Anim, Node, Constraint, ConstraintType, ConstraintAimUpType, BakedAnim, Transform,
+    Matrix, Vec3, Quat, InheritMode, EvaluateOpts, Result, evaluate_scene, matrix_mul, matrix_invert, matrix_determinant,
+    transform_to_matrix, matrix_to_transform, quat_mul, quat_normalize, quat_slerp, quat_fix_antipodal,
+    quat_rotate_vec3, quat_to_euler, euler_to_quat,
+};
+use crate::prelude::Real;
+use crate::bake_keys::{bake_node_keys, BakedNodeKeys};
+use crate::math::{
+    axis_vector, vec3_add, vec3_sub, vec3_mul, vec3_dot, vec3_cross, vec3_length, vec3_normalize,
+    quat_from_to,
+};
+
+const Y_UP: Vec3 = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+
+pub struct ConstraintOpts {
+    /// Iterations for IK chains longer than two bones.
+    pub ik_iterations: usize,
+}
+
+impl Default for ConstraintOpts {
+    fn default() -> Self {
+        ConstraintOpts { ik_iterations: 16 }
+    }
+}
+
+// Per-axis translation, rotation and scale masks
+struct Masks {
+    translation: [bool; 3],
+    rotation: [bool; 3],
+    scale: [bool; 3],
+}
+
+fn lerp(a: Vec3, b: Vec3, t: Real) -> Vec3 {
+    vec3_add(a, vec3_mul(vec3_sub(b, a), t))
+}
+
+fn vec3_scale(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3 { x: a.x * b.x, y: a.y * b.y, z: a.z * b.z }
+}
+
+fn select(mask: [bool; 3], original: Vec3, solved: Vec3) -> Vec3 {
+    Vec3 {
+        x: if mask[0] { solved.x } else { original.x },
+        y: if mask[1] { solved.y } else { original.y },
+        z: if mask[2] { solved.z } else { original.z },
+    }
+}
+
+fn world_position(m: &Matrix) -> Vec3 {
+    Vec3 { x: m.m03, y: m.m13, z: m.m23 }
+}
+
+// Weighted average of transforms, rotations are normalized-lerped
+fn average(items: &[(Transform, Real)]) -> Option<Transform> {
+    let total: Real = items.iter().map(|&(_, w)| w).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let reference = items[0].0.rotation;
+    let mut result = Transform { translation: Vec3::default(), rotation: Quat::default(), scale: Vec3::default() };
+    for &(t, w) in items {
+        let w = w / total;
+        let q = quat_fix_antipodal(t.rotation, reference);
+        result.translation = vec3_add(result.translation, vec3_mul(t.translation, w));
+        result.scale = vec3_add(result.scale, vec3_mul(t.scale, w));
+        result.rotation = Quat { x: result.rotation.x + q.x * w, y: result.rotation.y + q.y * w, z: result.rotation.z + q.z * w, w: result.rotation.w + q.w * w };
+    }
+    result.rotation = quat_normalize(result.rotation);
+    Some(result)
+}
+
+// Orthonormal basis with `x` along `aim` and `y` towards `up`
+fn aim_basis(aim: Vec3, up: Vec3) -> Option<[Vec3; 3]> {
+    let x = vec3_normalize(aim);
+    let z = vec3_cross(x, up);
+    if vec3_length(z) < 1e-9 {
+        return None;
+    }
+    let z = vec3_normalize(z);
+    Some([x, vec3_cross(z, x), z])
+}
+
+// Rotation taking the local aim/up frame to the world aim/up frame
+fn frame_rotation(local: [Vec3; 3], world: [Vec3; 3]) -> Quat {
+    let column = |j: usize| {
+        let axis = |v: Vec3| [v.x, v.y, v.z][j];
+        (0..3).fold(Vec3::default(), |sum, k| vec3_add(sum, vec3_mul(world[k], axis(local[k]))))
+    };
+    let (c0, c1, c2) = (column(0), column(1), column(2));
+    let m = Matrix {
+        m00: c0.x, m10: c0.y, m20: c0.z,
+        m01: c1.x, m11: c1.y, m21: c1.z,
+        m02: c2.x, m12: c2.y, m22: c2.z,
+        m03: 0.0, m13: 0.0, m23: 0.0,
+    };
+    matrix_to_transform(&m).rotation
+}
+
+fn perpendicular(v: Vec3) -> Vec3 {
+    let other = if v.x.abs() < 0.9 { Vec3 { x: 1.0, y: 0.0, z: 0.0 } } else { Y_UP };
+    vec3_normalize(vec3_cross(v, other))
+}
+
+/// Node transforms that constraints are solved on.
+#[derive(Clone)]
+pub struct ConstraintPose {
+    /// Indexed like `scene.nodes[]`.
+    pub local_transforms: Vec<Transform>,
+    pub node_to_world: Vec<Matrix>,
+    // Like the `Node` fields, needed for nodes that don't use `InheritMode::Normal`
+    unscaled_node_to_world: Vec<Matrix>,
+    inherit_scale: Vec<Vec3>,
+}
+
+fn unscaled(transform: &Transform) -> Transform {
+    Transform { scale: Vec3 { x: 1.0, y: 1.0, z: 1.0 }, ..*transform }
+}
+
+// Componentwise `a / b`, keeping `fallback` where `b` is zero
+fn vec3_div_or(a: Vec3, b: Vec3, fallback: Vec3) -> Vec3 {
+    let div = |a: Real, b: Real, fallback: Real| if b != 0.0 { a / b } else { fallback };
+    Vec3 { x: div(a.x, b.x, fallback.x), y: div(a.y, b.y, fallback.y), z: div(a.z, b.z, fallback.z) }
+}
+
+impl ConstraintPose {
+    /// Pose of the current node transforms, use the scene returned by `evaluate_scene()` for animation.
+    pub fn from_scene(scene: &Scene) -> ConstraintPose {
+        ConstraintPose {
+            local_transforms: scene.nodes.iter().map(|n| n.local_transform).collect(),
+            node_to_world: scene.nodes.iter().map(|n| n.node_to_world).collect(),
+            unscaled_node_to_world: scene.nodes.iter().map(|n| n.unscaled_node_to_world).collect(),
+            inherit_scale: scene.nodes.iter().map(|n| n.inherit_scale).collect(),
+        }
+    }
+
+    // Parent matrix that the local transform of `node` is applied to and the scale
+    // applied to its local translation and scale, following `InheritMode`
+    fn parent_space(&self, node: &Node) -> (Matrix, Vec3, Vec3) {
+        let one = Vec3 { x: 1.0, y: 1.0, z: 1.0 };
+        match node.parent.as_ref().map(|p| p.element.typed_id as usize) {
+            Some(parent) if node.inherit_mode != InheritMode::Normal => {
+                let scale = node.inherit_scale_node.as_ref().map_or(one, |n| self.inherit_scale[n.element.typed_id as usize]);
+                (self.unscaled_node_to_world[parent], self.inherit_scale[parent], scale)
+            },
+            Some(parent) => (self.node_to_world[parent], one, one),
+            None => (transform_to_matrix(&Transform::identity()), one, one),
+        }
+    }
+
+    pub(crate) fn world_transform(&self, node: &Node) -> Transform {
+        matrix_to_transform(&self.node_to_world[node.element.typed_id as usize])
+    }
+
+    /// Recomputes `node_to_world` of `node` and its descendants from `local_transforms`.
+    pub fn update_world(&mut self, node: &Node) {
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            let ix = node.element.typed_id as usize;
+            let (parent, translation_scale, scale) = self.parent_space(node);
+            let mut local = self.local_transforms[ix];
+            local.translation = vec3_scale(local.translation, translation_scale);
+            local.scale = vec3_scale(local.scale, scale);
+            self.node_to_world[ix] = matrix_mul(&parent, &transform_to_matrix(&local));
+            self.unscaled_node_to_world[ix] = matrix_mul(&parent, &transform_to_matrix(&unscaled(&local)));
+            self.inherit_scale[ix] = local.scale;
+            stack.extend(&node.children);
+        }
+    }
+
+    pub(crate) fn world_to_local(&self, node: &Node, world: &Transform) -> Transform {
+        let (parent, translation_scale, scale) = self.parent_space(node);
+        let current = &self.local_transforms[node.element.typed_id as usize];
+        if matrix_determinant(&parent) == 0.0 {
+            // Nothing under a zero scaled parent can be moved in world space
+            return *current;
+        }
+        let local = matrix_to_transform(&matrix_mul(&matrix_invert(&parent), &transform_to_matrix(world)));
+        Transform {
+            translation: vec3_div_or(local.translation, translation_scale, current.translation),
+            rotation: local.rotation,
+            scale: vec3_div_or(local.scale, scale, current.scale),
+        }
+    }
+
+    // Applies the offset, masks and weight to a solved world transform
+    fn apply(&mut self, node: &Node, world: &Transform, masks: &Masks, weight: Real, offset: &Transform) {
+        let ix = node.element.typed_id as usize;
+        let mut solved = self.world_to_local(node, world);
+        solved.translation = vec3_add(solved.translation, offset.translation);
+        solved.rotation = quat_mul(solved.rotation, offset.rotation);
+        solved.scale = vec3_scale(solved.scale, offset.scale);
+
+        let original = self.local_transforms[ix];
+        let rotation = match masks.rotation {
+            [true, true, true] => solved.rotation,
+            [false, false, false] => original.rotation,
+            mask => {
+                let a = quat_to_euler(original.rotation, node.rotation_order);
+                let b = quat_to_euler(solved.rotation, node.rotation_order);
+                euler_to_quat(select(mask, a, b), node.rotation_order)
+            },
+        };
+        let rotation = quat_fix_antipodal(rotation, original.rotation);
+        self.local_transforms[ix] = Transform {
+            translation: lerp(original.translation, select(masks.translation, original.translation, solved.translation), weight),
+            rotation: quat_slerp(original.rotation, rotation, weight),
+            scale: lerp(original.scale, select(masks.scale, original.scale, solved.scale), weight),
+        };
+        self.update_world(node);
+    }
+
+    fn targets(&self, constraint: &Constraint, offsets: bool) -> Vec<(Transform, Real)> {
+        constraint.targets.iter().map(|target| {
+            let world = &self.node_to_world[target.node.element.typed_id as usize];
+            let world = if offsets { matrix_mul(world, &transform_to_matrix(&target.transform)) } else { *world };
+            (matrix_to_transform(&world), target.weight)
+        }).collect()
+    }
+
+    fn aim_rotation(&self, scene: &Scene, constraint: &Constraint, current: &Transform, target: Vec3) -> Option<Quat> {
+        let dir = vec3_sub(target, current.translation);
+        if vec3_length(dir) < 1e-12 {
+            return None;
+        }
+        let aim = if vec3_length(constraint.aim_vector) > 0.0 { constraint.aim_vector } else { Vec3 { x: 1.0, y: 0.0, z: 0.0 } };
+        let world_up_vector = constraint.element.props.find_prop("WorldUpVector")
+            .map_or(Y_UP, |p| Vec3 { x: p.value_vec4.x, y: p.value_vec4.y, z: p.value_vec4.z });
+        let up_node = constraint.aim_up_node.as_ref().map(|n| self.world_transform(n));
+        let world_up = match (constraint.aim_up_type, up_node) {
+            (ConstraintAimUpType::Scene, _) => {
+                let up = axis_vector(scene.settings.axes.up);
+                Some(if vec3_length(up) > 0.0 { up } else { Y_UP })
+            },
+            (ConstraintAimUpType::ToNode, Some(up)) => Some(vec3_sub(up.translation, current.translation)),
+            (ConstraintAimUpType::AlignNode, Some(up)) => Some(quat_rotate_vec3(up.rotation, world_up_vector)),
+            (ConstraintAimUpType::Vector, _) => Some(world_up_vector),
+            _ => None,
+        };
+
+        let frames = world_up.and_then(|up| Some((aim_basis(aim, constraint.aim_up_vector)?, aim_basis(dir, up)?)));
+        Some(match frames {
+            Some((local, world)) => frame_rotation(local, world),
+            // Without an up vector rotate the aim axis with the shortest arc
+            None => quat_mul(quat_from_to(quat_rotate_vec3(current.rotation, aim), dir), current.rotation),
+        })
+    }
+
+    // Rotates `node` in world space keeping its local translation and scale
+    fn rotate_world(&mut self, node: &Node, rotation: Quat) {
+        let ix = node.element.typed_id as usize;
+        let mut world = self.world_transform(node);
+        world.rotation = quat_mul(rotation, world.rotation);
+        self.local_transforms[ix].rotation = self.world_to_local(node, &world).rotation;
+        self.update_world(node);
+    }
+
+    fn position(&self, node: &Node) -> Vec3 {
+        world_position(&self.node_to_world[node.element.typed_id as usize])
+    }
+
+    fn solve_ik(&mut self, constraint: &Constraint, chain: &[&Node], opts: &ConstraintOpts) {
+        let (root, end) = (chain[0], chain[chain.len() - 1]);
+        let goal = match constraint.ik_effector.as_ref() {
+            Some(effector) => self.position(effector),
+            None => return,
+        };
+        let original: Vec<Transform> = chain.iter().map(|n| self.local_transforms[n.element.typed_id as usize]).collect();
+
+        if chain.len() == 3 {
+            let mid = chain[1];
+            let (a, b, c) = (self.position(root), self.position(mid), self.position(end));
+            let (l1, l2) = (vec3_length(vec3_sub(b, a)), vec3_length(vec3_sub(c, b)));
+            let to_goal = vec3_sub(goal, a);
+            let length = vec3_length(to_goal);
+            if l1 < 1e-12 || l2 < 1e-12 || length < 1e-12 {
+                return;
+            }
+            let dir = vec3_mul(to_goal, 1.0 / length);
+            let d = length.clamp((l1 - l2).abs(), l1 + l2);
+
+            // Bend towards the pole targets, the pole vector or the current bend direction
+            let pole = match average(&self.targets(constraint, false)) {
+                Some(t) => vec3_sub(t.translation, a),
+                None if vec3_length(constraint.ik_pole_vector) > 0.0 => constraint.ik_pole_vector,
+                None => vec3_sub(b, a),
+            };
+            let reject = |v: Vec3| vec3_sub(v, vec3_mul(dir, vec3_dot(v, dir)));
+            let mut bend = reject(pole);
+            if vec3_length(bend) < 1e-9 {
+                bend = reject(vec3_sub(b, a));
+            }
+            let bend = if vec3_length(bend) < 1e-9 { perpendicular(dir) } else { vec3_normalize(bend) };
+
+            let cos_a = ((l1 * l1 + d * d - l2 * l2) / (2.0 * l1 * d)).clamp(-1.0, 1.0);
+            let sin_a = (1.0 - cos_a * cos_a).sqrt();
+            let mid_target = vec3_add(a, vec3_add(vec3_mul(dir, l1 * cos_a), vec3_mul(bend, l1 * sin_a)));
+            self.rotate_world(root, quat_from_to(vec3_sub(b, a), vec3_sub(mid_target, a)));
+
+            let b = self.position(mid);
+            let c = self.position(end);
+            let end_target = vec3_add(a, vec3_mul(dir, d));
+            self.rotate_world(mid, quat_from_to(vec3_sub(c, b), vec3_sub(end_target, b)));
+        } else {
+            // Cyclic coordinate descent for longer chains
+            for _ in 0..opts.ik_iterations {
+                for &joint in chain[..chain.len() - 1].iter().rev() {
+                    let p = self.position(joint);
+                    let (to_end, to_goal) = (vec3_sub(self.position(end), p), vec3_sub(goal, p));
+                    if vec3_length(to_end) > 1e-12 && vec3_length(to_goal) > 1e-12 {
+                        self.rotate_world(joint, quat_from_to(to_end, to_goal));
+                    }
+                }
+                if vec3_length(vec3_sub(self.position(end), goal)) < 1e-9 {
+                    break;
+                }
+            }
+        }
+
+        for (node, original) in chain.iter().zip(&original) {
+            let local = &mut self.local_transforms[node.element.typed_id as usize];
+            local.rotation = quat_slerp(original.rotation, quat_fix_antipodal(local.rotation, original.rotation), constraint.weight);
+        }
+        self.update_world(root);
+    }
+
+    /// Solves a single constraint, ignoring `Constraint::active`.
+    pub fn solve_constraint(&mut self, scene: &Scene, constraint: &Constraint, opts: &ConstraintOpts) {
+        let node = match constraint.node.as_ref() {
+            Some(node) => node,
+            None => return,
+        };
+        let current = self.world_transform(node);
+        let masks = Masks {
+            translation: constraint.constrain_translation,
+            rotation: constraint.constrain_rotation,
+            scale: constraint.constrain_scale,
+        };
+        let (weight, offset) = (constraint.weight, &constraint.transform_offset);
+
+        match constraint.type_ {
+            ConstraintType::Position | ConstraintType::Rotation | ConstraintType::Scale | ConstraintType::Parent => {
+                let is_parent = constraint.type_ == ConstraintType::Parent;
+                let target = match average(&self.targets(constraint, is_parent)) {
+                    Some(target) => target,
+                    None => return,
+                };
+                let world = match constraint.type_ {
+                    ConstraintType::Position => Transform { translation: target.translation, ..current },
+                    ConstraintType::Rotation => Transform { rotation: target.rotation, ..current },
+                    ConstraintType::Scale => Transform { scale: target.scale, ..current },
+                    _ => target,
+                };
+                self.apply(node, &world, &masks, weight, offset);
+            },
+            ConstraintType::Aim => {
+                let target = match average(&self.targets(constraint, false)) {
+                    Some(target) => target,
+                    None => return,
+                };
+                if let Some(rotation) = self.aim_rotation(scene, constraint, &current, target.translation) {
+                    self.apply(node, &Transform { rotation, ..current }, &masks, weight, offset);
+                }
+            },
+            ConstraintType::SingleChainIk => {
+                if let Some(chain) = ik_chain(constraint) {
+                    self.solve_ik(constraint, &chain, opts);
+                }
+            },
+            ConstraintType::Unknown => {},
+        }
+    }
+
+    /// Solves active constraints in hierarchy order, parents before children.
+    pub fn solve(&mut self, scene: &Scene, opts: &ConstraintOpts) {
+        let mut constraints: Vec<&Constraint> = scene.constraints.iter().map(|c| &**c).filter(|c| c.active && c.node.is_some()).collect();
+        constraints.sort_by_key(|c| c.node.as_ref().map_or(0, |n| n.node_depth));
+        for constraint in constraints {
+            self.solve_constraint(scene, constraint, opts);
+        }
+    }
+}
+
+// Joints from `Constraint::node` to `Constraint::ik_end_node`
+fn ik_chain(constraint: &Constraint) -> Option<Vec<&Node>> {
+    let root = constraint.node.as_ref()?;
+    let mut chain = vec![&**constraint.ik_end_node.as_ref()?];
+    while chain[chain.len() - 1].element.element_id != root.element.element_id {
+        let parent = chain[chain.len() - 1].parent.as_ref()?;
+        chain.push(parent);
+    }
+    chain.reverse();
+    if chain.len() >= 2 { Some(chain) } else { None }
+}
+
+// Nodes whose local transforms are modified by the constraint
+fn affected_nodes(constraint: &Constraint) -> Vec<u32> {
+    match constraint.type_ {
+        ConstraintType::SingleChainIk => ik_chain(constraint).map_or(Vec::new(), |chain| {
+            chain[..chain.len() - 1].iter().map(|n| n.element.typed_id).collect()
+        }),
+        _ => constraint.node.as_ref().map(|n| n.element.typed_id).into_iter().collect(),
+    }
+}
+
+/// Evaluates `anim` at `time` and solves the constraints on top of it.
+#[allow(clippy::result_large_err)]
+pub fn evaluate_constraints(scene: &Scene, anim: &Anim, time: f64, opts: &ConstraintOpts) -> Result<ConstraintPose> {
+    let state = evaluate_scene(scene, anim, time, EvaluateOpts::default())?;
+    let mut pose = ConstraintPose::from_scene(&state);
+    pose.solve(&state, opts);
+    Ok(pose)
+}
+
+/// Bakes the nodes affected by active constraints at every key time in `baked`.
+/// `baked` is not modified, the caller has to merge the results into `BakedAnim::nodes`
+/// by `typed_id`, overriding the unconstrained keys of those nodes.
+#[allow(clippy::result_large_err)]
+pub fn bake_constraints(scene: &Scene, anim: &Anim, baked: &BakedAnim, opts: &ConstraintOpts) -> Result<Vec<BakedNodeKeys>> {
+    let node_ids: Vec<u32> = scene.constraints.iter().filter(|c| c.active).flat_map(|c| affected_nodes(c)).collect();
+    bake_node_keys(scene, &node_ids, baked, |time| Ok(evaluate_constraints(scene, anim, time, opts)?.local_transforms))
+}
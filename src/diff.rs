@@ -0,0 +1,464 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+use crate::generated::{Scene, Node, Mesh, Material, Props, AnimCurve, Transform, quat_dot};
+use crate::prop_value::{prop_value, PropValue};
+use crate::prelude::Real;
+use crate::math::vec3_distance;
+
+pub struct DiffOpts {
+    pub translation_tolerance: Real,
+    pub rotation_tolerance_deg: Real,
+    pub scale_tolerance: Real,
+    pub prop_tolerance: Real,
+    pub time_tolerance: f64,
+}
+
+impl Default for DiffOpts {
+    fn default() -> Self {
+        DiffOpts {
+            translation_tolerance: 1e-5,
+            rotation_tolerance_deg: 1e-3,
+            scale_tolerance: 1e-5,
+            prop_tolerance: 1e-6,
+            time_tolerance: 1e-6,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TransformChange {
+    pub path: String,
+    pub old: Transform,
+    pub new: Transform,
+    pub translation_delta: Real,
+    pub rotation_delta_deg: Real,
+    pub scale_delta: Real,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MeshStats {
+    pub num_vertices: usize,
+    pub num_indices: usize,
+    pub num_faces: usize,
+    pub num_triangles: usize,
+    pub num_edges: usize,
+}
+
+impl MeshStats {
+    pub fn from_mesh(mesh: &Mesh) -> MeshStats {
+        MeshStats {
+            num_vertices: mesh.num_vertices,
+            num_indices: mesh.num_indices,
+            num_faces: mesh.num_faces,
+            num_triangles: mesh.num_triangles,
+            num_edges: mesh.num_edges,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MeshChange {
+    pub path: String,
+    pub old: Option<MeshStats>,
+    pub new: Option<MeshStats>,
+    pub topology_changed: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct PropChange {
+    pub name: String,
+    pub old: Option<PropValue>,
+    pub new: Option<PropValue>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MaterialChange {
+    pub name: String,
+    pub props: Vec<PropChange>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AnimCurveStats {
+    pub key_counts: [usize; 3],
+    pub time_begin: f64,
+    pub time_end: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimChange {
+    pub stack: String,
+    pub layer: String,
+    pub element: String,
+    pub prop: String,
+    pub old: Option<AnimCurveStats>,
+    pub new: Option<AnimCurveStats>,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct SceneDiff {
+    pub added_nodes: Vec<String>,
+    pub removed_nodes: Vec<String>,
+    pub transform_changes: Vec<TransformChange>,
+    pub mesh_changes: Vec<MeshChange>,
+    pub added_materials: Vec<String>,
+    pub removed_materials: Vec<String>,
+    pub material_changes: Vec<MaterialChange>,
+    pub anim_changes: Vec<AnimChange>,
+}
+
+impl SceneDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.transform_changes.is_empty()
+            && self.mesh_changes.is_empty()
+            && self.added_materials.is_empty()
+            && self.removed_materials.is_empty()
+            && self.material_changes.is_empty()
+            && self.anim_changes.is_empty()
+    }
+}
+
+/// Returns the names of the ancestors of `node` joined with `/`, excluding the root node.
+pub fn node_path(node: &Node) -> String {
+    let mut names = Vec::new();
+    let mut cur = Some(node);
+    while let Some(n) = cur {
+        if n.is_root { break }
+        names.push(n.element.name.as_ref());
+        cur = n.parent.as_deref();
+    }
+    names.reverse();
+    names.join("/")
+}
+
+// Suffixes `name` with `#1`, `#2`.. until it is not `taken`
+fn unique_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut key = name.to_string();
+    let mut index = 1;
+    while taken(&key) {
+        key = format!("{}#{}", name, index);
+        index += 1;
+    }
+    key
+}
+
+fn nodes_by_path(scene: &Scene) -> BTreeMap<String, &Node> {
+    let mut result = BTreeMap::new();
+    for node in &scene.nodes {
+        if node.is_root { continue }
+        let key = unique_name(&node_path(node), |k| result.contains_key(k));
+        result.insert(key, node);
+    }
+    result
+}
+
+fn materials_by_name(scene: &Scene) -> BTreeMap<String, &Material> {
+    let mut result = BTreeMap::new();
+    for material in &scene.materials {
+        let key = unique_name(&material.element.name, |k| result.contains_key(k));
+        result.insert(key, material);
+    }
+    result
+}
+
+fn diff_transform(path: &str, a: &Transform, b: &Transform, opts: &DiffOpts) -> Option<TransformChange> {
+    let translation_delta = vec3_distance(a.translation, b.translation);
+    let dot = quat_dot(a.rotation, b.rotation).abs().min(1.0);
+    let rotation_delta_deg = (2.0 * dot.acos()).to_degrees();
+    let scale_delta = vec3_distance(a.scale, b.scale);
+
+    if translation_delta > opts.translation_tolerance
+        || rotation_delta_deg > opts.rotation_tolerance_deg
+        || scale_delta > opts.scale_tolerance
+    {
+        Some(TransformChange {
+            path: path.to_string(),
+            old: *a,
+            new: *b,
+            translation_delta,
+            rotation_delta_deg,
+            scale_delta,
+        })
+    } else {
+        None
+    }
+}
+
+fn diff_mesh(path: &str, a: Option<&Mesh>, b: Option<&Mesh>) -> Option<MeshChange> {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        (None, None) => return None,
+        (a, b) => return Some(MeshChange {
+            path: path.to_string(),
+            old: a.map(MeshStats::from_mesh),
+            new: b.map(MeshStats::from_mesh),
+            topology_changed: true,
+        }),
+    };
+    let old = MeshStats::from_mesh(a);
+    let new = MeshStats::from_mesh(b);
+    let topology_changed = a.vertex_indices.as_ref() != b.vertex_indices.as_ref()
+        || a.faces.len() != b.faces.len()
+        || a.faces.iter().zip(b.faces.iter()).any(|(fa, fb)| {
+            fa.index_begin != fb.index_begin || fa.num_indices != fb.num_indices
+        });
+
+    if old != new || topology_changed {
+        Some(MeshChange { path: path.to_string(), old: Some(old), new: Some(new), topology_changed })
+    } else {
+        None
+    }
+}
+
+fn prop_values(props: &Props) -> BTreeMap<String, PropValue> {
+    props.props.iter()
+        .filter_map(|prop| Some((prop.name.to_string(), prop_value(prop)?)))
+        .collect()
+}
+
+fn prop_values_equal(a: &PropValue, b: &PropValue, tolerance: Real) -> bool {
+    match (a, b) {
+        (PropValue::Number(a), PropValue::Number(b)) => {
+            (a.x - b.x).abs() <= tolerance && (a.y - b.y).abs() <= tolerance
+                && (a.z - b.z).abs() <= tolerance && (a.w - b.w).abs() <= tolerance
+        },
+        (PropValue::Int(a), PropValue::Int(b)) => a == b,
+        (PropValue::String(a), PropValue::String(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn diff_material(name: &str, a: &Material, b: &Material, opts: &DiffOpts) -> Option<MaterialChange> {
+    let a_props = prop_values(&a.element.props);
+    let b_props = prop_values(&b.element.props);
+    let names: BTreeSet<&String> = a_props.keys().chain(b_props.keys()).collect();
+
+    let mut props = Vec::new();
+    for name in names {
+        let old = a_props.get(name);
+        let new = b_props.get(name);
+        let equal = match (old, new) {
+            (Some(old), Some(new)) => prop_values_equal(old, new, opts.prop_tolerance),
+            _ => false,
+        };
+        if !equal {
+            props.push(PropChange { name: name.clone(), old: old.cloned(), new: new.cloned() });
+        }
+    }
+
+    if props.is_empty() {
+        None
+    } else {
+        Some(MaterialChange { name: name.to_string(), props })
+    }
+}
+
+fn curve_stats(curves: &[Option<&AnimCurve>; 3]) -> AnimCurveStats {
+    let mut stats = AnimCurveStats { key_counts: [0; 3], time_begin: 0.0, time_end: 0.0 };
+    let mut has_keys = false;
+    for (i, curve) in curves.iter().enumerate() {
+        let keys = match curve {
+            Some(curve) => &curve.keyframes,
+            None => continue,
+        };
+        stats.key_counts[i] = keys.len();
+        if let (Some(first), Some(last)) = (keys.first(), keys.last()) {
+            if has_keys {
+                stats.time_begin = stats.time_begin.min(first.time);
+                stats.time_end = stats.time_end.max(last.time);
+            } else {
+                stats.time_begin = first.time;
+                stats.time_end = last.time;
+                has_keys = true;
+            }
+        }
+    }
+    stats
+}
+
+fn anim_stats(scene: &Scene) -> BTreeMap<(String, String, String, String), AnimCurveStats> {
+    let mut result = BTreeMap::new();
+    // Animated elements sharing a name are told apart like in `nodes_by_path()`
+    let mut element_names: BTreeMap<u32, String> = BTreeMap::new();
+    for stack in &scene.anim_stacks {
+        for layer in &stack.layers {
+            for anim_prop in &layer.anim_props {
+                let value = &anim_prop.anim_value;
+                let curves = [value.curves[0].as_deref(), value.curves[1].as_deref(), value.curves[2].as_deref()];
+                let element = &anim_prop.element;
+                if !element_names.contains_key(&element.element_id) {
+                    let name = unique_name(&element.name, |k| element_names.values().any(|n| n == k));
+                    element_names.insert(element.element_id, name);
+                }
+                let key = (
+                    stack.element.name.to_string(),
+                    layer.element.name.to_string(),
+                    element_names[&element.element_id].clone(),
+                    anim_prop.prop_name.to_string(),
+                );
+                result.insert(key, curve_stats(&curves));
+            }
+        }
+    }
+    result
+}
+
+fn anim_stats_equal(a: &AnimCurveStats, b: &AnimCurveStats, tolerance: f64) -> bool {
+    a.key_counts == b.key_counts
+        && (a.time_begin - b.time_begin).abs() <= tolerance
+        && (a.time_end - b.time_end).abs() <= tolerance
+}
+
+/// Compares two scenes structurally, matching nodes by path and other elements by name.
+pub fn diff(old: &Scene, new: &Scene, opts: DiffOpts) -> SceneDiff {
+    let mut result = SceneDiff::default();
+
+    let old_nodes = nodes_by_path(old);
+    let new_nodes = nodes_by_path(new);
+
+    for (path, old_node) in &old_nodes {
+        let new_node = match new_nodes.get(path) {
+            Some(node) => node,
+            None => {
+                result.removed_nodes.push(path.clone());
+                continue;
+            },
+        };
+
+        if let Some(change) = diff_transform(path, &old_node.local_transform, &new_node.local_transform, &opts) {
+            result.transform_changes.push(change);
+        }
+
+        if let Some(change) = diff_mesh(path, old_node.mesh.as_deref(), new_node.mesh.as_deref()) {
+            result.mesh_changes.push(change);
+        }
+    }
+    for path in new_nodes.keys() {
+        if !old_nodes.contains_key(path) {
+            result.added_nodes.push(path.clone());
+        }
+    }
+
+    let old_materials = materials_by_name(old);
+    let new_materials = materials_by_name(new);
+    for (name, old_material) in &old_materials {
+        match new_materials.get(name) {
+            Some(new_material) => {
+                if let Some(change) = diff_material(name, old_material, new_material, &opts) {
+                    result.material_changes.push(change);
+                }
+            },
+            None => result.removed_materials.push(name.clone()),
+        }
+    }
+    for name in new_materials.keys() {
+        if !old_materials.contains_key(name) {
+            result.added_materials.push(name.clone());
+        }
+    }
+
+    let old_anims = anim_stats(old);
+    let new_anims = anim_stats(new);
+    let keys: BTreeSet<_> = old_anims.keys().chain(new_anims.keys()).collect();
+    for key in keys {
+        let old_stats = old_anims.get(key);
+        let new_stats = new_anims.get(key);
+        let equal = match (old_stats, new_stats) {
+            (Some(a), Some(b)) => anim_stats_equal(a, b, opts.time_tolerance),
+            _ => false,
+        };
+        if !equal {
+            result.anim_changes.push(AnimChange {
+                stack: key.0.clone(),
+                layer: key.1.clone(),
+                element: key.2.clone(),
+                prop: key.3.clone(),
+                old: old_stats.copied(),
+                new: new_stats.copied(),
+            });
+        }
+    }
+
+    result
+}
+
+impl Display for MeshStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "vertices {}, faces {}, triangles {}", self.num_vertices, self.num_faces, self.num_triangles)
+    }
+}
+
+impl Display for AnimCurveStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "keys {:?} time {}..{}", self.key_counts, self.time_begin, self.time_end)
+    }
+}
+
+fn write_option<T: Display>(f: &mut Formatter<'_>, value: &Option<T>) -> fmt::Result {
+    match value {
+        Some(v) => write!(f, "{}", v),
+        None => write!(f, "(none)"),
+    }
+}
+
+impl Display for SceneDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for path in &self.added_nodes {
+            writeln!(f, "+ node {}", path)?;
+        }
+        for path in &self.removed_nodes {
+            writeln!(f, "- node {}", path)?;
+        }
+        for change in &self.transform_changes {
+            writeln!(f, "~ transform {}: translation {} -> {}, rotation {:.3} deg, scale {} -> {}",
+                change.path, change.old.translation, change.new.translation,
+                change.rotation_delta_deg, change.old.scale, change.new.scale)?;
+        }
+        for change in &self.mesh_changes {
+            let (old, new) = match (&change.old, &change.new) {
+                (Some(old), Some(new)) => (old, new),
+                (None, Some(new)) => {
+                    writeln!(f, "+ mesh {}: {}", change.path, new)?;
+                    continue;
+                },
+                (Some(old), None) => {
+                    writeln!(f, "- mesh {}: {}", change.path, old)?;
+                    continue;
+                },
+                (None, None) => continue,
+            };
+            write!(f, "~ mesh {}: vertices {} -> {}, faces {} -> {}, triangles {} -> {}",
+                change.path, old.num_vertices, new.num_vertices,
+                old.num_faces, new.num_faces,
+                old.num_triangles, new.num_triangles)?;
+            if change.topology_changed {
+                write!(f, ", topology changed")?;
+            }
+            writeln!(f)?;
+        }
+        for name in &self.added_materials {
+            writeln!(f, "+ material {}", name)?;
+        }
+        for name in &self.removed_materials {
+            writeln!(f, "- material {}", name)?;
+        }
+        for change in &self.material_changes {
+            for prop in &change.props {
+                write!(f, "~ material {}.{}: ", change.name, prop.name)?;
+                write_option(f, &prop.old)?;
+                write!(f, " -> ")?;
+                write_option(f, &prop.new)?;
+                writeln!(f)?;
+            }
+        }
+        for change in &self.anim_changes {
+            write!(f, "~ anim {}/{} {}.{}: ", change.stack, change.layer, change.element, change.prop)?;
+            write_option(f, &change.old)?;
+            write!(f, " -> ")?;
+            write_option(f, &change.new)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
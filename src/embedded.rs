@@ -0,0 +1,245 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::generated::Scene;
+use crate::prelude::String;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Gif,
+    Tiff,
+    Tga,
+    Dds,
+    Ktx,
+    Ktx2,
+    Hdr,
+    Exr,
+    Psd,
+    Webp,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Gif => "gif",
+            ImageFormat::Tiff => "tif",
+            ImageFormat::Tga => "tga",
+            ImageFormat::Dds => "dds",
+            ImageFormat::Ktx => "ktx",
+            ImageFormat::Ktx2 => "ktx2",
+            ImageFormat::Hdr => "hdr",
+            ImageFormat::Exr => "exr",
+            ImageFormat::Psd => "psd",
+            ImageFormat::Webp => "webp",
+        }
+    }
+}
+
+/// Detects the image format of `data` from its magic bytes.
+pub fn detect_image_format(data: &[u8]) -> Option<ImageFormat> {
+    let format = if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        ImageFormat::Png
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        ImageFormat::Jpeg
+    } else if data.starts_with(b"BM") && data.len() >= 14 {
+        ImageFormat::Bmp
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        ImageFormat::Gif
+    } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        ImageFormat::Tiff
+    } else if data.starts_with(b"DDS ") {
+        ImageFormat::Dds
+    } else if data.starts_with(b"\xabKTX 11\xbb\r\n\x1a\n") {
+        ImageFormat::Ktx
+    } else if data.starts_with(b"\xabKTX 20\xbb\r\n\x1a\n") {
+        ImageFormat::Ktx2
+    } else if data.starts_with(b"#?RADIANCE") || data.starts_with(b"#?RGBE") {
+        ImageFormat::Hdr
+    } else if data.starts_with(b"\x76\x2f\x31\x01") {
+        ImageFormat::Exr
+    } else if data.starts_with(b"8BPS") {
+        ImageFormat::Psd
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        ImageFormat::Webp
+    } else if data.ends_with(b"TRUEVISION-XFILE.\0") {
+        ImageFormat::Tga
+    } else {
+        return None;
+    };
+    Some(format)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EmbeddedKind {
+    Image(ImageFormat),
+    Audio,
+    Unknown,
+}
+
+#[derive(Clone, Debug)]
+pub struct EmbeddedFile<'a> {
+    pub kind: EmbeddedKind,
+    pub filename: std::string::String,
+    pub element_id: Option<u32>,
+    pub data: &'a [u8],
+}
+
+#[derive(Clone, Debug)]
+pub struct EmbeddedOpts {
+    pub rename_collisions: bool,
+}
+
+impl Default for EmbeddedOpts {
+    fn default() -> Self {
+        EmbeddedOpts { rename_collisions: true }
+    }
+}
+
+pub struct EmbeddedIter<'a> {
+    scene: &'a Scene,
+    opts: EmbeddedOpts,
+    stage: usize,
+    index: usize,
+    seen_content: HashSet<(usize, usize)>,
+    seen_files: HashSet<u32>,
+    seen_names: HashSet<std::string::String>,
+}
+
+fn last_component(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or("")
+}
+
+// Every name, including the element name fallback, is reduced to its last path component
+fn suggested_name(names: &[&String], fallback: &str) -> std::string::String {
+    let name = names.iter()
+        .map(|n| last_component(n))
+        .find(|n| !n.is_empty())
+        .unwrap_or_else(|| last_component(fallback));
+    let name: std::string::String = name.chars()
+        .map(|c| if c.is_control() || ":*?\"<>|/\\".contains(c) { '_' } else { c })
+        .collect();
+    if is_plain_file_name(&name) { name } else { "embedded".to_string() }
+}
+
+// Single normal path component that stays inside the directory it is joined to
+fn is_plain_file_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+impl<'a> EmbeddedIter<'a> {
+    fn next_source(&mut self) -> Option<Option<EmbeddedFile<'a>>> {
+        let scene = self.scene;
+        let ix = self.index;
+        self.index += 1;
+        let (names, fallback, element_id, data, audio): ([&'a String; 3], &'a str, Option<u32>, &'a [u8], bool) = match self.stage {
+            0 => {
+                let file = scene.texture_files.get(ix)?;
+                if !self.seen_files.insert(file.index) { return Some(None) }
+                ([&file.relative_filename, &file.filename, &file.absolute_filename], "texture", None, &file.content, false)
+            },
+            1 => {
+                let tex = scene.textures.get(ix)?;
+                if tex.has_file && self.seen_files.contains(&tex.file_index) { return Some(None) }
+                ([&tex.relative_filename, &tex.filename, &tex.absolute_filename], &tex.element.name, Some(tex.element.element_id), &tex.content, false)
+            },
+            2 => {
+                let video = scene.videos.get(ix)?;
+                ([&video.relative_filename, &video.filename, &video.absolute_filename], &video.element.name, Some(video.element.element_id), &video.content, false)
+            },
+            3 => {
+                let clip = scene.audio_clips.get(ix)?;
+                ([&clip.relative_filename, &clip.filename, &clip.absolute_filename], &clip.element.name, Some(clip.element.element_id), &clip.content, true)
+            },
+            _ => return None,
+        };
+
+        if data.is_empty() || !self.seen_content.insert((data.as_ptr() as usize, data.len())) {
+            return Some(None);
+        }
+
+        let kind = if audio {
+            EmbeddedKind::Audio
+        } else {
+            detect_image_format(data).map(EmbeddedKind::Image).unwrap_or(EmbeddedKind::Unknown)
+        };
+
+        let mut filename = suggested_name(&names, fallback);
+        if let EmbeddedKind::Image(format) = kind {
+            if Path::new(&filename).extension().is_none() {
+                filename = format!("{}.{}", filename, format.extension());
+            }
+        }
+        if self.opts.rename_collisions {
+            filename = self.unique_name(filename);
+        }
+
+        Some(Some(EmbeddedFile { kind, filename, element_id, data }))
+    }
+
+    fn unique_name(&mut self, filename: std::string::String) -> std::string::String {
+        if self.seen_names.insert(filename.to_lowercase()) {
+            return filename;
+        }
+        let (stem, ext) = match filename.rfind('.') {
+            Some(pos) if pos > 0 => filename.split_at(pos),
+            _ => (filename.as_str(), ""),
+        };
+        (1..).map(|n| format!("{}_{}{}", stem, n, ext))
+            .find(|name| self.seen_names.insert(name.to_lowercase()))
+            .unwrap()
+    }
+}
+
+impl<'a> Iterator for EmbeddedIter<'a> {
+    type Item = EmbeddedFile<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.stage < 4 {
+            match self.next_source() {
+                Some(Some(file)) => return Some(file),
+                Some(None) => {},
+                None => {
+                    self.stage += 1;
+                    self.index = 0;
+                },
+            }
+        }
+        None
+    }
+}
+
+/// Iterates over embedded media in `scene`, each blob is returned once.
+pub fn embedded_files(scene: &Scene, opts: EmbeddedOpts) -> EmbeddedIter<'_> {
+    EmbeddedIter {
+        scene,
+        opts,
+        stage: 0,
+        index: 0,
+        seen_content: HashSet::new(),
+        seen_files: HashSet::new(),
+        seen_names: HashSet::new(),
+    }
+}
+
+/// Writes all embedded media in `scene` to `dir`, returns the written paths.
+pub fn extract_embedded<P: AsRef<Path>>(scene: &Scene, dir: P, opts: EmbeddedOpts) -> io::Result<Vec<PathBuf>> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let mut paths = Vec::new();
+    for file in embedded_files(scene, opts) {
+        if !is_plain_file_name(&file.filename) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid embedded file name {:?}", file.filename)));
+        }
+        let path = dir.join(&file.filename);
+        fs::write(&path, file.data)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
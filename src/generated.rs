@@ -1588,7 +1588,7 @@ pub struct ShaderTexture {
     pub inputs: List<ShaderTextureInput>,
     pub shader_source: String,
     pub raw_shader_source: Blob,
-    pub main_texture: Ref<Texture>,
+    pub main_texture: Option<Ref<Texture>>,
     pub main_texture_output_index: i64,
     pub prop_prefix: String,
 }
@@ -57,3 +57,17 @@ impl From<Quat> for mint::Quaternion<Real> {
 impl mint::IntoMint for Quat {
     type MintType = mint::Quaternion<Real>;
 }
+
+impl From<mint::ColumnMatrix4<Real>> for crate::Matrix4 {
+    fn from(m: mint::ColumnMatrix4<Real>) -> Self {
+        Self{ cols: m.into() }
+    }
+}
+impl From<crate::Matrix4> for mint::ColumnMatrix4<Real> {
+    fn from(m: crate::Matrix4) -> Self {
+        m.cols.into()
+    }
+}
+impl mint::IntoMint for crate::Matrix4 {
+    type MintType = mint::ColumnMatrix4<Real>;
+}
@@ -2,9 +2,65 @@
 
 pub mod generated;
 pub mod prelude;
+pub mod prop_value;
+pub mod diff;
+pub mod validate;
+pub mod resolve;
+pub mod embedded;
+pub mod thumbnail;
+pub mod metallic_roughness;
+pub mod shader_graph;
+pub mod materialx;
+pub mod topology;
+pub mod simplify;
+pub mod optimize;
+pub mod meshlet;
+pub mod bvh;
+pub mod bounds;
+pub mod limit;
+pub mod trim;
+pub mod tessellate;
+pub mod sweep;
+pub mod cache_writer;
+pub mod cache_playback;
+pub mod camera;
+pub mod light_units;
+pub mod bake_keys;
+pub mod constraint;
+pub mod character;
+pub mod retarget;
+
+mod math;
 
 pub use prelude::*;
 pub use generated::*;
+pub use prop_value::PropValue;
+pub use diff::{diff, DiffOpts, SceneDiff};
+pub use validate::{validate, Rules, Severity, Finding, FindingKind, ValidationReport};
+pub use resolve::{TextureResolver, TextureResolution};
+pub use embedded::{embedded_files, extract_embedded, detect_image_format, EmbeddedFile, EmbeddedKind, EmbeddedOpts, ImageFormat};
+pub use thumbnail::encode_png_rgba8;
+pub use metallic_roughness::{MetallicRoughness, AlphaMode, DroppedFeature, TextureRef, ChannelImage};
+pub use shader_graph::{ShaderGraph, ShaderNode, ShaderNodeKind, ShaderInput, ShaderEdge, SHADER_GRAPH_JSON_SCHEMA};
+pub use materialx::{export_materialx, MaterialXOpts, MaterialXSurface};
+pub use topology::{MeshTopology, BoundaryLoops, Components};
+pub use simplify::{generate_lods, LodMesh, LodVertex, LodPart, SimplifyOpts};
+pub use optimize::{analyze_vertex_cache, optimize_vertex_cache, optimize_overdraw, optimize_vertex_fetch, remap_vertex_buffer, VertexCacheStats, OptimizeOpts, PartOptimizeStats};
+pub use meshlet::{build_meshlets, Meshlet, MeshletOpts, MeshletPart};
+pub use bvh::{SceneBvh, RayHit};
+pub use bounds::{animated_bounds, Aabb, AnimatedBounds};
+pub use limit::{LimitSurface, LimitSurfaceOpts, LimitPoint};
+pub use trim::{trimmed_surfaces, TrimmedSurface, TrimLoop, TrimTessellateOpts, SurfaceMesh, SurfaceVertex};
+pub use tessellate::{tessellate_nurbs_curve_adaptive, tessellate_nurbs_surface_adaptive, AdaptiveTessellateOpts};
+pub use sweep::{sweep_polyline, sweep_polyline_into, sweep_line_curve, SweepOpts, SweepProfile};
+pub use cache_writer::{bake_vertex_positions, write_pc2, write_pc2_file, write_maya_cache, Pc2Opts, McOpts, McFormat, McLayout, McChannel};
+pub use cache_playback::{CachePlayback, CachePlaybackOpts, CachePlaybackIter, CachePlaybackError};
+pub use camera::{ClipConvention, Matrix4};
+pub use light_units::{detect_light_convention, photometric_lights, LightConvention, LightUnitOpts, PhotometricLight, PhotometricUnit};
+pub use bake_keys::BakedNodeKeys;
+pub use constraint::{evaluate_constraints, bake_constraints, ConstraintPose, ConstraintOpts};
+pub use character::{character_definitions, HumanoidSlot, CharacterDefinition, CharacterLink, TPoseSource};
+pub use retarget::{BoneMap, BonePair, Retargeter, RetargetOpts};
 
 use std::vec::Vec;
 
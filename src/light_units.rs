@@ -0,0 +1,167 @@
+use std::f64::consts::PI;
+use crate::generated::{Scene, Light, Node, Vec3, Exporter, LightType, LightDecay, LightAreaShape};
+use crate::prelude::Real;
+use crate::math::vec3_length;
+
+/// How the exporting application defines `Light::intensity`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LightConvention {
+    /// Unitless intensity scaled by the decay in scene units, also used for unknown exporters.
+    Maya,
+    /// Photometric lights are written in candela with quadratic decay,
+    /// standard lights behave like `Maya`.
+    Max,
+    /// Radiant power in watts, sun strength in W/m² which Blender treats as lux.
+    Blender,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PhotometricUnit {
+    Lumen,
+    Candela,
+    Lux,
+    Nit,
+}
+
+pub struct LightUnitOpts {
+    /// Overrides the convention detected from `Metadata`.
+    pub convention: Option<LightConvention>,
+    /// Luminous efficacy used for radiometric intensities.
+    pub lumens_per_watt: Real,
+    /// Distance in meters where lights without inverse square falloff are matched.
+    pub reference_distance: Real,
+}
+
+impl Default for LightUnitOpts {
+    fn default() -> Self {
+        LightUnitOpts {
+            convention: None,
+            lumens_per_watt: 683.0,
+            reference_distance: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PhotometricLight {
+    pub type_: LightType,
+    pub unit: PhotometricUnit,
+    /// Intensity in `unit`, multiplied by `color`.
+    pub value: Real,
+    pub color: Vec3,
+    /// Half angles of the spot cone in radians.
+    pub inner_cone_angle: Real,
+    pub outer_cone_angle: Real,
+    /// The source falloff is not inverse square, `value` matches it at `reference_distance`.
+    pub approximate: bool,
+}
+
+pub fn detect_light_convention(scene: &Scene) -> LightConvention {
+    let metadata = &scene.metadata;
+    if let Exporter::BlenderBinary | Exporter::BlenderAscii = metadata.exporter {
+        return LightConvention::Blender;
+    }
+    let app = metadata.original_application.name.to_lowercase();
+    if app.contains("3ds max") || app.contains("3dsmax") {
+        LightConvention::Max
+    } else if app.contains("blender") {
+        LightConvention::Blender
+    } else {
+        LightConvention::Maya
+    }
+}
+
+// Area facing the light direction in square meters
+fn projected_area(light: &Light, node: Option<&Node>, unit_meters: Real) -> Real {
+    let scale = node.map_or([1.0; 3], |n| {
+        let m = &n.node_to_world;
+        [
+            vec3_length(Vec3 { x: m.m00, y: m.m10, z: m.m20 }),
+            vec3_length(Vec3 { x: m.m01, y: m.m11, z: m.m21 }),
+            vec3_length(Vec3 { x: m.m02, y: m.m12, z: m.m22 }),
+        ]
+    });
+    let area = match light.area_shape {
+        // Maya area lights span [-1, 1] in the local XY plane
+        LightAreaShape::Rectangle => 4.0 * scale[0] * scale[1],
+        LightAreaShape::Sphere => {
+            let radius = (scale[0] + scale[1] + scale[2]) / 3.0;
+            PI * radius * radius
+        },
+    };
+    area * unit_meters * unit_meters
+}
+
+fn decay_exponent(decay: LightDecay) -> i32 {
+    match decay {
+        LightDecay::None => 0,
+        LightDecay::Linear => 1,
+        LightDecay::Quadratic => 2,
+        LightDecay::Cubic => 3,
+    }
+}
+
+impl Light {
+    /// Converts the light to photometric units, see `photometric_lights()`.
+    pub fn to_photometric(&self, scene: &Scene, opts: &LightUnitOpts) -> PhotometricLight {
+        let convention = opts.convention.unwrap_or_else(|| detect_light_convention(scene));
+        let unit_meters = if scene.settings.unit_meters > 0.0 { scene.settings.unit_meters } else { 0.01 };
+        let intensity = self.intensity;
+        let r = opts.reference_distance;
+
+        // Blender lights always fall off with inverse square regardless of `DecayType`
+        let exponent = if convention == LightConvention::Blender { 2 } else { decay_exponent(self.decay) };
+        let approximate = exponent != 2 || self.type_ == LightType::Volume;
+
+        // Luminous intensity on the light axis in candela
+        let candela = match convention {
+            LightConvention::Blender => intensity * opts.lumens_per_watt / (4.0 * PI),
+            // ufbx divides "Intensity" by 100 while Max stores candela as is
+            LightConvention::Max if exponent == 2 => intensity * 100.0,
+            LightConvention::Max | LightConvention::Maya => {
+                // Illuminance is `intensity / d^k` with `d` in scene units
+                let k = Real::from(exponent);
+                intensity * opts.lumens_per_watt * unit_meters.powf(k) * r.powf(2.0 - k)
+            },
+        };
+
+        let (unit, value, approximate) = match self.type_ {
+            LightType::Point | LightType::Volume => (PhotometricUnit::Lumen, candela * 4.0 * PI, approximate),
+            LightType::Spot => (PhotometricUnit::Candela, candela, approximate),
+            LightType::Directional => {
+                let lux = match convention {
+                    LightConvention::Blender => intensity,
+                    LightConvention::Max if exponent == 2 => intensity * 100.0,
+                    _ => intensity * opts.lumens_per_watt,
+                };
+                (PhotometricUnit::Lux, lux, false)
+            },
+            LightType::Area => {
+                let area = projected_area(self, self.element.instances.iter().next().map(|n| &**n), unit_meters);
+                let nits = match convention {
+                    // Lambertian emitter, radiant power is spread over `pi * area`
+                    LightConvention::Blender => intensity * opts.lumens_per_watt / (PI * area),
+                    _ => candela / area,
+                };
+                (PhotometricUnit::Nit, nits, approximate)
+            },
+        };
+
+        PhotometricLight {
+            type_: self.type_,
+            unit,
+            value,
+            color: self.color,
+            inner_cone_angle: (self.inner_angle * 0.5).to_radians(),
+            outer_cone_angle: (self.outer_angle * 0.5).to_radians(),
+            approximate,
+        }
+    }
+}
+
+/// Converts every light in the scene to photometric units, lumens for point lights,
+/// candela for spot lights, lux for directional lights and nits for area lights.
+/// Indexed like `scene.lights[]`.
+pub fn photometric_lights(scene: &Scene, opts: &LightUnitOpts) -> Vec<PhotometricLight> {
+    scene.lights.iter().map(|light| light.to_photometric(scene, opts)).collect()
+}
@@ -0,0 +1,842 @@
+use std::collections::HashMap;
+use crate::generated::{Mesh, Face, Vec2, Vec3, SubdivisionBoundary, SubdivideOpts, Result};
+use crate::prelude::Real;
+use crate::topology::MeshTopology;
+use crate::math::{vec3_add, vec3_sub, vec3_mul, vec3_cross, vec3_normalize};
+
+pub struct LimitSurfaceOpts {
+    /// Number of subdivision levels computed for the whole mesh up front, patches
+    /// that are still irregular at this level are subdivided locally when evaluated.
+    /// Clamped to at least 1.
+    pub max_level: usize,
+    /// Overrides `Mesh::subdivision_boundary` unless `Default`.
+    pub boundary: SubdivisionBoundary,
+    /// Overrides `Mesh::subdivision_uv_boundary` unless `Default`.
+    pub uv_boundary: SubdivisionBoundary,
+}
+
+impl Default for LimitSurfaceOpts {
+    fn default() -> Self {
+        LimitSurfaceOpts {
+            max_level: 3,
+            boundary: SubdivisionBoundary::Default,
+            uv_boundary: SubdivisionBoundary::Default,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct LimitPoint {
+    pub position: Vec3,
+    /// Derivative of `position` with respect to `u`.
+    pub du: Vec3,
+    /// Derivative of `position` with respect to `v`.
+    pub dv: Vec3,
+    pub normal: Vec3,
+    /// Limit of the first UV set, zero if the mesh has no UVs.
+    pub uv: Vec2,
+}
+
+// Sharpness is the crease scaled by 10, creases of at least 0.999 never decay in `ufbx_subdivide_mesh()`
+const INFINITE_SHARPNESS: Real = 9.99;
+
+// Subdivision depth at which the remaining patches are smaller than double precision
+// relative to the face and are interpolated bilinearly
+const MAX_DEPTH: usize = 48;
+
+fn decay_sharpness(sharpness: Real) -> Real {
+    if sharpness >= INFINITE_SHARPNESS { sharpness } else { (sharpness - 1.0).max(0.0) }
+}
+
+fn is_stationary(sharpness: Real) -> bool {
+    sharpness <= 0.0 || sharpness >= INFINITE_SHARPNESS
+}
+
+#[derive(Clone, Copy)]
+struct BoundaryRules {
+    sharp_corners: bool,
+    sharp_splits: bool,
+    sharp_all: bool,
+}
+
+impl BoundaryRules {
+    // Same interpretation as `ufbx_subdivide_mesh()`
+    fn new(boundary: SubdivisionBoundary) -> BoundaryRules {
+        let (sharp_corners, sharp_splits, sharp_all) = match boundary {
+            SubdivisionBoundary::Default | SubdivisionBoundary::Legacy | SubdivisionBoundary::SharpNone => (false, false, false),
+            SubdivisionBoundary::SharpCorners => (true, false, false),
+            SubdivisionBoundary::SharpBoundary => (true, true, false),
+            SubdivisionBoundary::SharpInterior => (false, false, true),
+        };
+        BoundaryRules { sharp_corners, sharp_splits, sharp_all }
+    }
+}
+
+// Data of a single subdivision level, everything except `positions` is indexed by half-edge.
+struct Level {
+    topo: MeshTopology,
+    faces: Vec<Face>,
+    positions: Vec<Vec3>,
+    uv_indices: Vec<u32>,
+    uvs: Vec<Vec3>,
+    /// Edge crease scaled so that 1.0 is fully sharp.
+    edge_crease: Vec<Real>,
+    vertex_crease: Vec<Real>,
+}
+
+fn resolve_boundary(opt: SubdivisionBoundary, mesh: SubdivisionBoundary) -> SubdivisionBoundary {
+    if opt == SubdivisionBoundary::Default { mesh } else { opt }
+}
+
+impl Level {
+    fn new(mesh: &Mesh) -> Level {
+        let topo = MeshTopology::new(mesh);
+        let num_indices = topo.num_half_edges();
+        let edge_crease = (0..num_indices).map(|he| {
+            topo.mesh_edge(he).and_then(|e| mesh.edge_crease.get(e)).map_or(0.0, |&c| c * 10.0)
+        }).collect();
+        let vertex_crease = (0..num_indices).map(|ix| {
+            if mesh.vertex_crease.exists { mesh.vertex_crease.values[mesh.vertex_crease.indices[ix] as usize] * 10.0 } else { 0.0 }
+        }).collect();
+        let (uv_indices, uvs) = if mesh.vertex_uv.exists {
+            (mesh.vertex_uv.indices.to_vec(), mesh.vertex_uv.values.iter().map(|uv| Vec3 { x: uv.x, y: uv.y, z: 0.0 }).collect())
+        } else {
+            (vec![0; num_indices], vec![Vec3::default()])
+        };
+        Level {
+            topo,
+            faces: mesh.faces.to_vec(),
+            positions: mesh.vertices.to_vec(),
+            uv_indices,
+            uvs,
+            edge_crease,
+            vertex_crease,
+        }
+    }
+
+    // Copies `face` and the faces around its corners into a standalone patch
+    fn patch(&self, face: usize) -> Patch {
+        let topo = &self.topo;
+        let num_indices = topo.num_half_edges();
+        let begin = self.faces[face].index_begin as usize;
+
+        let mut faces = vec![face];
+        for corner in begin..begin + 4 {
+            let mut he = corner;
+            for _ in 0..num_indices {
+                match topo.prev_vertex_edge(he) {
+                    Some(prev) if prev != corner => he = prev,
+                    _ => break,
+                }
+                if !faces.contains(&topo.face(he)) { faces.push(topo.face(he)) }
+            }
+            let mut he = corner;
+            for _ in 0..num_indices {
+                match topo.next_vertex_edge(he) {
+                    Some(next) if next != corner => he = next,
+                    _ => break,
+                }
+                if !faces.contains(&topo.face(he)) { faces.push(topo.face(he)) }
+            }
+        }
+
+        let mut local_edges = HashMap::new();
+        for (quad, &f) in faces.iter().enumerate() {
+            let begin = self.faces[f].index_begin as usize;
+            for k in 0..4 {
+                local_edges.insert(begin + k, quad * 4 + k);
+            }
+        }
+
+        let mut patch = Patch::default();
+        let mut local_vertices = HashMap::new();
+        let mut local_uvs = HashMap::new();
+        for &f in &faces {
+            let begin = self.faces[f].index_begin as usize;
+            for he in begin..begin + 4 {
+                let vertex = *local_vertices.entry(topo.vertex(he)).or_insert_with(|| {
+                    patch.positions.push(self.positions[topo.vertex(he)]);
+                    patch.vertex_sharpness.push(self.vertex_crease[he]);
+                    patch.positions.len() - 1
+                });
+                let uv = *local_uvs.entry(self.uv_indices[he]).or_insert_with(|| {
+                    patch.uvs.push(self.uvs[self.uv_indices[he] as usize]);
+                    patch.uvs.len() - 1
+                });
+                patch.vertices.push(vertex);
+                patch.uv_indices.push(uv);
+                patch.twins.push(topo.twin(he).and_then(|twin| local_edges.get(&twin).copied()));
+                patch.edge_sharpness.push(self.edge_crease[he]);
+                patch.non_manifold_edges.push(topo.is_non_manifold_edge(he));
+            }
+        }
+        patch
+    }
+}
+
+// Quad faces around a face of some subdivision level, quad `0` being the face itself.
+// Half-edge `4 * quad + corner` starts at `corner` of `quad`, only the faces around
+// the corners of quad `0` are guaranteed to be present.
+#[derive(Default)]
+struct Patch {
+    vertices: Vec<usize>,
+    uv_indices: Vec<usize>,
+    twins: Vec<Option<usize>>,
+    edge_sharpness: Vec<Real>,
+    non_manifold_edges: Vec<bool>,
+    positions: Vec<Vec3>,
+    vertex_sharpness: Vec<Real>,
+    uvs: Vec<Vec3>,
+}
+
+// Values of a patch subdivided with a set of boundary rules, edges between
+// differing `indices` are split
+struct Layer<'a> {
+    indices: &'a [usize],
+    values: &'a [Vec3],
+    rules: BoundaryRules,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VertexRule {
+    Smooth,
+    Crease,
+    Corner,
+}
+
+// Edges around a vertex in the wedge of faces sharing its value
+struct Wedge {
+    /// Outgoing half-edges in `next_vertex_edge()` order.
+    fan: Vec<usize>,
+    closed: bool,
+    /// Half-edge of every edge with its crease and far value, including the
+    /// incoming edge of `fan[0]` if the wedge is open.
+    edges: Vec<(usize, Real, Vec3)>,
+    rule: VertexRule,
+}
+
+#[derive(Clone, Copy, Default)]
+struct PatchPoint {
+    value: Vec3,
+    du: Vec3,
+    dv: Vec3,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum ChildKey {
+    Vertex(usize),
+    Edge(usize),
+    Face(usize),
+}
+
+fn quad_next(he: usize) -> usize {
+    (he & !3) | ((he + 1) & 3)
+}
+
+fn quad_prev(he: usize) -> usize {
+    (he & !3) | ((he + 3) & 3)
+}
+
+impl Patch {
+    fn position_layer(&self, rules: BoundaryRules) -> Layer<'_> {
+        Layer { indices: &self.vertices, values: &self.positions, rules }
+    }
+
+    fn uv_layer(&self, rules: BoundaryRules) -> Layer<'_> {
+        Layer { indices: &self.uv_indices, values: &self.uvs, rules }
+    }
+
+    fn value(&self, layer: &Layer, he: usize) -> Vec3 {
+        layer.values[layer.indices[he]]
+    }
+
+    fn next_vertex_edge(&self, he: usize) -> Option<usize> {
+        self.twins[he].map(quad_next)
+    }
+
+    fn prev_vertex_edge(&self, he: usize) -> Option<usize> {
+        self.twins[quad_prev(he)]
+    }
+
+    fn is_split(&self, layer: &Layer, he: usize) -> bool {
+        match self.twins[he] {
+            Some(twin) => layer.indices[he] != layer.indices[quad_next(twin)]
+                || layer.indices[quad_next(he)] != layer.indices[twin],
+            None => false,
+        }
+    }
+
+    // Crease as used by `ufbx_subdivide_mesh()`, boundaries and splits are fully sharp
+    fn crease(&self, layer: &Layer, he: usize) -> Real {
+        if self.twins[he].is_none() || self.is_split(layer, he) { 1.0 } else { self.edge_sharpness[he] }
+    }
+
+    fn is_hard(&self, layer: &Layer, he: usize) -> bool {
+        layer.rules.sharp_all || self.crease(layer, he) > 0.0
+    }
+
+    fn wedge(&self, layer: &Layer, he: usize) -> Wedge {
+        let limit = self.twins.len();
+        let mut start = he;
+        for _ in 0..limit {
+            match self.prev_vertex_edge(start) {
+                Some(prev) if prev != he && !self.is_split(layer, prev) => start = prev,
+                _ => break,
+            }
+        }
+
+        let mut fan = vec![start];
+        let mut closed = false;
+        loop {
+            let cur = *fan.last().unwrap();
+            if self.is_split(layer, cur) { break }
+            match self.next_vertex_edge(cur) {
+                Some(next) if next == start => { closed = true; break }
+                Some(next) if fan.len() < limit => fan.push(next),
+                _ => break,
+            }
+        }
+
+        let mut edges: Vec<(usize, Real, Vec3)> = fan.iter()
+            .map(|&e| (e, self.crease(layer, e), self.value(layer, quad_next(e))))
+            .collect();
+        if !closed {
+            let incoming = quad_prev(start);
+            edges.push((incoming, self.crease(layer, incoming), self.value(layer, incoming)));
+        }
+
+        let rules = layer.rules;
+        let num_creases = edges.iter().filter(|e| e.1 > 0.0).count();
+        let non_manifold = fan.iter().any(|&e| self.non_manifold_edges[e] || self.non_manifold_edges[quad_prev(e)]);
+        let rule = if num_creases > 2
+            || (rules.sharp_corners && edges.len() == 2 && !closed)
+            || (rules.sharp_splits && !closed)
+            || rules.sharp_all
+            || non_manifold {
+            VertexRule::Corner
+        } else if num_creases == 2 {
+            VertexRule::Crease
+        } else {
+            VertexRule::Smooth
+        };
+        Wedge { fan, closed, edges, rule }
+    }
+
+    // Vertex rule of a corner whose creases do not decay anymore, `None` if they do
+    fn stationary_rule(&self, layer: &Layer, he: usize) -> Option<(Wedge, VertexRule)> {
+        let wedge = self.wedge(layer, he);
+        let vertex_sharpness = self.vertex_sharpness[self.vertices[he]];
+        if !is_stationary(vertex_sharpness) || !wedge.edges.iter().all(|e| is_stationary(e.1)) {
+            return None;
+        }
+        let rule = if vertex_sharpness > 0.0 { VertexRule::Corner } else { wedge.rule };
+        Some((wedge, rule))
+    }
+
+    fn face_point(&self, layer: &Layer, quad: usize) -> Vec3 {
+        let sum = (0..4).fold(Vec3::default(), |sum, k| vec3_add(sum, self.value(layer, quad * 4 + k)));
+        vec3_mul(sum, 0.25)
+    }
+
+    // Edge point following `ufbx_subdivide_mesh()`, fractional creases blend the smooth and sharp rules
+    fn edge_point(&self, layer: &Layer, he: usize, face_points: &[Vec3]) -> Vec3 {
+        let (v0, v1) = (self.value(layer, he), self.value(layer, quad_next(he)));
+        let crease = if layer.rules.sharp_all { 1.0 } else { self.crease(layer, he) };
+        match self.twins[he] {
+            Some(twin) if crease < 1.0 => {
+                let (f0, f1) = (face_points[he / 4], face_points[twin / 4]);
+                let (w0, w1) = (0.25 + 0.25 * crease.max(0.0), 0.25 - 0.25 * crease.max(0.0));
+                vec3_add(vec3_mul(vec3_add(v0, v1), w0), vec3_mul(vec3_add(f0, f1), w1))
+            },
+            _ => vec3_mul(vec3_add(v0, v1), 0.5),
+        }
+    }
+
+    // Vertex point following `ufbx_subdivide_mesh()`, fractional creases blend the smooth and sharp rules
+    fn vertex_point(&self, layer: &Layer, he: usize, face_points: &[Vec3]) -> Vec3 {
+        let wedge = self.wedge(layer, he);
+        let v = self.value(layer, he);
+        let point = match wedge.rule {
+            VertexRule::Corner => v,
+            _ => {
+                let n = wedge.edges.len() as Real;
+                let (v_weight, fe_weight) = ((n - 2.0) / n, 1.0 / (n * n));
+                let mut sum = wedge.edges.iter().fold(Vec3::default(), |sum, e| vec3_add(sum, e.2));
+                for &e in &wedge.fan {
+                    sum = vec3_add(sum, face_points[e / 4]);
+                }
+                let creases: Vec<&(usize, Real, Vec3)> = wedge.edges.iter().filter(|e| e.1 > 0.0).collect();
+                if creases.len() == 2 {
+                    let t = ((creases[0].1 + creases[1].1) * 0.5).clamp(0.0, 1.0);
+                    let ends = vec3_mul(vec3_add(creases[0].2, creases[1].2), 0.125 * t);
+                    let smooth = vec3_add(vec3_mul(v, v_weight * (1.0 - t) + 0.75 * t), vec3_mul(sum, fe_weight * (1.0 - t)));
+                    vec3_add(smooth, ends)
+                } else {
+                    vec3_add(vec3_mul(v, v_weight), vec3_mul(sum, fe_weight))
+                }
+            },
+        };
+        let sharpness = self.vertex_sharpness[self.vertices[he]].min(1.0);
+        if sharpness > 0.0 {
+            vec3_add(vec3_mul(v, sharpness), vec3_mul(point, 1.0 - sharpness))
+        } else {
+            point
+        }
+    }
+
+    // Limit of the value at the vertex of `he` with tangents in the parameterization of
+    // the quad of `he`, the tangents are exact along creases and smooth vertices
+    // but only give directions at extraordinary vertices
+    fn corner_limit(&self, layer: &Layer, he: usize, wedge: &Wedge, rule: VertexRule) -> PatchPoint {
+        let v = self.value(layer, he);
+        let incoming = quad_prev(he);
+        let same_edge = |a: usize, b: usize| a == b || self.twins[a] == Some(b);
+        let creases: Vec<&(usize, Real, Vec3)> = wedge.edges.iter().filter(|e| e.1 > 0.0).collect();
+
+        if rule == VertexRule::Smooth && wedge.closed {
+            let n = wedge.fan.len();
+            let offset = wedge.fan.iter().position(|&e| e == he).unwrap_or(0);
+            let theta = 2.0 * std::f64::consts::PI / n as Real;
+            let a = 1.0 + theta.cos() + (theta * 0.5).cos() * (2.0 * (9.0 + theta.cos())).sqrt();
+            let (mut sum, mut t0, mut t1) = (vec3_mul(v, (n * n) as Real), Vec3::default(), Vec3::default());
+            for i in 0..n {
+                let (e0, e1) = (wedge.fan[(offset + i) % n], wedge.fan[(offset + i + 1) % n]);
+                let (c0, s0) = ((theta * i as Real).cos(), (theta * i as Real).sin());
+                let (c1, s1) = ((theta * (i + 1) as Real).cos(), (theta * (i + 1) as Real).sin());
+                let e = self.value(layer, quad_next(e0));
+                let d = self.value(layer, quad_next(quad_next(e1)));
+                sum = vec3_add(sum, vec3_add(vec3_mul(e, 4.0), d));
+                t0 = vec3_add(t0, vec3_add(vec3_mul(e, a * c0), vec3_mul(d, c0 + c1)));
+                t1 = vec3_add(t1, vec3_add(vec3_mul(e, a * s0), vec3_mul(d, s0 + s1)));
+            }
+            let du = vec3_mul(t0, 1.0 / 12.0);
+            let dv = vec3_mul(vec3_sub(vec3_mul(t0, theta.cos()), vec3_mul(t1, theta.sin())), 1.0 / 12.0);
+            return PatchPoint { value: vec3_mul(sum, 1.0 / (n * (n + 5)) as Real), du, dv };
+        }
+
+        let value = match rule {
+            VertexRule::Crease => vec3_mul(vec3_add(vec3_add(creases[0].2, creases[1].2), vec3_mul(v, 4.0)), 1.0 / 6.0),
+            _ => v,
+        };
+        // Creases are uniform B-spline curves, other directions use the chord
+        let tangent = |edge: usize, end: Vec3| {
+            let on_crease = creases.iter().position(|e| same_edge(e.0, edge));
+            match (rule, on_crease) {
+                (VertexRule::Crease, Some(ix)) => vec3_mul(vec3_sub(end, creases[1 - ix].2), 0.5),
+                (VertexRule::Corner, Some(_)) => vec3_sub(end, v),
+                _ => vec3_sub(end, value),
+            }
+        };
+        PatchPoint {
+            value,
+            du: tangent(he, self.value(layer, quad_next(he))),
+            dv: tangent(incoming, self.value(layer, incoming)),
+        }
+    }
+
+    // B-spline control points of corner `he` of quad `0` diagonally opposite to the quad
+    // and opposite to its outgoing and incoming edges, `None` unless the surface near the
+    // corner is a B-spline patch, values across hard edges are extrapolated linearly
+    fn corner_grid(&self, layer: &Layer, he: usize) -> Option<[Vec3; 3]> {
+        let (wedge, rule) = self.stationary_rule(layer, he)?;
+        let value = |e: usize| self.value(layer, e);
+
+        // Faces around the corner on the side of quad `0`, bounded by hard edges
+        let mut prev_side = Vec::new();
+        let mut e = he;
+        while prev_side.len() < 4 {
+            match self.prev_vertex_edge(e) {
+                Some(prev) if prev != he && !self.is_hard(layer, prev) => { prev_side.push(prev); e = prev },
+                _ => break,
+            }
+        }
+        let mut next_side = Vec::new();
+        let mut e = he;
+        while prev_side.len() + next_side.len() < 4 && !self.is_hard(layer, e) {
+            match self.next_vertex_edge(e) {
+                Some(next) if next != he && !prev_side.contains(&next) => { next_side.push(next); e = next },
+                _ => break,
+            }
+        }
+
+        let (g, n, p, d) = (value(he), value(quad_next(he)), value(quad_prev(he)), value(quad_next(quad_next(he))));
+        let extrapolate = |a: Vec3, b: Vec3| vec3_sub(vec3_mul(a, 2.0), b);
+        let faces = 1 + prev_side.len() + next_side.len();
+        match rule {
+            VertexRule::Smooth if wedge.closed && wedge.fan.len() == 4 && prev_side.len() == 3 && !self.is_hard(layer, he) => {
+                let e2 = prev_side[1];
+                Some([value(quad_next(quad_next(e2))), value(quad_next(e2)), value(quad_prev(e2))])
+            },
+            VertexRule::Crease if faces == 2 && prev_side.len() == 1 => {
+                let e1 = prev_side[0];
+                let a = value(quad_prev(e1));
+                Some([extrapolate(a, value(quad_next(quad_next(e1)))), a, extrapolate(g, p)])
+            },
+            VertexRule::Crease if faces == 2 => {
+                let e3 = next_side[0];
+                let b = value(quad_next(e3));
+                Some([extrapolate(b, value(quad_next(quad_next(e3)))), extrapolate(g, n), b])
+            },
+            VertexRule::Corner if faces == 1 => {
+                let c = vec3_add(vec3_sub(vec3_mul(g, 4.0), vec3_mul(vec3_add(n, p), 2.0)), d);
+                Some([c, extrapolate(g, n), extrapolate(g, p)])
+            },
+            _ => None,
+        }
+    }
+
+    fn bspline_grid(&self, layer: &Layer) -> Option<[[Vec3; 4]; 4]> {
+        let grid_pos: [(isize, isize); 4] = [(1, 1), (2, 1), (2, 2), (1, 2)];
+        let mut grid = [[Vec3::default(); 4]; 4];
+        for k in 0..4 {
+            let [diagonal, opposite_next, opposite_prev] = self.corner_grid(layer, k)?;
+            let (gx, gy) = grid_pos[k];
+            let (nx, ny) = grid_pos[(k + 1) % 4];
+            let (px, py) = grid_pos[(k + 3) % 4];
+            let (dnx, dny) = (nx - gx, ny - gy);
+            let (dpx, dpy) = (px - gx, py - gy);
+            let mut set = |x: isize, y: isize, value: Vec3| grid[y as usize][x as usize] = value;
+            set(gx, gy, self.value(layer, k));
+            set(gx - dnx, gy - dny, opposite_next);
+            set(gx - dpx, gy - dpy, opposite_prev);
+            set(gx - dnx - dpx, gy - dny - dpy, diagonal);
+        }
+        Some(grid)
+    }
+
+    // Exact limit at `(u, v)` of quad `0` if it can be evaluated without subdividing further
+    fn evaluate(&self, layer: &Layer, u: Real, v: Real) -> Option<PatchPoint> {
+        if let Some(grid) = self.bspline_grid(layer) {
+            return Some(bspline_point(&grid, u, v));
+        }
+        if u == 0.0 && v == 0.0 && self.corner_grid(layer, 0).is_none() {
+            let (wedge, rule) = self.stationary_rule(layer, 0)?;
+            return Some(self.corner_limit(layer, 0, &wedge, rule));
+        }
+        None
+    }
+
+    // Same as `evaluate()` without derivatives, which allows following hard edges between corners
+    fn evaluate_value(&self, layer: &Layer, u: Real, v: Real) -> Option<Vec3> {
+        if let Some(point) = self.evaluate(layer, u, v) {
+            return Some(point.value);
+        }
+        // The limit of a hard edge between two corners is the edge itself
+        let is_corner = |he: usize| matches!(self.stationary_rule(layer, he), Some((_, VertexRule::Corner)));
+        let linear = |he: usize, t: Real| -> Option<Vec3> {
+            if self.is_hard(layer, he) && is_corner(he) && is_corner(quad_next(he)) {
+                let (a, b) = (self.value(layer, he), self.value(layer, quad_next(he)));
+                Some(vec3_add(vec3_mul(a, 1.0 - t), vec3_mul(b, t)))
+            } else {
+                None
+            }
+        };
+        match (u == 0.0, v == 0.0) {
+            (_, true) => linear(0, u),
+            (true, false) => linear(3, 1.0 - v),
+            _ => None,
+        }
+    }
+
+    // Bilinear interpolation of the corner limits, used once the patch is too small to matter
+    fn bilinear(&self, layer: &Layer, u: Real, v: Real) -> PatchPoint {
+        let c = [0, 1, 2, 3].map(|he| {
+            let wedge = self.wedge(layer, he);
+            let rule = wedge.rule;
+            self.corner_limit(layer, he, &wedge, rule).value
+        });
+        PatchPoint {
+            value: bilinear(c, u, v),
+            du: vec3_add(vec3_mul(vec3_sub(c[1], c[0]), 1.0 - v), vec3_mul(vec3_sub(c[2], c[3]), v)),
+            dv: vec3_add(vec3_mul(vec3_sub(c[3], c[0]), 1.0 - u), vec3_mul(vec3_sub(c[2], c[1]), u)),
+        }
+    }
+
+    // Subdivides the patch once and returns the patch around the child of quad `0` spawned by `corner`
+    fn subdivide(&self, rules: BoundaryRules, uv_rules: BoundaryRules, corner: usize) -> Patch {
+        let positions = self.position_layer(rules);
+        let uvs = self.uv_layer(uv_rules);
+        let num_quads = self.twins.len() / 4;
+        let position_faces: Vec<Vec3> = (0..num_quads).map(|q| self.face_point(&positions, q)).collect();
+        let uv_faces: Vec<Vec3> = (0..num_quads).map(|q| self.face_point(&uvs, q)).collect();
+        let edge_id = |he: usize| self.twins[he].map_or(he, |twin| twin.min(he));
+        let uv_edge_id = |he: usize| if self.is_split(&uvs, he) { he } else { edge_id(he) };
+        let uv_vertex_id = |he: usize| {
+            let wedge = self.wedge(&uvs, he);
+            if wedge.closed { *wedge.fan.iter().min().unwrap() } else { wedge.fan[0] }
+        };
+
+        // Children sharing a vertex with the target child, indexed by the half-edge spawning them
+        let target = [edge_id(corner), edge_id(quad_prev(corner))];
+        let touches = |he: usize| he < 4
+            || self.vertices[he] == self.vertices[corner]
+            || target.contains(&edge_id(he))
+            || target.contains(&edge_id(quad_prev(he)));
+        let mut spawners = vec![corner];
+        spawners.extend((0..self.twins.len()).filter(|&he| he != corner && touches(he)));
+        let mut child_of = vec![None; self.twins.len()];
+        for (child, &he) in spawners.iter().enumerate() {
+            child_of[he] = Some(child);
+        }
+
+        let mut patch = Patch::default();
+        let mut vertex_ids = HashMap::new();
+        let mut uv_ids = HashMap::new();
+        for &he in &spawners {
+            let prev = quad_prev(he);
+            let corners = [
+                (ChildKey::Vertex(self.vertices[he]), ChildKey::Vertex(uv_vertex_id(he))),
+                (ChildKey::Edge(edge_id(he)), ChildKey::Edge(uv_edge_id(he))),
+                (ChildKey::Face(he / 4), ChildKey::Face(he / 4)),
+                (ChildKey::Edge(edge_id(prev)), ChildKey::Edge(uv_edge_id(prev))),
+            ];
+            let point = |layer: &Layer, faces: &[Vec3], k: usize| match k {
+                0 => self.vertex_point(layer, he, faces),
+                1 => self.edge_point(layer, he, faces),
+                2 => faces[he / 4],
+                _ => self.edge_point(layer, prev, faces),
+            };
+            for (k, &(vertex_key, uv_key)) in corners.iter().enumerate() {
+                let vertex = *vertex_ids.entry(vertex_key).or_insert_with(|| {
+                    patch.positions.push(point(&positions, &position_faces, k));
+                    let sharpness = if k == 0 { decay_sharpness(self.vertex_sharpness[self.vertices[he]]) } else { 0.0 };
+                    patch.vertex_sharpness.push(sharpness);
+                    patch.positions.len() - 1
+                });
+                let uv = *uv_ids.entry(uv_key).or_insert_with(|| {
+                    patch.uvs.push(point(&uvs, &uv_faces, k));
+                    patch.uvs.len() - 1
+                });
+                patch.vertices.push(vertex);
+                patch.uv_indices.push(uv);
+            }
+
+            let child = |he: usize, k: usize| child_of[he].map(|c| c * 4 + k);
+            patch.twins.extend_from_slice(&[
+                self.twins[he].and_then(|twin| child(quad_next(twin), 3)),
+                child(quad_next(he), 2),
+                child(prev, 1),
+                self.twins[prev].and_then(|twin| child(twin, 0)),
+            ]);
+            patch.edge_sharpness.extend_from_slice(&[
+                decay_sharpness(self.edge_sharpness[he]),
+                0.0,
+                0.0,
+                decay_sharpness(self.edge_sharpness[prev]),
+            ]);
+            patch.non_manifold_edges.extend_from_slice(&[self.non_manifold_edges[he], false, false, self.non_manifold_edges[prev]]);
+        }
+        patch
+    }
+}
+
+fn bspline_basis(t: Real) -> ([Real; 4], [Real; 4]) {
+    let s = 1.0 - t;
+    let b = [
+        s * s * s / 6.0,
+        (3.0 * t * t * t - 6.0 * t * t + 4.0) / 6.0,
+        (-3.0 * t * t * t + 3.0 * t * t + 3.0 * t + 1.0) / 6.0,
+        t * t * t / 6.0,
+    ];
+    let d = [
+        -0.5 * s * s,
+        1.5 * t * t - 2.0 * t,
+        -1.5 * t * t + t + 0.5,
+        0.5 * t * t,
+    ];
+    (b, d)
+}
+
+fn bspline_point(grid: &[[Vec3; 4]; 4], u: Real, v: Real) -> PatchPoint {
+    let (bu, du) = bspline_basis(u);
+    let (bv, dv) = bspline_basis(v);
+    let mut point = PatchPoint::default();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &p) in row.iter().enumerate() {
+            point.value = vec3_add(point.value, vec3_mul(p, bu[x] * bv[y]));
+            point.du = vec3_add(point.du, vec3_mul(p, du[x] * bv[y]));
+            point.dv = vec3_add(point.dv, vec3_mul(p, bu[x] * dv[y]));
+        }
+    }
+    point
+}
+
+fn bilinear(c: [Vec3; 4], u: Real, v: Real) -> Vec3 {
+    let a = vec3_add(vec3_mul(c[0], 1.0 - u), vec3_mul(c[1], u));
+    let b = vec3_add(vec3_mul(c[3], 1.0 - u), vec3_mul(c[2], u));
+    vec3_add(vec3_mul(a, 1.0 - v), vec3_mul(b, v))
+}
+
+/// Catmull-Clark limit surface of a mesh.
+///
+/// Patches around extraordinary vertices, creases and boundaries are subdivided
+/// towards the evaluated point until it lies on a regular bicubic patch, or is
+/// the irregular vertex itself, which is evaluated with the limit stencils.
+/// Fractional creases are subdivided like `ufbx_subdivide_mesh()` until they
+/// have decayed. Derivatives at extraordinary vertices only give the tangent
+/// directions as the parameterization is singular there.
+///
+/// Faces are parameterized per corner like Ptex: corner `(0, 0)` is the
+/// vertex, `(1, 0)` the midpoint of the outgoing edge, `(1, 1)` the face
+/// center and `(0, 1)` the midpoint of the incoming edge. Quads can also be
+/// evaluated with a single `(u, v)` over the whole face.
+pub struct LimitSurface {
+    rules: BoundaryRules,
+    uv_rules: BoundaryRules,
+    faces: Vec<Face>,
+    vertex_first_index: Vec<Option<usize>>,
+    control_positions: Vec<Vec3>,
+    /// Levels `1..=max_level`, all faces are quads.
+    levels: Vec<Level>,
+}
+
+impl LimitSurface {
+    #[allow(clippy::result_large_err)]
+    pub fn new(mesh: &Mesh, opts: &LimitSurfaceOpts) -> Result<LimitSurface> {
+        let max_level = opts.max_level.max(1);
+        let boundary = resolve_boundary(opts.boundary, mesh.subdivision_boundary);
+        let uv_boundary = resolve_boundary(opts.uv_boundary, mesh.subdivision_uv_boundary);
+        let rules = BoundaryRules::new(boundary);
+        let uv_rules = BoundaryRules::new(uv_boundary);
+
+        let topo = MeshTopology::new(mesh);
+        let vertex_first_index = (0..mesh.num_vertices).map(|v| topo.vertex_edges(v).next()).collect();
+
+        let subdivide_opts = || SubdivideOpts {
+            boundary,
+            uv_boundary,
+            ignore_normals: true,
+            ..Default::default()
+        };
+        let mut levels = Vec::with_capacity(max_level);
+        let mut current = mesh.subdivide(1, subdivide_opts())?;
+        for level in 1..=max_level {
+            levels.push(Level::new(&current));
+            if level < max_level {
+                current = current.subdivide(1, subdivide_opts())?;
+            }
+        }
+
+        Ok(LimitSurface {
+            rules,
+            uv_rules,
+            faces: mesh.faces.to_vec(),
+            vertex_first_index,
+            control_positions: mesh.vertices.to_vec(),
+            levels,
+        })
+    }
+
+    /// Limit positions of the control vertices, indexed like `Mesh::vertices`.
+    pub fn vertex_limit_positions(&self) -> Vec<Vec3> {
+        self.vertex_first_index.iter().zip(&self.control_positions).map(|(&first, &control)| {
+            match first {
+                // Corner `0` of the face spawned by index `ix` is the vertex point
+                Some(ix) => self.evaluate_level(ix, 0.0, 0.0).position,
+                None => control,
+            }
+        }).collect()
+    }
+
+    /// Evaluates a quad face with `(0, 0)` at its first corner and `(1, 0)` at the second one.
+    /// Returns `None` if `face` is out of bounds or not a quad, use `evaluate_corner()` for other faces.
+    pub fn evaluate(&self, face: usize, u: Real, v: Real) -> Option<LimitPoint> {
+        if self.faces.get(face)?.num_indices != 4 {
+            return None;
+        }
+        let (corner, s, t) = child_param(u, v);
+        let mut point = self.evaluate_corner(face, corner, s, t)?;
+        let (du, dv) = scale_child_derivatives(point.du, point.dv, corner);
+        point.du = du;
+        point.dv = dv;
+        Some(point)
+    }
+
+    /// Evaluates the region of `face` closest to `corner`, see `LimitSurface` for the parameterization.
+    /// Returns `None` if `face` or `corner` is out of bounds.
+    pub fn evaluate_corner(&self, face: usize, corner: usize, u: Real, v: Real) -> Option<LimitPoint> {
+        let face = self.faces.get(face)?;
+        if corner >= face.num_indices as usize {
+            return None;
+        }
+        let u = u.clamp(0.0, 1.0);
+        let v = v.clamp(0.0, 1.0);
+        Some(self.evaluate_level(face.index_begin as usize + corner, u, v))
+    }
+
+    // Evaluates `face` of the first level, descending through the precomputed
+    // levels and then subdividing locally until the positions and UVs are resolved
+    fn evaluate_level(&self, face: usize, u: Real, v: Real) -> LimitPoint {
+        let (mut face, mut u, mut v) = (face, u, v);
+        let mut local: Option<Patch> = None;
+        let mut corners = Vec::new();
+        let mut position: Option<(PatchPoint, usize)> = None;
+        let mut uv: Option<Vec3> = None;
+        let mut depth = 0;
+        loop {
+            let patch = match local.take() {
+                Some(patch) => patch,
+                None => self.levels[depth].patch(face),
+            };
+            let positions = patch.position_layer(self.rules);
+            let uvs = patch.uv_layer(self.uv_rules);
+            if position.is_none() {
+                position = patch.evaluate(&positions, u, v).map(|point| (point, depth));
+            }
+            if uv.is_none() {
+                uv = patch.evaluate_value(&uvs, u, v);
+            }
+            if depth + 1 == MAX_DEPTH {
+                position.get_or_insert_with(|| (patch.bilinear(&positions, u, v), depth));
+                uv.get_or_insert_with(|| patch.bilinear(&uvs, u, v).value);
+            }
+
+            if let (Some((point, resolved)), Some(uv)) = (position, uv) {
+                let (mut du, mut dv) = (point.du, point.dv);
+                for &corner in corners[..resolved].iter().rev() {
+                    let (su, sv) = scale_child_derivatives(du, dv, corner);
+                    du = su;
+                    dv = sv;
+                }
+                return LimitPoint {
+                    position: point.value,
+                    du,
+                    dv,
+                    normal: vec3_normalize(vec3_cross(du, dv)),
+                    uv: Vec2 { x: uv.x, y: uv.y },
+                };
+            }
+
+            let (corner, s, t) = child_param(u, v);
+            corners.push(corner);
+            if depth + 1 < self.levels.len() {
+                face = self.levels[depth].faces[face].index_begin as usize + corner;
+            } else {
+                local = Some(patch.subdivide(self.rules, self.uv_rules, corner));
+            }
+            u = s;
+            v = t;
+            depth += 1;
+        }
+    }
+}
+
+// Maps `(u, v)` of a quad to the child face spawned by `corner` and its local parameters
+fn child_param(u: Real, v: Real) -> (usize, Real, Real) {
+    match (u >= 0.5, v >= 0.5) {
+        (false, false) => (0, 2.0 * u, 2.0 * v),
+        (true, false) => (1, 2.0 * v, 2.0 - 2.0 * u),
+        (true, true) => (2, 2.0 - 2.0 * u, 2.0 - 2.0 * v),
+        (false, true) => (3, 2.0 - 2.0 * v, 2.0 * u),
+    }
+}
+
+fn scale_child_derivatives(ds: Vec3, dt: Vec3, corner: usize) -> (Vec3, Vec3) {
+    let (du, dv) = match corner {
+        0 => (ds, dt),
+        1 => (vec3_mul(dt, -1.0), ds),
+        2 => (vec3_mul(ds, -1.0), vec3_mul(dt, -1.0)),
+        _ => (dt, vec3_mul(ds, -1.0)),
+    };
+    (vec3_mul(du, 2.0), vec3_mul(dv, 2.0))
+}
@@ -0,0 +1,518 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use crate::generated::{Material, MaterialMap, MaterialPbrMaps, Texture, TextureType, ShaderTextureType, PropType};
+use crate::prop_value::PropValue;
+use crate::shader_graph::{ShaderGraph, ShaderNodeKind};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MaterialXSurface {
+    StandardSurface,
+    OpenPbrSurface,
+}
+
+#[derive(Clone, Debug)]
+pub struct MaterialXOpts {
+    pub surface: MaterialXSurface,
+    pub passthrough_shaders: bool,
+}
+
+impl Default for MaterialXOpts {
+    fn default() -> Self {
+        MaterialXOpts {
+            surface: MaterialXSurface::StandardSurface,
+            passthrough_shaders: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum InputType {
+    Float,
+    Color3,
+    Vector3,
+    Normal,
+}
+
+impl InputType {
+    fn name(self) -> &'static str {
+        match self {
+            InputType::Float => "float",
+            InputType::Color3 => "color3",
+            InputType::Vector3 | InputType::Normal => "vector3",
+        }
+    }
+}
+
+struct InputDef {
+    map: fn(&MaterialPbrMaps) -> &MaterialMap,
+    name: &'static str,
+    standard_surface: Option<(&'static str, InputType)>,
+    open_pbr_surface: Option<(&'static str, InputType)>,
+}
+
+macro_rules! input_def {
+    ($field:ident, $std:expr, $open:expr) => {
+        InputDef { map: |pbr| &pbr.$field, name: stringify!($field), standard_surface: $std, open_pbr_surface: $open }
+    };
+}
+
+use InputType::{Float, Color3, Vector3, Normal};
+
+const INPUT_DEFS: &[InputDef] = &[
+    input_def!(base_factor, Some(("base", Float)), Some(("base_weight", Float))),
+    input_def!(base_color, Some(("base_color", Color3)), Some(("base_color", Color3))),
+    input_def!(roughness, Some(("specular_roughness", Float)), Some(("specular_roughness", Float))),
+    input_def!(metalness, Some(("metalness", Float)), Some(("base_metalness", Float))),
+    input_def!(diffuse_roughness, Some(("diffuse_roughness", Float)), Some(("base_diffuse_roughness", Float))),
+    input_def!(specular_factor, Some(("specular", Float)), Some(("specular_weight", Float))),
+    input_def!(specular_color, Some(("specular_color", Color3)), Some(("specular_color", Color3))),
+    input_def!(specular_ior, Some(("specular_IOR", Float)), Some(("specular_ior", Float))),
+    input_def!(specular_anisotropy, Some(("specular_anisotropy", Float)), Some(("specular_roughness_anisotropy", Float))),
+    input_def!(specular_rotation, Some(("specular_rotation", Float)), None),
+    input_def!(transmission_factor, Some(("transmission", Float)), Some(("transmission_weight", Float))),
+    input_def!(transmission_color, Some(("transmission_color", Color3)), Some(("transmission_color", Color3))),
+    input_def!(transmission_depth, Some(("transmission_depth", Float)), Some(("transmission_depth", Float))),
+    input_def!(transmission_scatter, Some(("transmission_scatter", Color3)), Some(("transmission_scatter", Color3))),
+    input_def!(transmission_scatter_anisotropy, Some(("transmission_scatter_anisotropy", Float)), Some(("transmission_scatter_anisotropy", Float))),
+    input_def!(transmission_dispersion, Some(("transmission_dispersion", Float)), Some(("transmission_dispersion_scale", Float))),
+    input_def!(transmission_roughness, None, None),
+    input_def!(transmission_extra_roughness, Some(("transmission_extra_roughness", Float)), None),
+    input_def!(transmission_priority, None, None),
+    input_def!(transmission_enable_in_aov, None, None),
+    input_def!(subsurface_factor, Some(("subsurface", Float)), Some(("subsurface_weight", Float))),
+    input_def!(subsurface_color, Some(("subsurface_color", Color3)), Some(("subsurface_color", Color3))),
+    input_def!(subsurface_radius, Some(("subsurface_radius", Color3)), Some(("subsurface_radius_scale", Color3))),
+    input_def!(subsurface_scale, Some(("subsurface_scale", Float)), Some(("subsurface_radius", Float))),
+    input_def!(subsurface_anisotropy, Some(("subsurface_anisotropy", Float)), Some(("subsurface_scatter_anisotropy", Float))),
+    input_def!(subsurface_tint_color, None, None),
+    input_def!(subsurface_type, None, None),
+    input_def!(sheen_factor, Some(("sheen", Float)), Some(("fuzz_weight", Float))),
+    input_def!(sheen_color, Some(("sheen_color", Color3)), Some(("fuzz_color", Color3))),
+    input_def!(sheen_roughness, Some(("sheen_roughness", Float)), Some(("fuzz_roughness", Float))),
+    input_def!(coat_factor, Some(("coat", Float)), Some(("coat_weight", Float))),
+    input_def!(coat_color, Some(("coat_color", Color3)), Some(("coat_color", Color3))),
+    input_def!(coat_roughness, Some(("coat_roughness", Float)), Some(("coat_roughness", Float))),
+    input_def!(coat_ior, Some(("coat_IOR", Float)), Some(("coat_ior", Float))),
+    input_def!(coat_anisotropy, Some(("coat_anisotropy", Float)), Some(("coat_roughness_anisotropy", Float))),
+    input_def!(coat_rotation, Some(("coat_rotation", Float)), None),
+    input_def!(coat_normal, Some(("coat_normal", Normal)), Some(("geometry_coat_normal", Normal))),
+    input_def!(coat_affect_base_color, Some(("coat_affect_color", Float)), None),
+    input_def!(coat_affect_base_roughness, Some(("coat_affect_roughness", Float)), None),
+    input_def!(thin_film_factor, None, Some(("thin_film_weight", Float))),
+    input_def!(thin_film_thickness, Some(("thin_film_thickness", Float)), Some(("thin_film_thickness", Float))),
+    input_def!(thin_film_ior, Some(("thin_film_IOR", Float)), Some(("thin_film_ior", Float))),
+    input_def!(emission_factor, Some(("emission", Float)), Some(("emission_luminance", Float))),
+    input_def!(emission_color, Some(("emission_color", Color3)), Some(("emission_color", Color3))),
+    input_def!(opacity, Some(("opacity", Color3)), Some(("geometry_opacity", Float))),
+    input_def!(indirect_diffuse, None, None),
+    input_def!(indirect_specular, None, None),
+    input_def!(normal_map, Some(("normal", Normal)), Some(("geometry_normal", Normal))),
+    input_def!(tangent_map, Some(("tangent", Vector3)), Some(("geometry_tangent", Vector3))),
+    input_def!(displacement_map, None, None),
+    input_def!(matte_factor, None, None),
+    input_def!(matte_color, None, None),
+    input_def!(ambient_occlusion, None, None),
+];
+
+struct MtlxWriter<'a> {
+    opts: &'a MaterialXOpts,
+    nodes: String,
+    names: HashSet<String>,
+    emitted: HashMap<(u32, &'static str), String>,
+    // Graph nodes whose inputs are being emitted, used to cut cycles
+    in_progress: HashSet<(u32, &'static str)>,
+}
+
+// Graph node whose inputs are being emitted by `MtlxWriter::graph_node()`
+struct PendingGraphNode {
+    node: usize,
+    ty: &'static str,
+    next_input: usize,
+    inputs: String,
+}
+
+fn escape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn comment_text(s: &str) -> String {
+    let mut result = s.to_string();
+    while result.contains("--") {
+        result = result.replace("--", "- -");
+    }
+    result
+}
+
+fn identifier(s: &str) -> String {
+    let mut result: String = s.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if !result.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        result.insert(0, '_');
+    }
+    result
+}
+
+fn format_value(map: &MaterialMap, ty: InputType, invert: bool) -> String {
+    let v = map.value_vec4;
+    let x = if invert { 1.0 - v.x } else { v.x };
+    match ty {
+        InputType::Float => format!("{}", x),
+        InputType::Color3 if map.value_components == 1 => format!("{}, {}, {}", x, x, x),
+        InputType::Color3 | InputType::Vector3 | InputType::Normal => format!("{}, {}, {}", v.x, v.y, v.z),
+    }
+}
+
+fn texture_enabled(map: &MaterialMap) -> Option<&Texture> {
+    map.texture.as_deref().filter(|_| map.texture_enabled)
+}
+
+fn is_set(map: &MaterialMap) -> bool {
+    let v = map.value_vec4;
+    texture_enabled(map).is_some() || (map.has_value && (v.x != 0.0 || v.y != 0.0 || v.z != 0.0))
+}
+
+fn prop_input_type(prop_type: PropType) -> Option<&'static str> {
+    match prop_type {
+        PropType::Boolean => Some("boolean"),
+        PropType::Integer => Some("integer"),
+        PropType::Number | PropType::Distance => Some("float"),
+        PropType::Vector | PropType::Translation | PropType::Rotation | PropType::Scaling => Some("vector3"),
+        PropType::Color => Some("color3"),
+        PropType::ColorWithAlpha => Some("color4"),
+        PropType::String => Some("string"),
+        _ => None,
+    }
+}
+
+fn format_prop_value(ty: &str, value: &PropValue) -> String {
+    match value {
+        PropValue::Int(v) if ty == "boolean" => (if *v != 0 { "true" } else { "false" }).to_string(),
+        PropValue::Int(v) => format!("{}", v),
+        PropValue::String(v) => v.clone(),
+        PropValue::Number(v) => match ty {
+            "float" => format!("{}", v.x),
+            "color4" => format!("{}, {}, {}, {}", v.x, v.y, v.z, v.w),
+            _ => format!("{}, {}, {}", v.x, v.y, v.z),
+        },
+    }
+}
+
+impl MtlxWriter<'_> {
+    fn unique_name(&mut self, base: &str) -> String {
+        let base = identifier(base);
+        let mut name = base.clone();
+        let mut counter = 1;
+        while !self.names.insert(name.clone()) {
+            name = format!("{}_{}", base, counter);
+            counter += 1;
+        }
+        name
+    }
+
+    fn annotate(&mut self, text: &str) {
+        writeln!(self.nodes, "  <!-- ufbx: {} -->", comment_text(text)).unwrap();
+    }
+
+    fn texcoord(&mut self, texture: &Texture) -> String {
+        let name = self.unique_name(&format!("{}_texcoord", texture.element.name));
+        if texture.uv_set.is_empty() {
+            writeln!(self.nodes, "  <texcoord name=\"{}\" type=\"vector2\" />", name).unwrap();
+        } else {
+            writeln!(self.nodes, "  <geompropvalue name=\"{}\" type=\"vector2\">", name).unwrap();
+            writeln!(self.nodes, "    <input name=\"geomprop\" type=\"string\" value=\"{}\" />", escape(&texture.uv_set)).unwrap();
+            writeln!(self.nodes, "  </geompropvalue>").unwrap();
+        }
+
+        if !texture.has_uv_transform {
+            return name;
+        }
+        let t = texture.uv_transform;
+        let rotate = 2.0 * t.rotation.z.atan2(t.rotation.w);
+        let place = self.unique_name(&format!("{}_place2d", texture.element.name));
+        writeln!(self.nodes, "  <place2d name=\"{}\" type=\"vector2\">", place).unwrap();
+        writeln!(self.nodes, "    <input name=\"texcoord\" type=\"vector2\" nodename=\"{}\" />", name).unwrap();
+        writeln!(self.nodes, "    <input name=\"offset\" type=\"vector2\" value=\"{}, {}\" />", t.translation.x, t.translation.y).unwrap();
+        writeln!(self.nodes, "    <input name=\"scale\" type=\"vector2\" value=\"{}, {}\" />", t.scale.x, t.scale.y).unwrap();
+        writeln!(self.nodes, "    <input name=\"rotate\" type=\"float\" value=\"{}\" />", rotate.to_degrees()).unwrap();
+        writeln!(self.nodes, "  </place2d>").unwrap();
+        place
+    }
+
+    fn image(&mut self, texture: &Texture, ty: &'static str) -> String {
+        let key = (texture.element.element_id, ty);
+        if let Some(name) = self.emitted.get(&key) {
+            return name.clone();
+        }
+
+        let texcoord = self.texcoord(texture);
+        let name = self.unique_name(&format!("{}_image", texture.element.name));
+        let filename = if texture.relative_filename.is_empty() { &texture.filename } else { &texture.relative_filename };
+        writeln!(self.nodes, "  <image name=\"{}\" type=\"{}\">", name, ty).unwrap();
+        writeln!(self.nodes, "    <input name=\"file\" type=\"filename\" value=\"{}\" />", escape(&filename.replace('\\', "/"))).unwrap();
+        writeln!(self.nodes, "    <input name=\"texcoord\" type=\"vector2\" nodename=\"{}\" />", texcoord).unwrap();
+        writeln!(self.nodes, "  </image>").unwrap();
+        self.emitted.insert(key, name.clone());
+        name
+    }
+
+    /// Starts emitting `node` by pushing it to `stack`, returns the result directly instead
+    /// if it has already been emitted, can't be mapped or is part of a cycle.
+    fn begin_graph_node(&mut self, graph: &ShaderGraph, node: usize, ty: &'static str, stack: &mut Vec<PendingGraphNode>) -> Option<Option<String>> {
+        let shader = &graph.nodes[node];
+        let key = (shader.element_id, ty);
+        if let Some(name) = self.emitted.get(&key) {
+            return Some(Some(name.clone()));
+        }
+        if shader.kind != ShaderNodeKind::Shader || shader.shader_name.is_empty() {
+            return Some(None);
+        }
+        if !self.in_progress.insert(key) {
+            self.annotate(&format!("{} is part of a shader cycle, the connection is cut", shader.name));
+            return Some(None);
+        }
+        stack.push(PendingGraphNode { node, ty, next_input: 0, inputs: String::new() });
+        None
+    }
+
+    /// Emits a passthrough node for `node` in `graph`, returns `None` if it can't be mapped.
+    /// Upstream nodes are emitted first using an explicit stack as shader networks can be arbitrarily deep.
+    fn graph_node(&mut self, graph: &ShaderGraph, node: usize, textures: &HashMap<u32, &Texture>, ty: &'static str) -> Option<String> {
+        let mut stack = Vec::new();
+        if let Some(result) = self.begin_graph_node(graph, node, ty, &mut stack) {
+            return result;
+        }
+
+        // Result of the last finished node, consumed by the input of its user that is waiting for it
+        let mut returned: Option<Option<String>> = None;
+        while let Some(top) = stack.last_mut() {
+            let shader = &graph.nodes[top.node];
+            let input = match shader.inputs.get(top.next_input) {
+                Some(input) => input,
+                None => {
+                    let done = stack.pop().unwrap();
+                    let name = self.unique_name(&shader.name);
+                    let category = identifier(&shader.shader_name);
+                    writeln!(self.nodes, "  <{} name=\"{}\" type=\"{}\">", category, name, done.ty).unwrap();
+                    self.nodes.push_str(&done.inputs);
+                    writeln!(self.nodes, "  </{}>", category).unwrap();
+                    let key = (shader.element_id, done.ty);
+                    self.in_progress.remove(&key);
+                    self.emitted.insert(key, name.clone());
+                    if stack.is_empty() {
+                        return Some(name);
+                    }
+                    returned = Some(Some(name));
+                    continue;
+                },
+            };
+
+            let (node, ty) = (top.node, top.ty);
+            let edge = graph.edges.iter().find(|e| e.to == node && e.input == input.name);
+            let input_type = match prop_input_type(input.prop_type) {
+                Some(t) => t,
+                None if edge.is_some() => ty,
+                None => {
+                    self.annotate(&format!("{} input '{}' has no MaterialX type", shader.name, input.name));
+                    top.next_input += 1;
+                    continue;
+                },
+            };
+
+            let mut line = None;
+            if let Some(edge) = edge {
+                let source = &graph.nodes[edge.from];
+                let upstream = match source.kind {
+                    ShaderNodeKind::Shader => match returned.take() {
+                        Some(result) => result,
+                        None => match self.begin_graph_node(graph, edge.from, input_type, &mut stack) {
+                            Some(result) => result,
+                            // Revisit this input once the upstream node is done
+                            None => continue,
+                        },
+                    },
+                    ShaderNodeKind::File => textures.get(&source.element_id).map(|t| self.image(t, input_type)),
+                    _ => None,
+                };
+                match upstream {
+                    Some(upstream) => line = Some(format!("    <input name=\"{}\" type=\"{}\" {} />",
+                        escape(&input.name), input_type, connection(&upstream, edge.output_index))),
+                    None => self.annotate(&format!("{} input '{}' is connected to unmappable {:?} texture '{}'",
+                        shader.name, input.name, source.kind, source.name)),
+                }
+            }
+            if line.is_none() {
+                line = input.value.as_ref().map(|value| format!("    <input name=\"{}\" type=\"{}\" value=\"{}\" />",
+                    escape(&input.name), input_type, escape(&format_prop_value(input_type, value))));
+            }
+
+            let top = stack.last_mut().unwrap();
+            if let Some(line) = line {
+                writeln!(top.inputs, "{}", line).unwrap();
+            }
+            top.next_input += 1;
+        }
+        None
+    }
+
+    /// Emits the node network for `texture`, returns its output node and output index.
+    fn texture(&mut self, material: &Material, graph: &mut Option<ShaderGraph>, texture: &Texture, ty: &'static str) -> Option<(String, i64)> {
+        match texture.type_ {
+            TextureType::File => Some((self.image(texture, ty), 0)),
+            TextureType::Shader if self.opts.passthrough_shaders => {
+                let graph = graph.get_or_insert_with(|| ShaderGraph::from_material(material));
+                let (source, output_index) = match texture.shader.as_ref().and_then(|s| s.main_texture.as_ref().map(|m| (s, m))) {
+                    Some((shader, main)) if shader.type_ == ShaderTextureType::SelectOutput => (&**main, shader.main_texture_output_index),
+                    _ => (texture, 0),
+                };
+                let node = graph.node_by_element(source.element.element_id)?;
+                let mut textures = HashMap::new();
+                collect_textures(source, &mut textures);
+                let name = self.graph_node(graph, node, &textures, ty)?;
+                Some((name, output_index))
+            },
+            _ => None,
+        }
+    }
+}
+
+fn collect_textures<'a>(texture: &'a Texture, textures: &mut HashMap<u32, &'a Texture>) {
+    let mut stack = vec![texture];
+    while let Some(texture) = stack.pop() {
+        if textures.insert(texture.element.element_id, texture).is_some() {
+            continue;
+        }
+        if let Some(shader) = texture.shader.as_deref() {
+            stack.extend(shader.inputs.iter().filter_map(|input| input.texture.as_deref()));
+            stack.extend(shader.main_texture.as_deref());
+        }
+        stack.extend(texture.layers.iter().map(|layer| &*layer.texture));
+    }
+}
+
+fn connection(node: &str, output_index: i64) -> String {
+    if output_index != 0 {
+        format!("nodename=\"{}\" output=\"out{}\"", node, output_index)
+    } else {
+        format!("nodename=\"{}\"", node)
+    }
+}
+
+fn write_material(writer: &mut MtlxWriter, out: &mut String, material: &Material) {
+    let surface = writer.opts.surface;
+    let (category, thin_walled) = match surface {
+        MaterialXSurface::StandardSurface => ("standard_surface", "thin_walled"),
+        MaterialXSurface::OpenPbrSurface => ("open_pbr_surface", "geometry_thin_walled"),
+    };
+
+    let mut graph = None;
+    let mut inputs = String::new();
+    let features = &material.features;
+    for def in INPUT_DEFS {
+        // Glossiness materials keep their data in the glossiness maps
+        let (map, invert) = match def.name {
+            "roughness" if features.roughness_as_glossiness.enabled => (&material.pbr.glossiness, true),
+            "coat_roughness" if features.coat_roughness_as_glossiness.enabled => (&material.pbr.coat_glossiness, true),
+            _ => ((def.map)(&material.pbr), false),
+        };
+        let target = match surface {
+            MaterialXSurface::StandardSurface => def.standard_surface,
+            MaterialXSurface::OpenPbrSurface => def.open_pbr_surface,
+        };
+        let (name, ty) = match target {
+            Some(target) => target,
+            None => {
+                if is_set(map) {
+                    let source = match texture_enabled(map) {
+                        Some(texture) => format!("texture '{}'", texture.element.name),
+                        None => format!("value {}", map.value_vec4),
+                    };
+                    writer.annotate(&format!("material '{}' input '{}' ({}) has no {} equivalent",
+                        material.element.name, def.name, source, category));
+                }
+                continue;
+            },
+        };
+
+        if let Some(texture) = texture_enabled(map) {
+            let image_type = if ty == InputType::Normal { "vector3" } else { ty.name() };
+            match writer.texture(material, &mut graph, texture, image_type) {
+                Some((mut node, mut output_index)) => {
+                    if ty == InputType::Normal {
+                        let normal = writer.unique_name(&format!("{}_{}_normalmap", material.element.name, name));
+                        writeln!(writer.nodes, "  <normalmap name=\"{}\" type=\"vector3\">", normal).unwrap();
+                        writeln!(writer.nodes, "    <input name=\"in\" type=\"vector3\" {} />", connection(&node, output_index)).unwrap();
+                        writeln!(writer.nodes, "  </normalmap>").unwrap();
+                        node = normal;
+                        output_index = 0;
+                    } else if invert {
+                        let inverted = writer.unique_name(&format!("{}_{}_invert", material.element.name, name));
+                        writeln!(writer.nodes, "  <subtract name=\"{}\" type=\"float\">", inverted).unwrap();
+                        writeln!(writer.nodes, "    <input name=\"in1\" type=\"float\" value=\"1\" />").unwrap();
+                        writeln!(writer.nodes, "    <input name=\"in2\" type=\"float\" {} />", connection(&node, output_index)).unwrap();
+                        writeln!(writer.nodes, "  </subtract>").unwrap();
+                        node = inverted;
+                        output_index = 0;
+                    }
+                    writeln!(inputs, "    <input name=\"{}\" type=\"{}\" {} />", name, ty.name(), connection(&node, output_index)).unwrap();
+                    continue;
+                },
+                None => {
+                    writer.annotate(&format!("material '{}' input '{}' uses unmappable {:?} texture '{}'",
+                        material.element.name, def.name, texture.type_, texture.element.name));
+                },
+            }
+        }
+
+        if map.has_value && ty != InputType::Normal {
+            writeln!(inputs, "    <input name=\"{}\" type=\"{}\" value=\"{}\" />", name, ty.name(), format_value(map, ty, invert)).unwrap();
+        }
+    }
+    if features.thin_walled.enabled {
+        writeln!(inputs, "    <input name=\"{}\" type=\"boolean\" value=\"true\" />", thin_walled).unwrap();
+    }
+
+    let shader_name = writer.unique_name(&format!("{}_surface", material.element.name));
+    let material_name = writer.unique_name(&material.element.name);
+    out.push_str(&writer.nodes);
+    writer.nodes.clear();
+    writeln!(out, "  <{} name=\"{}\" type=\"surfaceshader\">", category, shader_name).unwrap();
+    out.push_str(&inputs);
+    writeln!(out, "  </{}>", category).unwrap();
+    writeln!(out, "  <surfacematerial name=\"{}\" type=\"material\">", material_name).unwrap();
+    writeln!(out, "    <input name=\"surfaceshader\" type=\"surfaceshader\" nodename=\"{}\" />", shader_name).unwrap();
+    writeln!(out, "  </surfacematerial>").unwrap();
+}
+
+/// Writes `materials` as a MaterialX document.
+pub fn export_materialx<'a, I: IntoIterator<Item = &'a Material>>(materials: I, opts: &MaterialXOpts) -> String {
+    let version = match opts.surface {
+        MaterialXSurface::StandardSurface => "1.38",
+        MaterialXSurface::OpenPbrSurface => "1.39",
+    };
+    let mut writer = MtlxWriter {
+        opts,
+        nodes: String::new(),
+        names: HashSet::new(),
+        emitted: HashMap::new(),
+        in_progress: HashSet::new(),
+    };
+
+    let mut out = String::new();
+    writeln!(out, "<?xml version=\"1.0\"?>").unwrap();
+    writeln!(out, "<materialx version=\"{}\">", version).unwrap();
+    for material in materials {
+        write_material(&mut writer, &mut out, material);
+    }
+    writeln!(out, "</materialx>").unwrap();
+    out
+}
@@ -0,0 +1,68 @@
+use crate::generated::{Vec3, Quat, CoordinateAxis};
+use crate::prelude::Real;
+
+pub(crate) fn vec3_add(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3 { x: a.x + b.x, y: a.y + b.y, z: a.z + b.z }
+}
+
+pub(crate) fn vec3_sub(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3 { x: a.x - b.x, y: a.y - b.y, z: a.z - b.z }
+}
+
+pub(crate) fn vec3_mul(a: Vec3, s: Real) -> Vec3 {
+    Vec3 { x: a.x * s, y: a.y * s, z: a.z * s }
+}
+
+pub(crate) fn vec3_dot(a: Vec3, b: Vec3) -> Real {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+pub(crate) fn vec3_length(a: Vec3) -> Real {
+    vec3_dot(a, a).sqrt()
+}
+
+pub(crate) fn vec3_distance(a: Vec3, b: Vec3) -> Real {
+    vec3_length(vec3_sub(a, b))
+}
+
+pub(crate) fn vec3_cross(a: Vec3, b: Vec3) -> Vec3 {
+    Vec3 { x: a.y * b.z - a.z * b.y, y: a.z * b.x - a.x * b.z, z: a.x * b.y - a.y * b.x }
+}
+
+pub(crate) fn vec3_normalize(a: Vec3) -> Vec3 {
+    let len = vec3_length(a);
+    if len > 0.0 { vec3_mul(a, 1.0 / len) } else { Vec3::default() }
+}
+
+pub(crate) fn axis_vector(axis: CoordinateAxis) -> Vec3 {
+    match axis {
+        CoordinateAxis::PositiveX => Vec3 { x: 1.0, y: 0.0, z: 0.0 },
+        CoordinateAxis::NegativeX => Vec3 { x: -1.0, y: 0.0, z: 0.0 },
+        CoordinateAxis::PositiveY => Vec3 { x: 0.0, y: 1.0, z: 0.0 },
+        CoordinateAxis::NegativeY => Vec3 { x: 0.0, y: -1.0, z: 0.0 },
+        CoordinateAxis::PositiveZ => Vec3 { x: 0.0, y: 0.0, z: 1.0 },
+        CoordinateAxis::NegativeZ => Vec3 { x: 0.0, y: 0.0, z: -1.0 },
+        CoordinateAxis::Unknown => Vec3::default(),
+    }
+}
+
+/// Inverse of a unit quaternion.
+pub(crate) fn quat_conjugate(q: Quat) -> Quat {
+    Quat { x: -q.x, y: -q.y, z: -q.z, w: q.w }
+}
+
+/// Shortest rotation taking the direction `a` to `b`.
+pub(crate) fn quat_from_to(a: Vec3, b: Vec3) -> Quat {
+    let (a, b) = (vec3_normalize(a), vec3_normalize(b));
+    let d = vec3_dot(a, b);
+    if d < -1.0 + 1e-12 {
+        // Opposite directions, rotate half a turn around any perpendicular axis
+        let other = if a.x.abs() < 0.9 { Vec3 { x: 1.0, y: 0.0, z: 0.0 } } else { Vec3 { x: 0.0, y: 1.0, z: 0.0 } };
+        let axis = vec3_normalize(vec3_cross(a, other));
+        return Quat { x: axis.x, y: axis.y, z: axis.z, w: 0.0 };
+    }
+    let c = vec3_cross(a, b);
+    let q = Quat { x: c.x, y: c.y, z: c.z, w: 1.0 + d };
+    let len = (q.x * q.x + q.y * q.y + q.z * q.z + q.w * q.w).sqrt();
+    Quat { x: q.x / len, y: q.y / len, z: q.z / len, w: q.w / len }
+}
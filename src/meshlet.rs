@@ -0,0 +1,198 @@
+use crate::generated::Vec3;
+use crate::prelude::Real;
+use crate::simplify::LodMesh;
+use crate::math::{vec3_add, vec3_sub, vec3_mul, vec3_dot, vec3_length, vec3_distance, vec3_cross, vec3_normalize};
+
+pub struct MeshletOpts {
+    /// Clamped to `3..=256` so that a triangle fits and local indices fit in a byte.
+    pub max_vertices: usize,
+    /// Clamped to at least 1.
+    pub max_triangles: usize,
+}
+
+impl Default for MeshletOpts {
+    fn default() -> Self {
+        MeshletOpts { max_vertices: 64, max_triangles: 124 }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Meshlet {
+    /// Indices into the source vertex buffer.
+    pub vertices: Vec<u32>,
+    /// Triangles as indices into `vertices`.
+    pub triangles: Vec<[u8; 3]>,
+    pub center: Vec3,
+    pub radius: Real,
+    pub cone_apex: Vec3,
+    pub cone_axis: Vec3,
+    /// Cosine limit for `is_backfacing()`, 1.0 if the normals are too spread out to cull.
+    pub cone_cutoff: Real,
+}
+
+impl Meshlet {
+    /// Returns `true` if every triangle faces away from `camera`.
+    pub fn is_backfacing(&self, camera: Vec3) -> bool {
+        if self.cone_cutoff >= 1.0 { return false }
+        let dir = vec3_normalize(vec3_sub(self.cone_apex, camera));
+        vec3_dot(dir, self.cone_axis) >= self.cone_cutoff
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MeshletPart {
+    /// Material index, same as `MeshPart::index`.
+    pub index: u32,
+    pub meshlets: Vec<Meshlet>,
+}
+
+fn bounding_sphere(points: &[Vec3]) -> (Vec3, Real) {
+    // Ritter's algorithm starting from the point furthest away from the first one
+    let furthest = |from: Vec3| points.iter().copied()
+        .fold(from, |best, p| if vec3_distance(p, from) > vec3_distance(best, from) { p } else { best });
+    let a = furthest(points[0]);
+    let b = furthest(a);
+    let mut center = vec3_mul(vec3_add(a, b), 0.5);
+    let mut radius = vec3_distance(a, b) * 0.5;
+    for &p in points {
+        let d = vec3_distance(p, center);
+        if d > radius {
+            let new_radius = (radius + d) * 0.5;
+            center = vec3_add(center, vec3_mul(vec3_sub(p, center), (new_radius - radius) / d));
+            radius = new_radius;
+        }
+    }
+    (center, radius)
+}
+
+fn finish_meshlet(vertices: Vec<u32>, triangles: Vec<[u8; 3]>, positions: &[Vec3]) -> Meshlet {
+    let points: Vec<Vec3> = vertices.iter().map(|&v| positions[v as usize]).collect();
+    let (center, radius) = bounding_sphere(&points);
+
+    let normals: Vec<(Vec3, Vec3)> = triangles.iter().filter_map(|t| {
+        let p = t.map(|i| points[i as usize]);
+        let n = vec3_cross(vec3_sub(p[1], p[0]), vec3_sub(p[2], p[0]));
+        if vec3_length(n) > 0.0 { Some((p[0], vec3_normalize(n))) } else { None }
+    }).collect();
+    let axis = vec3_normalize(normals.iter().fold(Vec3::default(), |sum, &(_, n)| vec3_add(sum, n)));
+    let min_dot = normals.iter().map(|&(_, n)| vec3_dot(n, axis)).fold(1.0, Real::min);
+
+    let mut meshlet = Meshlet {
+        vertices,
+        triangles,
+        center,
+        radius,
+        cone_apex: center,
+        cone_axis: axis,
+        cone_cutoff: 1.0,
+    };
+    if !normals.is_empty() && min_dot > 0.1 {
+        // Move the apex back so that the cone contains every triangle plane
+        let offset = normals.iter()
+            .map(|&(p, n)| vec3_dot(vec3_sub(center, p), n) / vec3_dot(axis, n))
+            .fold(0.0, Real::max);
+        meshlet.cone_apex = vec3_sub(center, vec3_mul(axis, offset));
+        meshlet.cone_cutoff = (1.0 - min_dot * min_dot).sqrt();
+    }
+    meshlet
+}
+
+/// Greedily groups triangles of `indices` into meshlets, preferring triangles
+/// that reuse vertices already in the meshlet. The output only depends on the input.
+pub fn build_meshlets(indices: &[u32], positions: &[Vec3], opts: &MeshletOpts) -> Vec<Meshlet> {
+    let max_vertices = opts.max_vertices.clamp(3, 256);
+    let max_triangles = opts.max_triangles.max(1);
+
+    let num_vertices = positions.len();
+    let num_triangles = indices.len() / 3;
+    let mut adj_first = vec![0usize; num_vertices + 1];
+    for &ix in &indices[..num_triangles * 3] {
+        adj_first[ix as usize + 1] += 1;
+    }
+    for v in 0..num_vertices {
+        adj_first[v + 1] += adj_first[v];
+    }
+    let mut adj = vec![0u32; adj_first[num_vertices]];
+    let mut cursor = adj_first.clone();
+    for (tri, t) in indices.chunks_exact(3).enumerate() {
+        for &ix in t {
+            adj[cursor[ix as usize]] = tri as u32;
+            cursor[ix as usize] += 1;
+        }
+    }
+
+    let tri_centroid = |tri: usize| {
+        let t = &indices[tri * 3..tri * 3 + 3];
+        let sum = vec3_add(vec3_add(positions[t[0] as usize], positions[t[1] as usize]), positions[t[2] as usize]);
+        vec3_mul(sum, 1.0 / 3.0)
+    };
+
+    let mut emitted = vec![false; num_triangles];
+    let mut local = vec![u32::MAX; num_vertices];
+    let mut meshlets = Vec::new();
+    let mut scan = 0;
+
+    loop {
+        while scan < num_triangles && emitted[scan] { scan += 1 }
+        if scan >= num_triangles { break }
+
+        let mut vertices: Vec<u32> = Vec::new();
+        let mut triangles: Vec<[u8; 3]> = Vec::new();
+        let mut centroid_sum = Vec3::default();
+        let mut next = Some(scan);
+
+        while let Some(tri) = next {
+            emitted[tri] = true;
+            let mut local_tri = [0u8; 3];
+            for (c, &v) in indices[tri * 3..tri * 3 + 3].iter().enumerate() {
+                if local[v as usize] == u32::MAX {
+                    local[v as usize] = vertices.len() as u32;
+                    vertices.push(v);
+                }
+                local_tri[c] = local[v as usize] as u8;
+            }
+            triangles.push(local_tri);
+            centroid_sum = vec3_add(centroid_sum, tri_centroid(tri));
+            if triangles.len() >= max_triangles { break }
+
+            // Pick the adjacent triangle adding the fewest vertices, then the closest one
+            let centroid = vec3_mul(centroid_sum, 1.0 / triangles.len() as Real);
+            let mut best: Option<(usize, Real, usize)> = None;
+            for &v in &vertices {
+                for &cand in &adj[adj_first[v as usize]..adj_first[v as usize + 1]] {
+                    let cand = cand as usize;
+                    if emitted[cand] { continue }
+                    let new_verts = indices[cand * 3..cand * 3 + 3].iter().filter(|&&x| local[x as usize] == u32::MAX).count();
+                    if vertices.len() + new_verts > max_vertices { continue }
+                    let dist = vec3_distance(tri_centroid(cand), centroid);
+                    let better = match best {
+                        None => true,
+                        Some((bn, bd, bt)) => (new_verts, dist, cand) < (bn, bd, bt),
+                    };
+                    if better {
+                        best = Some((new_verts, dist, cand));
+                    }
+                }
+            }
+            next = best.map(|b| b.2);
+        }
+
+        for &v in &vertices {
+            local[v as usize] = u32::MAX;
+        }
+        meshlets.push(finish_meshlet(vertices, triangles, positions));
+    }
+
+    meshlets
+}
+
+impl LodMesh {
+    /// Builds meshlets for each part, vertex indices refer to `self.vertices`.
+    pub fn build_meshlets(&self, opts: &MeshletOpts) -> Vec<MeshletPart> {
+        let positions: Vec<Vec3> = self.vertices.iter().map(|v| v.position).collect();
+        self.parts.iter().map(|part| {
+            let indices = &self.indices[part.index_begin..part.index_begin + part.num_triangles * 3];
+            MeshletPart { index: part.index, meshlets: build_meshlets(indices, &positions, opts) }
+        }).collect()
+    }
+}
@@ -0,0 +1,243 @@
+use crate::generated::{Vec3, Vec4, Transform, Material, MaterialMap, Texture, ShaderType};
+use crate::prelude::Real;
+use crate::math::vec3_mul;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AlphaMode {
+    Opaque,
+    Blend,
+}
+
+/// Source features that have no metallic-roughness equivalent.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DroppedFeature {
+    UnknownShader,
+    SpecularGlossinessWorkflow,
+    SpecularColor,
+    Ior,
+    Anisotropy,
+    DiffuseRoughness,
+    Transmission,
+    Subsurface,
+    Sheen,
+    Coat,
+    ThinFilm,
+    Matte,
+    Displacement,
+    OpacityTexture,
+}
+
+#[derive(Clone, Copy)]
+pub struct TextureRef<'a> {
+    pub texture: &'a Texture,
+    pub uv_set: &'a str,
+    pub uv_transform: Option<Transform>,
+}
+
+#[derive(Clone)]
+pub struct MetallicRoughness<'a> {
+    pub base_color_factor: Vec4,
+    pub metallic_factor: Real,
+    pub roughness_factor: Real,
+    pub emissive_factor: Vec3,
+    pub emissive_strength: Real,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+    pub unlit: bool,
+    pub base_color_texture: Option<TextureRef<'a>>,
+    pub metallic_texture: Option<TextureRef<'a>>,
+    pub roughness_texture: Option<TextureRef<'a>>,
+    /// The roughness texture stores glossiness and must be inverted.
+    pub roughness_from_glossiness: bool,
+    pub normal_texture: Option<TextureRef<'a>>,
+    pub occlusion_texture: Option<TextureRef<'a>>,
+    pub emissive_texture: Option<TextureRef<'a>>,
+    pub dropped: Vec<DroppedFeature>,
+}
+
+/// One channel of decoded 8-bit pixels, `pixels` has `components` interleaved values per pixel.
+#[derive(Clone, Copy)]
+pub struct ChannelImage<'a> {
+    pub pixels: &'a [u8],
+    pub components: usize,
+    pub channel: usize,
+}
+
+impl ChannelImage<'_> {
+    fn get(&self, ix: usize) -> u8 {
+        self.pixels[ix * self.components + self.channel]
+    }
+}
+
+impl MetallicRoughness<'_> {
+    /// Returns `true` if metalness and roughness already share a texture
+    /// and can be used directly as a glTF metallic-roughness texture.
+    pub fn is_packed(&self) -> bool {
+        match (&self.metallic_texture, &self.roughness_texture) {
+            (Some(m), Some(r)) => m.texture.element.element_id == r.texture.element.element_id,
+            _ => false,
+        }
+    }
+
+    /// Packs decoded `metallic_texture` and `roughness_texture` pixels into a `width * height`
+    /// RGBA8 glTF metallic-roughness image, roughness in green and metalness in blue.
+    /// Glossiness is inverted if `roughness_from_glossiness` is set. Missing images are
+    /// filled with 255 so that the factors apply as is.
+    /// Returns `None` if an image does not match the size or `channel >= components`.
+    pub fn pack_textures(&self, width: u32, height: u32, metallic: Option<ChannelImage>, roughness: Option<ChannelImage>) -> Option<Vec<u8>> {
+        let num_pixels = width as usize * height as usize;
+        for image in metallic.iter().chain(roughness.iter()) {
+            if image.channel >= image.components || image.pixels.len() != num_pixels * image.components {
+                return None;
+            }
+        }
+
+        let mut result = Vec::with_capacity(num_pixels * 4);
+        for ix in 0..num_pixels {
+            let metal = metallic.map_or(255, |m| m.get(ix));
+            let rough = match roughness {
+                Some(r) if self.roughness_from_glossiness => 255 - r.get(ix),
+                Some(r) => r.get(ix),
+                None => 255,
+            };
+            result.extend_from_slice(&[255, rough, metal, 255]);
+        }
+        Some(result)
+    }
+}
+
+fn value(map: &MaterialMap, default: Real) -> Real {
+    if map.has_value { map.value_vec4.x } else { default }
+}
+
+fn color(map: &MaterialMap, default: Real) -> Vec3 {
+    if map.has_value {
+        Vec3 { x: map.value_vec4.x, y: map.value_vec4.y, z: map.value_vec4.z }
+    } else {
+        Vec3 { x: default, y: default, z: default }
+    }
+}
+
+fn texture_ref(map: &MaterialMap) -> Option<TextureRef<'_>> {
+    let texture = map.texture.as_deref().filter(|_| map.texture_enabled)?;
+    Some(TextureRef {
+        texture,
+        uv_set: &texture.uv_set,
+        uv_transform: if texture.has_uv_transform { Some(texture.uv_transform) } else { None },
+    })
+}
+
+fn is_active(map: &MaterialMap) -> bool {
+    !map.feature_disabled && (value(map, 0.0) > 0.0 || texture_ref(map).is_some())
+}
+
+impl Material {
+    pub fn to_metallic_roughness(&self) -> MetallicRoughness<'_> {
+        let pbr = &self.pbr;
+        let features = &self.features;
+        let mut dropped = Vec::new();
+
+        let base = color(&pbr.base_color, 1.0);
+        let base_factor = value(&pbr.base_factor, 1.0);
+
+        let opacity = if pbr.opacity.has_value {
+            pbr.opacity.value_vec4.x
+        } else {
+            let transparency = self.fbx.transparency_color.value_vec4;
+            1.0 - self.fbx.transparency_factor.value_vec4.x * (transparency.x + transparency.y + transparency.z) / 3.0
+        };
+        let opacity = opacity.clamp(0.0, 1.0);
+
+        let base_color_texture = texture_ref(&pbr.base_color);
+        let opacity_texture = texture_ref(&pbr.opacity);
+        let alpha_mode = match (&opacity_texture, &base_color_texture) {
+            (Some(o), Some(b)) if o.texture.element.element_id == b.texture.element.element_id => AlphaMode::Blend,
+            (Some(_), _) => {
+                dropped.push(DroppedFeature::OpacityTexture);
+                AlphaMode::Blend
+            },
+            _ if opacity < 1.0 => AlphaMode::Blend,
+            _ => AlphaMode::Opaque,
+        };
+
+        // ufbx moves glossiness data to `pbr.glossiness`, leaving only the inverted value in `pbr.roughness`
+        let roughness_from_glossiness = features.roughness_as_glossiness.enabled;
+        let (roughness, roughness_map) = if roughness_from_glossiness {
+            (1.0 - value(&pbr.glossiness, 0.0), &pbr.glossiness)
+        } else {
+            (value(&pbr.roughness, 1.0), &pbr.roughness)
+        };
+
+        if self.shader_type == ShaderType::E3DsMaxPbrSpecGloss {
+            dropped.push(DroppedFeature::SpecularGlossinessWorkflow);
+        } else if self.shader_type == ShaderType::Unknown && self.shader.is_some() {
+            dropped.push(DroppedFeature::UnknownShader);
+        }
+
+        if features.specular.enabled && features.pbr.enabled {
+            let spec = color(&pbr.specular_color, 1.0);
+            if spec.x != spec.y || spec.y != spec.z || texture_ref(&pbr.specular_color).is_some() {
+                dropped.push(DroppedFeature::SpecularColor);
+            }
+        }
+        if pbr.specular_ior.has_value && (pbr.specular_ior.value_vec4.x - 1.5).abs() > 0.01 {
+            dropped.push(DroppedFeature::Ior);
+        }
+        if is_active(&pbr.specular_anisotropy) {
+            dropped.push(DroppedFeature::Anisotropy);
+        }
+        if is_active(&pbr.diffuse_roughness) {
+            dropped.push(DroppedFeature::DiffuseRoughness);
+        }
+        if is_active(&pbr.transmission_factor) {
+            dropped.push(DroppedFeature::Transmission);
+        }
+        if is_active(&pbr.subsurface_factor) {
+            dropped.push(DroppedFeature::Subsurface);
+        }
+        if is_active(&pbr.sheen_factor) {
+            dropped.push(DroppedFeature::Sheen);
+        }
+        if is_active(&pbr.coat_factor) {
+            dropped.push(DroppedFeature::Coat);
+        }
+        if is_active(&pbr.thin_film_factor) {
+            dropped.push(DroppedFeature::ThinFilm);
+        }
+        if is_active(&pbr.matte_factor) {
+            dropped.push(DroppedFeature::Matte);
+        }
+        if texture_ref(&pbr.displacement_map).is_some() {
+            dropped.push(DroppedFeature::Displacement);
+        }
+
+        let emission = color(&pbr.emission_color, 0.0);
+        let emission_factor = value(&pbr.emission_factor, 1.0);
+        let emissive = vec3_mul(emission, emission_factor);
+        let max_emissive = emissive.x.max(emissive.y).max(emissive.z);
+        let (emissive_factor, emissive_strength) = if max_emissive > 1.0 {
+            (vec3_mul(emissive, 1.0 / max_emissive), max_emissive)
+        } else {
+            (emissive, 1.0)
+        };
+
+        MetallicRoughness {
+            base_color_factor: Vec4 { x: base.x * base_factor, y: base.y * base_factor, z: base.z * base_factor, w: opacity },
+            metallic_factor: value(&pbr.metalness, 0.0),
+            roughness_factor: roughness,
+            emissive_factor,
+            emissive_strength,
+            alpha_mode,
+            double_sided: features.double_sided.enabled,
+            unlit: features.unlit.enabled,
+            base_color_texture,
+            metallic_texture: texture_ref(&pbr.metalness),
+            roughness_texture: texture_ref(roughness_map),
+            roughness_from_glossiness,
+            normal_texture: texture_ref(&pbr.normal_map),
+            occlusion_texture: texture_ref(&pbr.ambient_occlusion),
+            emissive_texture: texture_ref(&pbr.emission_color),
+            dropped,
+        }
+    }
+}
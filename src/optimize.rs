@@ -0,0 +1,274 @@
+use crate::generated::Vec3;
+use crate::prelude::Real;
+use crate::simplify::LodMesh;
+use crate::math::{vec3_add, vec3_sub, vec3_mul, vec3_dot, vec3_length, vec3_cross, vec3_normalize};
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct VertexCacheStats {
+    /// Average cache misses per triangle, 0.5 is ideal for large grids and 3.0 is worst.
+    pub acmr: Real,
+    /// Average cache misses per referenced vertex, 1.0 is ideal.
+    pub atvr: Real,
+    pub num_misses: usize,
+}
+
+/// Simulates a FIFO post-transform cache of `cache_size` entries.
+pub fn analyze_vertex_cache(indices: &[u32], num_vertices: usize, cache_size: usize) -> VertexCacheStats {
+    let mut timestamps = vec![0usize; num_vertices];
+    let mut referenced = vec![false; num_vertices];
+    let mut num_referenced = 0;
+    let mut time = cache_size + 1;
+    let mut num_misses = 0;
+    for &ix in indices {
+        let ix = ix as usize;
+        if time - timestamps[ix] > cache_size {
+            timestamps[ix] = time;
+            time += 1;
+            num_misses += 1;
+        }
+        if !referenced[ix] {
+            referenced[ix] = true;
+            num_referenced += 1;
+        }
+    }
+
+    let num_triangles = indices.len() / 3;
+    VertexCacheStats {
+        acmr: if num_triangles > 0 { num_misses as Real / num_triangles as Real } else { 0.0 },
+        atvr: if num_referenced > 0 { num_misses as Real / num_referenced as Real } else { 0.0 },
+        num_misses,
+    }
+}
+
+/// Reorders triangles for post-transform cache locality using Tipsify.
+pub fn optimize_vertex_cache(indices: &mut [u32], num_vertices: usize, cache_size: usize) {
+    let num_triangles = indices.len() / 3;
+    if num_triangles == 0 { return }
+
+    let mut live = vec![0u32; num_vertices];
+    for &ix in indices.iter() {
+        live[ix as usize] += 1;
+    }
+    let mut adj_first = vec![0usize; num_vertices + 1];
+    for v in 0..num_vertices {
+        adj_first[v + 1] = adj_first[v] + live[v] as usize;
+    }
+    let mut adj = vec![0u32; adj_first[num_vertices]];
+    let mut cursor = adj_first.clone();
+    for (tri, t) in indices.chunks_exact(3).enumerate() {
+        for &ix in t {
+            adj[cursor[ix as usize]] = tri as u32;
+            cursor[ix as usize] += 1;
+        }
+    }
+
+    let source: Vec<u32> = indices.to_vec();
+    let mut emitted = vec![false; num_triangles];
+    let mut timestamps = vec![0usize; num_vertices];
+    let mut dead_end: Vec<u32> = Vec::new();
+    let mut candidates: Vec<u32> = Vec::new();
+    let mut time = cache_size + 1;
+    let mut scan = 0;
+    let mut out = 0;
+
+    let mut fanning = source[0];
+    loop {
+        candidates.clear();
+        for &tri in &adj[adj_first[fanning as usize]..adj_first[fanning as usize + 1]] {
+            if emitted[tri as usize] { continue }
+            emitted[tri as usize] = true;
+            for &v in &source[tri as usize * 3..tri as usize * 3 + 3] {
+                indices[out] = v;
+                out += 1;
+                dead_end.push(v);
+                candidates.push(v);
+                live[v as usize] -= 1;
+                if time - timestamps[v as usize] > cache_size {
+                    timestamps[v as usize] = time;
+                    time += 1;
+                }
+            }
+        }
+
+        // Prefer a vertex still in the cache that has few triangles left
+        let mut best = None;
+        let mut best_priority = 0;
+        for &v in &candidates {
+            if live[v as usize] == 0 { continue }
+            let age = time - timestamps[v as usize];
+            let priority = if age + 2 * live[v as usize] as usize <= cache_size { age } else { 0 };
+            if best.is_none() || priority > best_priority {
+                best = Some(v);
+                best_priority = priority;
+            }
+        }
+
+        let next = best.or_else(|| {
+            while let Some(v) = dead_end.pop() {
+                if live[v as usize] > 0 { return Some(v) }
+            }
+            while scan < num_triangles {
+                if !emitted[scan] { return Some(source[scan * 3]) }
+                scan += 1;
+            }
+            None
+        });
+        match next {
+            Some(v) => fanning = v,
+            None => break,
+        }
+    }
+}
+
+/// Reorders clusters of triangles so that outward facing clusters are drawn first,
+/// reverts to the input order if the cache miss ratio would grow above `threshold`.
+pub fn optimize_overdraw(indices: &mut [u32], positions: &[Vec3], cache_size: usize, threshold: Real) {
+    let num_triangles = indices.len() / 3;
+    if num_triangles == 0 { return }
+
+    // Split at triangles that miss the cache on every vertex, these start new fans
+    let mut clusters = vec![0usize];
+    let mut timestamps = vec![0usize; positions.len()];
+    let mut time = cache_size + 1;
+    for (tri, t) in indices.chunks_exact(3).enumerate() {
+        let mut misses = 0;
+        for &ix in t {
+            if time - timestamps[ix as usize] > cache_size {
+                timestamps[ix as usize] = time;
+                time += 1;
+                misses += 1;
+            }
+        }
+        if misses == 3 && tri > 0 {
+            clusters.push(tri);
+        }
+    }
+    clusters.push(num_triangles);
+
+    let tri_data = |tri: usize| {
+        let p = [0, 1, 2].map(|c| positions[indices[tri * 3 + c] as usize]);
+        let n = vec3_cross(vec3_sub(p[1], p[0]), vec3_sub(p[2], p[0]));
+        let area = vec3_length(n);
+        (vec3_mul(vec3_add(vec3_add(p[0], p[1]), p[2]), area / 3.0), n, area)
+    };
+
+    let mut mesh_centroid = Vec3::default();
+    let mut mesh_area = 0.0;
+    for tri in 0..num_triangles {
+        let (c, _, area) = tri_data(tri);
+        mesh_centroid = vec3_add(mesh_centroid, c);
+        mesh_area += area;
+    }
+    if mesh_area > 0.0 {
+        mesh_centroid = vec3_mul(mesh_centroid, 1.0 / mesh_area);
+    }
+
+    let mut order: Vec<(Real, usize)> = clusters.windows(2).enumerate().map(|(ix, range)| {
+        let (mut centroid, mut normal, mut area) = (Vec3::default(), Vec3::default(), 0.0);
+        for tri in range[0]..range[1] {
+            let (c, n, a) = tri_data(tri);
+            centroid = vec3_add(centroid, c);
+            normal = vec3_add(normal, n);
+            area += a;
+        }
+        if area > 0.0 {
+            centroid = vec3_mul(centroid, 1.0 / area);
+        }
+        (vec3_dot(vec3_sub(centroid, mesh_centroid), vec3_normalize(normal)), ix)
+    }).collect();
+    order.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal).then(a.1.cmp(&b.1)));
+
+    let mut sorted = Vec::with_capacity(indices.len());
+    for &(_, ix) in &order {
+        sorted.extend_from_slice(&indices[clusters[ix] * 3..clusters[ix + 1] * 3]);
+    }
+
+    let before = analyze_vertex_cache(indices, positions.len(), cache_size);
+    let after = analyze_vertex_cache(&sorted, positions.len(), cache_size);
+    if after.acmr <= before.acmr * threshold {
+        indices[..sorted.len()].copy_from_slice(&sorted);
+    }
+}
+
+/// Renumbers vertices in the order they are first referenced, returns the
+/// new index of each old vertex or `u32::MAX` for unreferenced ones.
+pub fn optimize_vertex_fetch(indices: &mut [u32], num_vertices: usize) -> Vec<u32> {
+    let mut remap = vec![u32::MAX; num_vertices];
+    let mut next = 0;
+    for ix in indices.iter_mut() {
+        let slot = &mut remap[*ix as usize];
+        if *slot == u32::MAX {
+            *slot = next;
+            next += 1;
+        }
+        *ix = *slot;
+    }
+    remap
+}
+
+/// Applies a remap table from `optimize_vertex_fetch()` to a vertex buffer.
+pub fn remap_vertex_buffer<T: Clone>(vertices: &[T], remap: &[u32]) -> Vec<T> {
+    let mut result: Vec<Option<T>> = vec![None; remap.iter().filter(|&&r| r != u32::MAX).count()];
+    for (v, &r) in vertices.iter().zip(remap) {
+        if r != u32::MAX {
+            result[r as usize] = Some(v.clone());
+        }
+    }
+    result.into_iter().map(|v| v.unwrap()).collect()
+}
+
+pub struct OptimizeOpts {
+    pub cache_size: usize,
+    pub vertex_cache: bool,
+    pub overdraw: bool,
+    /// Maximum allowed ACMR growth factor from overdraw optimization.
+    pub overdraw_threshold: Real,
+    pub vertex_fetch: bool,
+}
+
+impl Default for OptimizeOpts {
+    fn default() -> Self {
+        OptimizeOpts {
+            cache_size: 16,
+            vertex_cache: true,
+            overdraw: true,
+            overdraw_threshold: 1.05,
+            vertex_fetch: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PartOptimizeStats {
+    /// Material index of the part.
+    pub index: u32,
+    pub before: VertexCacheStats,
+    pub after: VertexCacheStats,
+}
+
+impl LodMesh {
+    /// Optimizes the triangle order of each part and the vertex order of the whole mesh.
+    pub fn optimize(&mut self, opts: &OptimizeOpts) -> Vec<PartOptimizeStats> {
+        let num_vertices = self.vertices.len();
+        let positions: Vec<Vec3> = self.vertices.iter().map(|v| v.position).collect();
+        let mut stats = Vec::with_capacity(self.parts.len());
+        for part in &self.parts {
+            let indices = &mut self.indices[part.index_begin..part.index_begin + part.num_triangles * 3];
+            let before = analyze_vertex_cache(indices, num_vertices, opts.cache_size);
+            if opts.vertex_cache {
+                optimize_vertex_cache(indices, num_vertices, opts.cache_size);
+            }
+            if opts.overdraw {
+                optimize_overdraw(indices, &positions, opts.cache_size, opts.overdraw_threshold);
+            }
+            let after = analyze_vertex_cache(indices, num_vertices, opts.cache_size);
+            stats.push(PartOptimizeStats { index: part.index, before, after });
+        }
+
+        if opts.vertex_fetch {
+            let remap = optimize_vertex_fetch(&mut self.indices, num_vertices);
+            self.vertices = remap_vertex_buffer(&self.vertices, &remap);
+        }
+        stats
+    }
+}
@@ -0,0 +1,30 @@
+use std::fmt::{self, Display, Formatter};
+use crate::generated::{Prop, PropType, Vec4};
+
+/// Plain value of a property, see `prop_value()`.
+#[derive(Clone, Debug)]
+pub enum PropValue {
+    Number(Vec4),
+    Int(i64),
+    String(String),
+}
+
+/// Returns the value of `prop`, or `None` for blob, reference and compound properties.
+pub fn prop_value(prop: &Prop) -> Option<PropValue> {
+    match prop.type_ {
+        PropType::String => Some(PropValue::String(prop.value_str.to_string())),
+        PropType::Boolean | PropType::Integer => Some(PropValue::Int(prop.value_int)),
+        PropType::Blob | PropType::Reference | PropType::Compound => None,
+        _ => Some(PropValue::Number(prop.value_vec4)),
+    }
+}
+
+impl Display for PropValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PropValue::Number(v) => write!(f, "{}", v),
+            PropValue::Int(v) => write!(f, "{}", v),
+            PropValue::String(v) => write!(f, "{:?}", v),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use crate::generated::{Scene, Texture, TextureFile, TextureType};
+use crate::prelude::{String, Blob};
+
+#[derive(Clone, Debug)]
+pub struct TextureResolver {
+    pub relative_root: Option<PathBuf>,
+    pub search_paths: Vec<PathBuf>,
+    pub case_insensitive: bool,
+}
+
+impl Default for TextureResolver {
+    fn default() -> Self {
+        TextureResolver {
+            relative_root: None,
+            search_paths: Vec::new(),
+            case_insensitive: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TextureResolution {
+    pub path: Option<PathBuf>,
+    pub candidates: Vec<PathBuf>,
+}
+
+impl TextureResolution {
+    pub fn is_resolved(&self) -> bool {
+        self.path.is_some()
+    }
+}
+
+#[cfg(unix)]
+fn path_from_raw(s: &String, raw: &Blob) -> std::string::String {
+    use std::os::unix::ffi::OsStrExt;
+    use std::ffi::OsStr;
+    if s.contains('\u{FFFD}') {
+        if let Some(s) = OsStr::from_bytes(raw).to_str() {
+            return s.to_string();
+        }
+    }
+    s.to_string()
+}
+
+#[cfg(not(unix))]
+fn path_from_raw(s: &String, _raw: &Blob) -> std::string::String {
+    s.to_string()
+}
+
+/// Converts Windows separators to `/` and strips trailing whitespace.
+pub fn normalize_path(path: &str) -> PathBuf {
+    PathBuf::from(path.trim_end().replace('\\', "/"))
+}
+
+fn is_drive_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+fn is_absolute(path: &str) -> bool {
+    is_drive_path(path) || path.starts_with('/') || path.starts_with('\\')
+}
+
+fn file_name(path: &str) -> Option<&str> {
+    path.rsplit(['/', '\\']).next().filter(|s| !s.is_empty())
+}
+
+fn find_case_insensitive(path: &Path) -> Option<PathBuf> {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => {
+                let exact = result.join(name);
+                if exact.exists() {
+                    result = exact;
+                    continue;
+                }
+                let dir = if result.as_os_str().is_empty() { Path::new(".") } else { result.as_path() };
+                // Sorted so that the result does not depend on directory order
+                let lower = name.to_str()?.to_lowercase();
+                let mut matches: Vec<_> = fs::read_dir(dir).ok()?
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.file_name())
+                    .filter(|n| n.to_str().map(|n| n.to_lowercase() == lower).unwrap_or(false))
+                    .collect();
+                matches.sort();
+                result = result.join(matches.first()?);
+            },
+            other => result.push(other.as_os_str()),
+        }
+    }
+    if result.is_file() { Some(result) } else { None }
+}
+
+impl TextureResolver {
+    pub fn for_scene(scene: &Scene) -> TextureResolver {
+        let root = path_from_raw(&scene.metadata.relative_root, &scene.metadata.raw_relative_root);
+        TextureResolver {
+            relative_root: if root.is_empty() { None } else { Some(normalize_path(&root)) },
+            ..Default::default()
+        }
+    }
+
+    pub fn candidates(&self, absolute: &str, relative: &str, filename: &str) -> Vec<PathBuf> {
+        let mut result: Vec<PathBuf> = Vec::new();
+        let mut push = |path: PathBuf| {
+            if !result.contains(&path) {
+                result.push(path);
+            }
+        };
+
+        if !absolute.is_empty() {
+            push(normalize_path(absolute));
+        }
+        if !relative.is_empty() {
+            if is_absolute(relative) {
+                push(normalize_path(relative));
+            } else if let Some(root) = &self.relative_root {
+                push(root.join(normalize_path(relative)));
+            }
+        }
+        if !filename.is_empty() {
+            push(normalize_path(filename));
+        }
+
+        let base_name = file_name(relative).or_else(|| file_name(absolute)).or_else(|| file_name(filename));
+        for search_path in &self.search_paths {
+            if !relative.is_empty() && !is_absolute(relative) {
+                push(search_path.join(normalize_path(relative)));
+            }
+            if let Some(base_name) = base_name {
+                push(search_path.join(base_name));
+            }
+        }
+
+        result
+    }
+
+    pub fn resolve_filenames(&self, absolute: &str, relative: &str, filename: &str) -> TextureResolution {
+        let candidates = self.candidates(absolute, relative, filename);
+        let mut path = candidates.iter().find(|p| p.is_file()).cloned();
+        if path.is_none() && self.case_insensitive {
+            path = candidates.iter().find_map(|p| find_case_insensitive(p));
+        }
+        TextureResolution { path, candidates }
+    }
+
+    pub fn resolve_texture(&self, texture: &Texture) -> TextureResolution {
+        self.resolve_filenames(
+            &path_from_raw(&texture.absolute_filename, &texture.raw_absolute_filename),
+            &path_from_raw(&texture.relative_filename, &texture.raw_relative_filename),
+            &path_from_raw(&texture.filename, &texture.raw_filename))
+    }
+
+    pub fn resolve_texture_file(&self, file: &TextureFile) -> TextureResolution {
+        self.resolve_filenames(
+            &path_from_raw(&file.absolute_filename, &file.raw_absolute_filename),
+            &path_from_raw(&file.relative_filename, &file.raw_relative_filename),
+            &path_from_raw(&file.filename, &file.raw_filename))
+    }
+
+    /// Returns all file textures in `scene` that could not be resolved.
+    pub fn unresolved<'a>(&self, scene: &'a Scene) -> Vec<(&'a Texture, TextureResolution)> {
+        scene.textures.iter()
+            .map(|t| t.as_ref())
+            .filter(|t| t.type_ == TextureType::File && t.has_file)
+            .map(|t| (t, self.resolve_texture(t)))
+            .filter(|(_, r)| !r.is_resolved())
+            .collect()
+    }
+}
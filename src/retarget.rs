@@ -0,0 +1,212 @@
+use crate::generated::{
+    Scene, Anim, Node, Matrix, Vec3, BakedAnim, EvaluateOpts, Result,
+    evaluate_scene, matrix_to_transform, quat_mul, quat_normalize,
+};
+use crate::prelude::Real;
+use crate::math::{axis_vector, vec3_add, vec3_sub, vec3_mul, vec3_dot, vec3_distance, quat_conjugate};
+use crate::character::{rest_pose, CharacterDefinition, HumanoidSlot};
+use crate::constraint::ConstraintPose;
+use crate::bake_keys::{bake_node_keys, BakedNodeKeys};
+
+/// A source node driving a target node.
+#[derive(Clone, Copy)]
+pub struct BonePair {
+    /// `typed_id` of the node in the source scene.
+    pub source: u32,
+    /// `typed_id` of the node in the target scene.
+    pub target: u32,
+    pub slot: Option<HumanoidSlot>,
+    /// World transforms where the source and target bones are considered to match.
+    pub source_rest: Matrix,
+    pub target_rest: Matrix,
+}
+
+impl BonePair {
+    /// Pairs two nodes at their bind pose, or as loaded if they are not in one.
+    pub fn new(source: &Node, target: &Node) -> BonePair {
+        let slot = slot_from_name(&target.element.name).or_else(|| slot_from_name(&source.element.name));
+        BonePair {
+            source: source.element.typed_id,
+            target: target.element.typed_id,
+            slot,
+            source_rest: rest_pose(source).0,
+            target_rest: rest_pose(target).0,
+        }
+    }
+}
+
+fn strip_namespace(name: &str) -> &str {
+    name.rsplit([':', '|']).next().unwrap_or(name)
+}
+
+fn slot_from_name(name: &str) -> Option<HumanoidSlot> {
+    HumanoidSlot::from_name(strip_namespace(name))
+}
+
+// Lowercase name without namespace and separators
+fn normalize_name(name: &str) -> String {
+    strip_namespace(name).chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect()
+}
+
+fn find_node<'a>(scene: &'a Scene, name: &str) -> Option<&'a Node> {
+    scene.nodes.iter().map(|n| &**n).find(|n| !n.is_root && n.element.name == name)
+}
+
+#[derive(Clone, Default)]
+pub struct BoneMap {
+    pub pairs: Vec<BonePair>,
+}
+
+impl BoneMap {
+    /// Maps nodes by `(source, target)` name pairs, names missing from either scene are skipped.
+    pub fn from_names(source: &Scene, target: &Scene, names: &[(&str, &str)]) -> BoneMap {
+        let pairs = names.iter().filter_map(|&(s, t)| {
+            Some(BonePair::new(find_node(source, s)?, find_node(target, t)?))
+        }).collect();
+        BoneMap { pairs }
+    }
+
+    /// Maps nodes whose names match ignoring case, namespaces and separators.
+    pub fn match_names(source: &Scene, target: &Scene) -> BoneMap {
+        let source_names: Vec<String> = source.nodes.iter().map(|n| normalize_name(&n.element.name)).collect();
+        let pairs = target.nodes.iter().filter(|n| !n.is_root).filter_map(|t| {
+            let name = normalize_name(&t.element.name);
+            if name.is_empty() { return None }
+            let ix = source.nodes.iter().zip(&source_names).position(|(s, n)| !s.is_root && *n == name)?;
+            Some(BonePair::new(&source.nodes[ix], t))
+        }).collect();
+        BoneMap { pairs }
+    }
+
+    /// Maps the slots linked in both characters, using their characterization T-poses as rest.
+    pub fn from_characters(source: &CharacterDefinition, target: &CharacterDefinition) -> BoneMap {
+        let pairs = target.links.iter().filter_map(|t| {
+            let s = source.link(t.slot)?;
+            Some(BonePair {
+                source: s.node.element.typed_id,
+                target: t.node.element.typed_id,
+                slot: Some(t.slot),
+                source_rest: s.tpose,
+                target_rest: t.tpose,
+            })
+        }).collect();
+        BoneMap { pairs }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct RetargetOpts {
+    /// Scale of the root translation, defaults to the ratio of the target and source leg lengths.
+    pub root_scale: Option<Real>,
+}
+
+fn translation(m: &Matrix) -> Vec3 {
+    Vec3 { x: m.m03, y: m.m13, z: m.m23 }
+}
+
+// Thigh to ankle length averaged over both legs, or the height of the root
+// above the lowest mapped bone if the legs are not tagged with slots
+fn leg_length(pairs: &[BonePair], root: Option<usize>, up: Vec3, rest: impl Fn(&BonePair) -> Vec3) -> Real {
+    use HumanoidSlot::*;
+    let position = |slot: HumanoidSlot| pairs.iter().find(|p| p.slot == Some(slot)).map(&rest);
+    let lengths: Vec<Real> = [[LeftUpLeg, LeftLeg, LeftFoot], [RightUpLeg, RightLeg, RightFoot]].iter().filter_map(|side| {
+        let (hip, knee, ankle) = (position(side[0])?, position(side[1])?, position(side[2])?);
+        Some(vec3_distance(hip, knee) + vec3_distance(knee, ankle))
+    }).collect();
+    if !lengths.is_empty() {
+        return lengths.iter().sum::<Real>() / lengths.len() as Real;
+    }
+
+    let root = match root {
+        Some(ix) => vec3_dot(rest(&pairs[ix]), up),
+        None => return 0.0,
+    };
+    let lowest = pairs.iter().map(|p| vec3_dot(rest(p), up)).fold(Real::INFINITY, Real::min);
+    root - lowest
+}
+
+/// Transfers source animation to a target skeleton by applying the world space rotation
+/// of each source bone relative to its rest pose on top of the target rest pose.
+/// Both scenes are expected to use the same axes, see `LoadOpts::target_axes`.
+pub struct Retargeter<'a> {
+    source: &'a Scene,
+    target: &'a Scene,
+    // Ordered parents first
+    pairs: Vec<BonePair>,
+    root: Option<usize>,
+    root_scale: Real,
+    rest: ConstraintPose,
+}
+
+impl<'a> Retargeter<'a> {
+    pub fn new(source: &'a Scene, target: &'a Scene, map: &BoneMap, opts: &RetargetOpts) -> Retargeter<'a> {
+        let mut pairs: Vec<BonePair> = Vec::with_capacity(map.pairs.len());
+        for pair in &map.pairs {
+            let valid = (pair.source as usize) < source.nodes.len() && (pair.target as usize) < target.nodes.len();
+            if valid && !pairs.iter().any(|p| p.target == pair.target) {
+                pairs.push(*pair);
+            }
+        }
+        pairs.sort_by_key(|p| target.nodes[p.target as usize].node_depth);
+
+        // Only the root bone is translated, preferring the hips over the reference
+        let root = pairs.iter().position(|p| p.slot == Some(HumanoidSlot::Hips))
+            .or_else(|| (!pairs.is_empty()).then_some(0));
+        let root_scale = opts.root_scale.unwrap_or_else(|| {
+            let source_length = leg_length(&pairs, root, axis_vector(source.settings.axes.up), |p| translation(&p.source_rest));
+            let target_length = leg_length(&pairs, root, axis_vector(target.settings.axes.up), |p| translation(&p.target_rest));
+            if source_length > 0.0 && target_length > 0.0 { target_length / source_length } else { 1.0 }
+        });
+
+        Retargeter { source, target, pairs, root, root_scale, rest: ConstraintPose::from_scene(target) }
+    }
+
+    pub fn pairs(&self) -> &[BonePair] {
+        &self.pairs
+    }
+
+    pub fn root_scale(&self) -> Real {
+        self.root_scale
+    }
+
+    /// Poses the target scene from an evaluated state of the source scene.
+    /// Unmapped target nodes keep their local transforms as loaded.
+    pub fn retarget(&self, source_state: &Scene) -> ConstraintPose {
+        let mut pose = self.rest.clone();
+        for (ix, pair) in self.pairs.iter().enumerate() {
+            let node = &self.target.nodes[pair.target as usize];
+            let current = matrix_to_transform(&source_state.nodes[pair.source as usize].node_to_world);
+            let source_rest = matrix_to_transform(&pair.source_rest);
+            let target_rest = matrix_to_transform(&pair.target_rest);
+
+            let delta = quat_mul(current.rotation, quat_conjugate(source_rest.rotation));
+            let mut world = pose.world_transform(node);
+            world.rotation = quat_normalize(quat_mul(delta, target_rest.rotation));
+
+            if Some(ix) == self.root {
+                let offset = vec3_mul(vec3_sub(current.translation, source_rest.translation), self.root_scale);
+                world.translation = vec3_add(target_rest.translation, offset);
+                pose.local_transforms[pair.target as usize] = pose.world_to_local(node, &world);
+            } else {
+                pose.local_transforms[pair.target as usize].rotation = pose.world_to_local(node, &world).rotation;
+            }
+            pose.update_world(node);
+        }
+        pose
+    }
+
+    /// Evaluates the source `anim` at `time` and retargets it.
+    #[allow(clippy::result_large_err)]
+    pub fn evaluate(&self, anim: &Anim, time: f64) -> Result<ConstraintPose> {
+        let state = evaluate_scene(self.source, anim, time, EvaluateOpts::default())?;
+        Ok(self.retarget(&state))
+    }
+
+    /// Bakes the mapped target nodes at every key time of `baked`, the source animation baked
+    /// with `bake_anim()`.
+    #[allow(clippy::result_large_err)]
+    pub fn bake(&self, anim: &Anim, baked: &BakedAnim) -> Result<Vec<BakedNodeKeys>> {
+        let node_ids: Vec<u32> = self.pairs.iter().map(|p| p.target).collect();
+        bake_node_keys(self.target, &node_ids, baked, |time| Ok(self.evaluate(anim, time)?.local_transforms))
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use crate::generated::{Material, Texture, TextureType, ShaderTextureType, ShaderType, PropType, Vec4};
+use crate::prop_value::{prop_value, PropValue};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShaderNodeKind {
+    Material,
+    File,
+    Layered,
+    Procedural,
+    Shader,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShaderInput {
+    pub name: String,
+    pub prop_type: PropType,
+    pub value: Option<PropValue>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShaderNode {
+    pub kind: ShaderNodeKind,
+    pub element_id: u32,
+    pub name: String,
+    /// Shading model for materials, shader name for shader textures.
+    pub shader_name: String,
+    pub shader_type_id: u64,
+    pub shader_source: String,
+    pub filename: String,
+    pub inputs: Vec<ShaderInput>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShaderEdge {
+    pub from: usize,
+    pub output_index: i64,
+    pub to: usize,
+    pub input: String,
+}
+
+/// Network of textures feeding a material, `nodes[root]` is the material.
+#[derive(Clone, Debug)]
+pub struct ShaderGraph {
+    pub root: usize,
+    pub nodes: Vec<ShaderNode>,
+    pub edges: Vec<ShaderEdge>,
+}
+
+pub const SHADER_GRAPH_JSON_SCHEMA: &str = r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "ufbx shader graph",
+  "type": "object",
+  "required": ["root", "nodes", "edges"],
+  "properties": {
+    "root": { "type": "integer" },
+    "nodes": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["id", "kind", "element_id", "name", "inputs"],
+        "properties": {
+          "id": { "type": "integer" },
+          "kind": { "enum": ["material", "file", "layered", "procedural", "shader"] },
+          "element_id": { "type": "integer" },
+          "name": { "type": "string" },
+          "shader_name": { "type": "string" },
+          "shader_type_id": { "type": "integer" },
+          "shader_source": { "type": "string" },
+          "filename": { "type": "string" },
+          "inputs": {
+            "type": "array",
+            "items": {
+              "type": "object",
+              "required": ["name", "value"],
+              "properties": {
+                "name": { "type": "string" },
+                "value": {
+                  "oneOf": [
+                    { "type": "null" },
+                    { "type": "integer" },
+                    { "type": "string" },
+                    { "type": "array", "items": { "type": ["number", "null"] }, "minItems": 4, "maxItems": 4 }
+                  ]
+                }
+              }
+            }
+          }
+        }
+      }
+    },
+    "edges": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["from", "output", "to", "input"],
+        "properties": {
+          "from": { "type": "integer" },
+          "output": { "type": "integer" },
+          "to": { "type": "integer" },
+          "input": { "type": "string" }
+        }
+      }
+    }
+  }
+}
+"#;
+
+struct GraphBuilder {
+    nodes: Vec<ShaderNode>,
+    edges: Vec<ShaderEdge>,
+    texture_nodes: HashMap<u32, usize>,
+}
+
+// Texture node whose inputs are still being added
+struct PendingNode<'a> {
+    texture: &'a Texture,
+    node: usize,
+    next_input: usize,
+    // Edge to the user of `node`, pushed once all inputs of `node` are done
+    edge: ShaderEdge,
+}
+
+fn shading_model(material: &Material) -> String {
+    if material.shader_type == ShaderType::Unknown {
+        material.shading_model_name.to_string()
+    } else {
+        format!("{:?}", material.shader_type)
+    }
+}
+
+impl GraphBuilder {
+    /// Returns the texture that `texture` represents, select-output shaders are collapsed
+    /// into their source texture and the selected output index is returned with it.
+    /// A chain of select-outputs that loops back is cut at the last texture before the loop.
+    fn resolve<'a>(&self, texture: &'a Texture) -> (&'a Texture, Option<i64>) {
+        let mut visited = HashSet::new();
+        visited.insert(texture.element.element_id);
+        let mut current = texture;
+        let mut output_index = None;
+        loop {
+            let source = current.shader.as_deref()
+                .filter(|s| s.type_ == ShaderTextureType::SelectOutput)
+                .and_then(|s| Some((s.main_texture.as_deref()?, s.main_texture_output_index)));
+            match source {
+                Some((main, index)) if visited.insert(main.element.element_id) => {
+                    output_index.get_or_insert(index);
+                    current = main;
+                },
+                _ => return (current, output_index),
+            }
+        }
+    }
+
+    /// Connects `texture` and everything feeding it to `input` of node `to`.
+    /// Uses an explicit stack as texture networks can be nested arbitrarily deep.
+    fn connect(&mut self, texture: &Texture, output_index: i64, to: usize, input: &str) {
+        let mut stack = Vec::new();
+        self.push_edge(&mut stack, texture, output_index, to, input);
+
+        while let Some(top) = stack.last_mut() {
+            let (texture, node, ix) = (top.texture, top.node, top.next_input);
+            top.next_input += 1;
+
+            let shader_inputs = texture.shader.as_deref().map(|s| s.inputs.as_ref()).unwrap_or(&[]);
+            if let Some(input) = shader_inputs.get(ix) {
+                self.nodes[node].inputs.push(ShaderInput {
+                    name: input.name.to_string(),
+                    prop_type: input.prop.type_,
+                    value: prop_value(&input.prop),
+                });
+                if let Some(source) = input.texture.as_deref().filter(|_| input.texture_enabled) {
+                    self.push_edge(&mut stack, source, input.texture_output_index, node, &input.name);
+                }
+            } else if let Some(layer) = texture.layers.get(ix - shader_inputs.len()) {
+                let name = format!("layer{}", ix - shader_inputs.len());
+                self.nodes[node].inputs.push(ShaderInput {
+                    name: format!("{}.blend_mode", name),
+                    prop_type: PropType::String,
+                    value: Some(PropValue::String(format!("{:?}", layer.blend_mode))),
+                });
+                self.nodes[node].inputs.push(ShaderInput {
+                    name: format!("{}.alpha", name),
+                    prop_type: PropType::Number,
+                    value: Some(PropValue::Number(Vec4 { x: layer.alpha, ..Default::default() })),
+                });
+                self.push_edge(&mut stack, &layer.texture, 0, node, &name);
+            } else if let Some(done) = stack.pop() {
+                self.edges.push(done.edge);
+            }
+        }
+    }
+
+    // Pushes the edge right away if the source node exists, otherwise creates the node
+    // and defers the edge until its inputs have been added
+    fn push_edge<'a>(&mut self, stack: &mut Vec<PendingNode<'a>>, texture: &'a Texture, output_index: i64, to: usize, input: &str) {
+        let (texture, resolved_index) = self.resolve(texture);
+        let output_index = resolved_index.unwrap_or(output_index);
+        let id = texture.element.element_id;
+        let edge = |from| ShaderEdge { from, output_index, to, input: input.to_string() };
+        if let Some(&node) = self.texture_nodes.get(&id) {
+            self.edges.push(edge(node));
+            return;
+        }
+
+        let kind = match texture.type_ {
+            TextureType::File => ShaderNodeKind::File,
+            TextureType::Layered => ShaderNodeKind::Layered,
+            TextureType::Procedural => ShaderNodeKind::Procedural,
+            TextureType::Shader => ShaderNodeKind::Shader,
+        };
+        let shader = texture.shader.as_deref();
+        let node = self.nodes.len();
+        self.nodes.push(ShaderNode {
+            kind,
+            element_id: id,
+            name: texture.element.name.to_string(),
+            shader_name: shader.map(|s| s.shader_name.to_string()).unwrap_or_default(),
+            shader_type_id: shader.map(|s| s.shader_type_id).unwrap_or(0),
+            shader_source: shader.map(|s| s.shader_source.to_string()).unwrap_or_default(),
+            filename: texture.filename.to_string(),
+            inputs: Vec::new(),
+        });
+        self.texture_nodes.insert(id, node);
+        stack.push(PendingNode { texture, node, next_input: 0, edge: edge(node) });
+    }
+}
+
+impl ShaderGraph {
+    pub fn from_material(material: &Material) -> ShaderGraph {
+        let mut builder = GraphBuilder {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            texture_nodes: HashMap::new(),
+        };
+        builder.nodes.push(ShaderNode {
+            kind: ShaderNodeKind::Material,
+            element_id: material.element.element_id,
+            name: material.element.name.to_string(),
+            shader_name: shading_model(material),
+            shader_type_id: 0,
+            shader_source: String::new(),
+            filename: String::new(),
+            inputs: Vec::new(),
+        });
+
+        for tex in &material.textures {
+            let input = if tex.shader_prop.is_empty() { &tex.material_prop } else { &tex.shader_prop };
+            builder.connect(&tex.texture, 0, 0, input);
+        }
+
+        ShaderGraph { root: 0, nodes: builder.nodes, edges: builder.edges }
+    }
+
+    pub fn node_by_element(&self, element_id: u32) -> Option<usize> {
+        self.nodes.iter().position(|n| n.element_id == element_id)
+    }
+
+    pub fn inputs_of(&self, node: usize) -> impl Iterator<Item = &ShaderEdge> {
+        self.edges.iter().filter(move |e| e.to == node)
+    }
+
+    /// Returns the nodes reachable from `root` ordered so that every node comes after its inputs,
+    /// or `None` if the inputs form a cycle and the graph is not a DAG.
+    pub fn topological_order(&self) -> Option<Vec<usize>> {
+        let mut inputs = vec![Vec::new(); self.nodes.len()];
+        for edge in &self.edges {
+            inputs[edge.to].push(edge.from);
+        }
+
+        // 0: unvisited, 1: on the stack, 2: done
+        let mut state = vec![0u8; self.nodes.len()];
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![(self.root, 0)];
+        state[self.root] = 1;
+        while let Some((node, next)) = stack.pop() {
+            match inputs[node].get(next) {
+                Some(&input) => {
+                    stack.push((node, next + 1));
+                    match state[input] {
+                        0 => {
+                            state[input] = 1;
+                            stack.push((input, 0));
+                        },
+                        1 => return None,
+                        _ => {},
+                    }
+                },
+                None => {
+                    state[node] = 2;
+                    order.push(node);
+                },
+            }
+        }
+        Some(order)
+    }
+
+    /// Serializes the graph as JSON following `SHADER_GRAPH_JSON_SCHEMA`.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        write!(out, "{{\"root\":{},\"nodes\":[", self.root).unwrap();
+        for (ix, node) in self.nodes.iter().enumerate() {
+            if ix > 0 { out.push(',') }
+            let kind = match node.kind {
+                ShaderNodeKind::Material => "material",
+                ShaderNodeKind::File => "file",
+                ShaderNodeKind::Layered => "layered",
+                ShaderNodeKind::Procedural => "procedural",
+                ShaderNodeKind::Shader => "shader",
+            };
+            write!(out, "{{\"id\":{},\"kind\":\"{}\",\"element_id\":{},\"name\":", ix, kind, node.element_id).unwrap();
+            write_json_string(&mut out, &node.name);
+            out.push_str(",\"shader_name\":");
+            write_json_string(&mut out, &node.shader_name);
+            write!(out, ",\"shader_type_id\":{},\"shader_source\":", node.shader_type_id).unwrap();
+            write_json_string(&mut out, &node.shader_source);
+            out.push_str(",\"filename\":");
+            write_json_string(&mut out, &node.filename);
+            out.push_str(",\"inputs\":[");
+            for (ix, input) in node.inputs.iter().enumerate() {
+                if ix > 0 { out.push(',') }
+                out.push_str("{\"name\":");
+                write_json_string(&mut out, &input.name);
+                out.push_str(",\"value\":");
+                match &input.value {
+                    None => out.push_str("null"),
+                    Some(PropValue::Int(v)) => write!(out, "{}", v).unwrap(),
+                    Some(PropValue::String(v)) => write_json_string(&mut out, v),
+                    Some(PropValue::Number(v)) => {
+                        out.push('[');
+                        for (ix, c) in [v.x, v.y, v.z, v.w].iter().enumerate() {
+                            if ix > 0 { out.push(',') }
+                            if c.is_finite() { write!(out, "{}", c).unwrap() } else { out.push_str("null") }
+                        }
+                        out.push(']');
+                    },
+                }
+                out.push('}');
+            }
+            out.push_str("]}");
+        }
+        out.push_str("],\"edges\":[");
+        for (ix, edge) in self.edges.iter().enumerate() {
+            if ix > 0 { out.push(',') }
+            write!(out, "{{\"from\":{},\"output\":{},\"to\":{},\"input\":", edge.from, edge.output_index, edge.to).unwrap();
+            write_json_string(&mut out, &edge.input);
+            out.push('}');
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
@@ -0,0 +1,591 @@
+use std::collections::HashMap;
+use crate::generated::{Mesh, Vec2, Vec3};
+use crate::prelude::Real;
+use crate::triangulate_face_vec;
+use crate::math::{vec3_add, vec3_sub, vec3_mul, vec3_dot, vec3_length, vec3_cross, vec3_normalize};
+
+const MAX_SKIN_WEIGHTS: usize = 4;
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct LodVertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub uv: Vec2,
+    /// Strongest influences of the first skin deformer, unused slots have zero weight.
+    pub skin_clusters: [u32; MAX_SKIN_WEIGHTS],
+    pub skin_weights: [Real; MAX_SKIN_WEIGHTS],
+    /// Index into `mesh.vertices[]` of the source mesh.
+    pub vertex: u32,
+    /// Index into `mesh.vertex_indices[]` of the source mesh, can be used to fetch other attributes.
+    pub index: u32,
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct LodPart {
+    /// Material index, same as `MeshPart::index`.
+    pub index: u32,
+    /// First entry of the part in `LodMesh::indices`.
+    pub index_begin: usize,
+    pub num_triangles: usize,
+}
+
+/// Owned indexed triangle mesh, triangles are grouped by material part.
+#[derive(Clone, Default, Debug)]
+pub struct LodMesh {
+    pub vertices: Vec<LodVertex>,
+    pub indices: Vec<u32>,
+    pub parts: Vec<LodPart>,
+    /// Largest relative error of any collapse applied to reach this mesh.
+    pub error: Real,
+}
+
+pub struct SimplifyOpts {
+    /// Stop once the mesh has at most this many triangles.
+    pub target_triangles: usize,
+    /// Maximum error relative to the mesh extent, use `Real::INFINITY` to only respect `target_triangles`.
+    pub max_error: Real,
+    pub normal_weight: Real,
+    pub uv_weight: Real,
+    pub skin_weight: Real,
+    /// Keep open borders in place, eg. for meshes that tile with neighbors.
+    pub lock_borders: bool,
+}
+
+impl Default for SimplifyOpts {
+    fn default() -> Self {
+        SimplifyOpts {
+            target_triangles: 0,
+            max_error: 0.01,
+            normal_weight: 0.05,
+            uv_weight: 1.0,
+            skin_weight: 1.0,
+            lock_borders: false,
+        }
+    }
+}
+
+impl LodMesh {
+    /// Triangulates `mesh` and merges corners with identical attributes.
+    pub fn from_mesh(mesh: &Mesh) -> LodMesh {
+        let skin = mesh.skin_deformers.first();
+        let mut result = LodMesh::default();
+        let mut lookup: HashMap<(u32, [u64; 5]), u32> = HashMap::new();
+        let mut tri_indices = Vec::new();
+
+        for part in &mesh.material_parts {
+            let index_begin = result.indices.len();
+            for &face_ix in &part.face_indices {
+                triangulate_face_vec(&mut tri_indices, mesh, mesh.faces[face_ix as usize]);
+                for &ix in &tri_indices {
+                    let ix = ix as usize;
+                    let vertex = mesh.vertex_indices[ix];
+                    let normal = if mesh.vertex_normal.exists { mesh.vertex_normal[ix] } else { Vec3::default() };
+                    let uv = if mesh.vertex_uv.exists { mesh.vertex_uv[ix] } else { Vec2::default() };
+                    let key = (vertex, [normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits(), uv.x.to_bits(), uv.y.to_bits()]);
+                    let vertices = &mut result.vertices;
+                    let index = *lookup.entry(key).or_insert_with(|| {
+                        let mut lod_vertex = LodVertex {
+                            position: mesh.vertices[vertex as usize],
+                            normal,
+                            uv,
+                            vertex,
+                            index: ix as u32,
+                            ..Default::default()
+                        };
+                        if let Some(skin) = skin {
+                            if let Some(sv) = skin.vertices.get(vertex as usize) {
+                                let begin = sv.weight_begin as usize;
+                                let mut weights: Vec<_> = skin.weights.iter().skip(begin).take(sv.num_weights as usize).copied().collect();
+                                weights.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap_or(std::cmp::Ordering::Equal));
+                                let total: Real = weights.iter().take(MAX_SKIN_WEIGHTS).map(|w| w.weight).sum();
+                                for (slot, w) in weights.iter().take(MAX_SKIN_WEIGHTS).enumerate() {
+                                    lod_vertex.skin_clusters[slot] = w.cluster_index;
+                                    lod_vertex.skin_weights[slot] = if total > 0.0 { w.weight / total } else { 0.0 };
+                                }
+                            }
+                        }
+                        vertices.push(lod_vertex);
+                        (vertices.len() - 1) as u32
+                    });
+                    result.indices.push(index);
+                }
+            }
+            result.parts.push(LodPart {
+                index: part.index,
+                index_begin,
+                num_triangles: (result.indices.len() - index_begin) / 3,
+            });
+        }
+
+        result
+    }
+
+    pub fn num_triangles(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Reduces the triangle count with quadric error edge collapses.
+    ///
+    /// Vertices only collapse onto their neighbors so attributes are never interpolated.
+    /// UV seams and material part boundaries can only collapse along themselves.
+    pub fn simplify(&self, opts: &SimplifyOpts) -> LodMesh {
+        let mut simplifier = Simplifier::new(self, opts);
+        let mut error: Real = 0.0;
+        for _ in 0..256 {
+            if simplifier.tris.len() <= opts.target_triangles { break }
+            match simplifier.pass() {
+                Some(pass_error) => error = error.max(pass_error),
+                None => break,
+            }
+        }
+        simplifier.finish(self.error.max(error))
+    }
+}
+
+/// Generates a chain of LODs, each level is simplified from the previous one.
+pub fn generate_lods(mesh: &Mesh, levels: &[SimplifyOpts]) -> Vec<LodMesh> {
+    let mut lods = Vec::with_capacity(levels.len());
+    let mut prev = LodMesh::from_mesh(mesh);
+    for opts in levels {
+        let lod = prev.simplify(opts);
+        lods.push(lod.clone());
+        prev = lod;
+    }
+    lods
+}
+
+#[derive(Clone, Copy, Default)]
+struct Quadric {
+    // Upper triangle of the symmetric plane matrix: xx xy xz xd yy yz yd zz zd dd
+    m: [Real; 10],
+    weight: Real,
+}
+
+impl Quadric {
+    fn from_plane(n: Vec3, p: Vec3, weight: Real) -> Quadric {
+        let d = -vec3_dot(n, p);
+        let m = [
+            n.x * n.x, n.x * n.y, n.x * n.z, n.x * d,
+            n.y * n.y, n.y * n.z, n.y * d,
+            n.z * n.z, n.z * d,
+            d * d,
+        ];
+        Quadric { m: m.map(|v| v * weight), weight }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (a, b) in self.m.iter_mut().zip(other.m.iter()) {
+            *a += b;
+        }
+        self.weight += other.weight;
+    }
+
+    /// Weighted mean squared distance from `p` to the accumulated planes.
+    fn error(&self, p: Vec3) -> Real {
+        let m = &self.m;
+        let e = m[0] * p.x * p.x + 2.0 * m[1] * p.x * p.y + 2.0 * m[2] * p.x * p.z + 2.0 * m[3] * p.x
+            + m[4] * p.y * p.y + 2.0 * m[5] * p.y * p.z + 2.0 * m[6] * p.y
+            + m[7] * p.z * p.z + 2.0 * m[8] * p.z
+            + m[9];
+        if self.weight > 0.0 { e.abs() / self.weight } else { 0.0 }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EdgeKind {
+    Interior,
+    /// Open border, UV seam or material part boundary.
+    Crease,
+    NonManifold,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VertexKind {
+    Manifold,
+    Crease,
+    Locked,
+}
+
+struct Edge {
+    a: u32,
+    b: u32,
+    kind: EdgeKind,
+    open: bool,
+}
+
+type EdgeTris = HashMap<(u32, u32), Vec<u32>>;
+
+struct Candidate {
+    cost: Real,
+    u: u32,
+    v: u32,
+    remap: Vec<(u32, u32)>,
+}
+
+struct Simplifier<'a> {
+    opts: &'a SimplifyOpts,
+    vertices: &'a [LodVertex],
+    /// Geometric vertex of each wedge.
+    geo: Vec<u32>,
+    positions: Vec<Vec3>,
+    quadrics: Vec<Quadric>,
+    tris: Vec<[u32; 3]>,
+    tri_parts: Vec<u32>,
+    part_indices: Vec<u32>,
+    extent: Real,
+}
+
+fn tri_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    vec3_cross(vec3_sub(b, a), vec3_sub(c, a))
+}
+
+impl<'a> Simplifier<'a> {
+    fn new(mesh: &'a LodMesh, opts: &'a SimplifyOpts) -> Simplifier<'a> {
+        let mut geo_lookup = HashMap::new();
+        let mut positions = Vec::new();
+        let geo: Vec<u32> = mesh.vertices.iter().map(|v| {
+            *geo_lookup.entry(v.vertex).or_insert_with(|| {
+                positions.push(v.position);
+                (positions.len() - 1) as u32
+            })
+        }).collect();
+
+        let mut tris = Vec::with_capacity(mesh.num_triangles());
+        let mut tri_parts = Vec::with_capacity(mesh.num_triangles());
+        for (part_ix, part) in mesh.parts.iter().enumerate() {
+            let indices = &mesh.indices[part.index_begin..part.index_begin + part.num_triangles * 3];
+            for tri in indices.chunks_exact(3) {
+                let (a, b, c) = (geo[tri[0] as usize], geo[tri[1] as usize], geo[tri[2] as usize]);
+                if a == b || b == c || c == a { continue }
+                tris.push([tri[0], tri[1], tri[2]]);
+                tri_parts.push(part_ix as u32);
+            }
+        }
+
+        let (mut min, mut max) = (Vec3 { x: Real::MAX, y: Real::MAX, z: Real::MAX }, Vec3 { x: Real::MIN, y: Real::MIN, z: Real::MIN });
+        for p in &positions {
+            min = Vec3 { x: min.x.min(p.x), y: min.y.min(p.y), z: min.z.min(p.z) };
+            max = Vec3 { x: max.x.max(p.x), y: max.y.max(p.y), z: max.z.max(p.z) };
+        }
+        let extent = if positions.is_empty() { 0.0 } else { vec3_length(vec3_sub(max, min)) };
+
+        let mut simplifier = Simplifier {
+            opts,
+            vertices: &mesh.vertices,
+            geo,
+            quadrics: vec![Quadric::default(); positions.len()],
+            positions,
+            tris,
+            tri_parts,
+            part_indices: mesh.parts.iter().map(|p| p.index).collect(),
+            extent: if extent > 0.0 { extent } else { 1.0 },
+        };
+        simplifier.init_quadrics();
+        simplifier
+    }
+
+    fn tri_geo(&self, tri: usize) -> [u32; 3] {
+        let t = self.tris[tri];
+        [self.geo[t[0] as usize], self.geo[t[1] as usize], self.geo[t[2] as usize]]
+    }
+
+    fn init_quadrics(&mut self) {
+        for tri in 0..self.tris.len() {
+            let g = self.tri_geo(tri);
+            let p = g.map(|g| self.positions[g as usize]);
+            let n = tri_normal(p[0], p[1], p[2]);
+            let area = vec3_length(n) * 0.5;
+            let q = Quadric::from_plane(vec3_normalize(n), p[0], area);
+            for &g in &g {
+                self.quadrics[g as usize].add(&q);
+            }
+        }
+
+        // Penalize moving creases away from their original line
+        let edges = self.build_edges();
+        for edge in &edges.0 {
+            if edge.kind != EdgeKind::Crease { continue }
+            for &tri in &edges.1[&(edge.a, edge.b)] {
+                let g = self.tri_geo(tri as usize);
+                let p = g.map(|g| self.positions[g as usize]);
+                let dir = vec3_sub(self.positions[edge.b as usize], self.positions[edge.a as usize]);
+                let n = vec3_normalize(vec3_cross(dir, tri_normal(p[0], p[1], p[2])));
+                let q = Quadric::from_plane(n, self.positions[edge.a as usize], vec3_dot(dir, dir) * 10.0);
+                self.quadrics[edge.a as usize].add(&q);
+                self.quadrics[edge.b as usize].add(&q);
+            }
+        }
+    }
+
+    /// Returns unique edges and the triangles around each edge.
+    fn build_edges(&self) -> (Vec<Edge>, EdgeTris) {
+        let mut edge_tris = EdgeTris::new();
+        let mut order = Vec::new();
+        for tri in 0..self.tris.len() {
+            let g = self.tri_geo(tri);
+            for c in 0..3 {
+                let (a, b) = (g[c], g[(c + 1) % 3]);
+                let key = (a.min(b), a.max(b));
+                let list = edge_tris.entry(key).or_default();
+                if list.is_empty() { order.push(key) }
+                list.push(tri as u32);
+            }
+        }
+
+        let edges = order.into_iter().map(|(a, b)| {
+            let tris = &edge_tris[&(a, b)];
+            let kind = match tris.len() {
+                1 => EdgeKind::Crease,
+                2 => {
+                    let (t0, t1) = (tris[0] as usize, tris[1] as usize);
+                    let wedge = |tri: usize, g: u32| {
+                        let gs = self.tri_geo(tri);
+                        let c = gs.iter().position(|&x| x == g).unwrap();
+                        (self.tris[tri][c], gs[(c + 1) % 3] == if g == a { b } else { a })
+                    };
+                    let (wa0, fwd0) = wedge(t0, a);
+                    let (wa1, fwd1) = wedge(t1, a);
+                    let (wb0, _) = wedge(t0, b);
+                    let (wb1, _) = wedge(t1, b);
+                    if fwd0 == fwd1 {
+                        EdgeKind::NonManifold
+                    } else if self.tri_parts[t0] != self.tri_parts[t1] || wa0 != wa1 || wb0 != wb1 {
+                        EdgeKind::Crease
+                    } else {
+                        EdgeKind::Interior
+                    }
+                },
+                _ => EdgeKind::NonManifold,
+            };
+            Edge { a, b, kind, open: tris.len() == 1 }
+        }).collect();
+
+        (edges, edge_tris)
+    }
+
+    fn attribute_error(&self, from: [u32; 3], to: u32) -> Real {
+        let v = &self.vertices;
+        let (u, a, b) = (&v[from[0] as usize], &v[from[1] as usize], &v[from[2] as usize]);
+        let target = &v[to as usize];
+
+        // Barycentric coordinates of the new position in the old triangle plane
+        let (e0, e1, e2) = (vec3_sub(a.position, u.position), vec3_sub(b.position, u.position), vec3_sub(target.position, u.position));
+        let (d00, d01, d11) = (vec3_dot(e0, e0), vec3_dot(e0, e1), vec3_dot(e1, e1));
+        let (d20, d21) = (vec3_dot(e2, e0), vec3_dot(e2, e1));
+        let denom = d00 * d11 - d01 * d01;
+        if denom.abs() <= Real::EPSILON * d00 * d11 { return 0.0 }
+        let wa = (d11 * d20 - d01 * d21) / denom;
+        let wb = (d00 * d21 - d01 * d20) / denom;
+        let wu = 1.0 - wa - wb;
+
+        let normal = vec3_add(vec3_add(vec3_mul(u.normal, wu), vec3_mul(a.normal, wa)), vec3_mul(b.normal, wb));
+        let dn = vec3_sub(normal, target.normal);
+        let du = u.uv.x * wu + a.uv.x * wa + b.uv.x * wb - target.uv.x;
+        let dv = u.uv.y * wu + a.uv.y * wa + b.uv.y * wb - target.uv.y;
+
+        let weight_of = |vertex: &LodVertex, cluster: u32| -> Real {
+            (0..MAX_SKIN_WEIGHTS)
+                .filter(|&i| vertex.skin_clusters[i] == cluster && vertex.skin_weights[i] > 0.0)
+                .map(|i| vertex.skin_weights[i])
+                .sum()
+        };
+        let mut skin = 0.0;
+        for (src, slot) in [u, a, b, target].iter().flat_map(|x| (0..MAX_SKIN_WEIGHTS).map(move |s| (*x, s))) {
+            if src.skin_weights[slot] <= 0.0 { continue }
+            let cluster = src.skin_clusters[slot];
+            let predicted = weight_of(u, cluster) * wu + weight_of(a, cluster) * wa + weight_of(b, cluster) * wb;
+            let d = predicted - weight_of(target, cluster);
+            skin = Real::max(skin, d * d);
+        }
+
+        self.opts.normal_weight * vec3_dot(dn, dn) + self.opts.uv_weight * (du * du + dv * dv) + self.opts.skin_weight * skin
+    }
+
+    fn collapse_cost(&self, u: u32, v: u32, vertex_tris: &[u32]) -> Option<Candidate> {
+        // Map every wedge of `u` to the wedge of `v` on the same side of any seam
+        let mut remap: Vec<(u32, u32)> = Vec::new();
+        for &tri in vertex_tris {
+            let g = self.tri_geo(tri as usize);
+            if let (Some(cu), Some(cv)) = (g.iter().position(|&x| x == u), g.iter().position(|&x| x == v)) {
+                let (wu, wv) = (self.tris[tri as usize][cu], self.tris[tri as usize][cv]);
+                match remap.iter().find(|r| r.0 == wu) {
+                    Some(r) if r.1 != wv => return None,
+                    Some(_) => {},
+                    None => remap.push((wu, wv)),
+                }
+            }
+        }
+
+        let pv = self.positions[v as usize];
+        let mut attribute_error: Real = 0.0;
+        for &tri in vertex_tris {
+            let g = self.tri_geo(tri as usize);
+            if g.contains(&v) { continue }
+            let c = g.iter().position(|&x| x == u).unwrap();
+            let t = self.tris[tri as usize];
+            let wu = t[c];
+            let wv = remap.iter().find(|r| r.0 == wu)?.1;
+
+            let p = g.map(|g| self.positions[g as usize]);
+            let old = tri_normal(p[0], p[1], p[2]);
+            let mut moved = p;
+            moved[c] = pv;
+            let new = tri_normal(moved[0], moved[1], moved[2]);
+            if vec3_dot(old, new) <= 1e-2 * vec3_length(old) * vec3_length(new) {
+                return None;
+            }
+
+            let from = [wu, t[(c + 1) % 3], t[(c + 2) % 3]];
+            attribute_error = attribute_error.max(self.attribute_error(from, wv));
+        }
+
+        let position_error = self.quadrics[u as usize].error(pv) / (self.extent * self.extent);
+        let cost = (position_error + attribute_error).sqrt();
+        Some(Candidate { cost, u, v, remap })
+    }
+
+    /// Runs a round of independent collapses, returns the largest error or `None` if nothing collapsed.
+    fn pass(&mut self) -> Option<Real> {
+        let num_geo = self.positions.len();
+        let mut vt_first = vec![0usize; num_geo + 1];
+        for tri in 0..self.tris.len() {
+            for g in self.tri_geo(tri) {
+                vt_first[g as usize + 1] += 1;
+            }
+        }
+        for g in 0..num_geo {
+            vt_first[g + 1] += vt_first[g];
+        }
+        let mut vt_list = vec![0u32; vt_first[num_geo]];
+        let mut cursor = vt_first.clone();
+        for tri in 0..self.tris.len() {
+            for g in self.tri_geo(tri) {
+                vt_list[cursor[g as usize]] = tri as u32;
+                cursor[g as usize] += 1;
+            }
+        }
+        let vertex_tris = |g: u32| &vt_list[vt_first[g as usize]..vt_first[g as usize + 1]];
+
+        let (edges, _) = self.build_edges();
+        let mut creases = vec![0u32; num_geo];
+        let mut locked = vec![false; num_geo];
+        for edge in &edges {
+            match edge.kind {
+                EdgeKind::Interior => {},
+                EdgeKind::Crease => {
+                    creases[edge.a as usize] += 1;
+                    creases[edge.b as usize] += 1;
+                    if edge.open && self.opts.lock_borders {
+                        locked[edge.a as usize] = true;
+                        locked[edge.b as usize] = true;
+                    }
+                },
+                EdgeKind::NonManifold => {
+                    locked[edge.a as usize] = true;
+                    locked[edge.b as usize] = true;
+                },
+            }
+        }
+
+        let kinds: Vec<VertexKind> = (0..num_geo).map(|g| {
+            let mut wedges: Vec<u32> = vertex_tris(g as u32).iter().map(|&tri| {
+                let c = self.tri_geo(tri as usize).iter().position(|&x| x == g as u32).unwrap();
+                self.tris[tri as usize][c]
+            }).collect();
+            wedges.sort_unstable();
+            wedges.dedup();
+            if locked[g] || wedges.len() > 2 {
+                VertexKind::Locked
+            } else if creases[g] == 0 && wedges.len() == 1 {
+                VertexKind::Manifold
+            } else if creases[g] == 2 {
+                VertexKind::Crease
+            } else {
+                VertexKind::Locked
+            }
+        }).collect();
+
+        let mut candidates = Vec::new();
+        for edge in &edges {
+            if edge.kind == EdgeKind::NonManifold { continue }
+            let mut best: Option<Candidate> = None;
+            for &(u, v) in &[(edge.a, edge.b), (edge.b, edge.a)] {
+                let allowed = match kinds[u as usize] {
+                    VertexKind::Manifold => edge.kind == EdgeKind::Interior,
+                    VertexKind::Crease => edge.kind == EdgeKind::Crease,
+                    VertexKind::Locked => false,
+                };
+                if !allowed { continue }
+                if let Some(c) = self.collapse_cost(u, v, vertex_tris(u)) {
+                    match &best {
+                        Some(b) if b.cost <= c.cost => {},
+                        _ => best = Some(c),
+                    }
+                }
+            }
+            candidates.extend(best);
+        }
+        candidates.sort_by(|a, b| a.cost.total_cmp(&b.cost).then(a.u.cmp(&b.u)).then(a.v.cmp(&b.v)));
+
+        let mut touched = vec![false; num_geo];
+        let mut wedge_remap: Vec<u32> = (0..self.vertices.len() as u32).collect();
+        let mut num_tris = self.tris.len();
+        let mut max_error: Option<Real> = None;
+        for c in &candidates {
+            if c.cost > self.opts.max_error || num_tris <= self.opts.target_triangles { break }
+            if touched[c.u as usize] || touched[c.v as usize] { continue }
+
+            for &tri in vertex_tris(c.u) {
+                let g = self.tri_geo(tri as usize);
+                if g.contains(&c.v) { num_tris -= 1 }
+                for g in g {
+                    touched[g as usize] = true;
+                }
+            }
+            for &(wu, wv) in &c.remap {
+                wedge_remap[wu as usize] = wv;
+            }
+            let q = self.quadrics[c.u as usize];
+            self.quadrics[c.v as usize].add(&q);
+            max_error = Some(max_error.unwrap_or(0.0).max(c.cost));
+        }
+
+        let max_error = max_error?;
+        let geo = &self.geo;
+        let mut kept = 0;
+        for tri in 0..self.tris.len() {
+            let t = self.tris[tri].map(|w| wedge_remap[w as usize]);
+            let g = t.map(|w| geo[w as usize]);
+            if g[0] == g[1] || g[1] == g[2] || g[2] == g[0] { continue }
+            self.tris[kept] = t;
+            self.tri_parts[kept] = self.tri_parts[tri];
+            kept += 1;
+        }
+        self.tris.truncate(kept);
+        self.tri_parts.truncate(kept);
+        Some(max_error)
+    }
+
+    fn finish(self, error: Real) -> LodMesh {
+        let mut result = LodMesh { error, ..Default::default() };
+        let mut new_index = vec![u32::MAX; self.vertices.len()];
+        let mut part_tris: Vec<Vec<usize>> = vec![Vec::new(); self.part_indices.len()];
+        for (tri, &part) in self.tri_parts.iter().enumerate() {
+            part_tris[part as usize].push(tri);
+        }
+        for (&index, tris) in self.part_indices.iter().zip(part_tris.iter()) {
+            let index_begin = result.indices.len();
+            for &tri in tris {
+                for &w in &self.tris[tri] {
+                    if new_index[w as usize] == u32::MAX {
+                        new_index[w as usize] = result.vertices.len() as u32;
+                        result.vertices.push(self.vertices[w as usize]);
+                    }
+                    result.indices.push(new_index[w as usize]);
+                }
+            }
+            result.parts.push(LodPart { index, index_begin, num_triangles: tris.len() });
+        }
+        result
+    }
+}
@@ -0,0 +1,258 @@
+use crate::generated::{LineCurve, Vec2, Vec3};
+use crate::prelude::Real;
+use crate::trim::{SurfaceMesh, SurfaceVertex};
+use crate::math::{vec3_add, vec3_sub, vec3_mul, vec3_dot, vec3_length, vec3_distance, vec3_cross, vec3_normalize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SweepProfile {
+    /// Closed circular cross section with `SweepOpts::sides` segments.
+    Tube,
+    /// Flat strip of width `2 * radius` facing along the frame normal.
+    Ribbon,
+}
+
+pub struct SweepOpts {
+    pub profile: SweepProfile,
+    pub sides: usize,
+    pub radius: Real,
+    /// Radius multipliers spaced evenly over the normalized arc length, empty for a constant radius.
+    pub radius_scale: Vec<Real>,
+    /// Close open tube ends with flat caps.
+    pub caps: bool,
+    /// Initial frame normal, perpendicular to the first tangent after projection.
+    /// Picked automatically if zero.
+    pub reference_normal: Vec3,
+    /// V texture coordinate per unit of arc length.
+    pub uv_scale: Real,
+}
+
+impl Default for SweepOpts {
+    fn default() -> Self {
+        SweepOpts {
+            profile: SweepProfile::Tube,
+            sides: 8,
+            radius: 0.1,
+            radius_scale: Vec::new(),
+            caps: true,
+            reference_normal: Vec3::default(),
+            uv_scale: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    position: Vec3,
+    tangent: Vec3,
+    normal: Vec3,
+    binormal: Vec3,
+    distance: Real,
+}
+
+fn any_perpendicular(t: Vec3) -> Vec3 {
+    let axis = if t.x.abs() <= t.y.abs() && t.x.abs() <= t.z.abs() {
+        Vec3 { x: 1.0, y: 0.0, z: 0.0 }
+    } else if t.y.abs() <= t.z.abs() {
+        Vec3 { x: 0.0, y: 1.0, z: 0.0 }
+    } else {
+        Vec3 { x: 0.0, y: 0.0, z: 1.0 }
+    };
+    vec3_normalize(vec3_cross(vec3_cross(t, axis), t))
+}
+
+fn reflect(v: Vec3, axis: Vec3, axis_len2: Real) -> Vec3 {
+    vec3_sub(v, vec3_mul(axis, 2.0 * vec3_dot(axis, v) / axis_len2))
+}
+
+// Rotation minimizing frames using the double reflection method (Wang et al. 2008)
+fn transport_frames(points: &[Vec3], closed: bool, reference_normal: Vec3) -> Vec<Frame> {
+    let n = points.len();
+    let segment = |i: usize| vec3_normalize(vec3_sub(points[(i + 1) % n], points[i]));
+    let num_segments = if closed { n } else { n - 1 };
+    let tangents: Vec<Vec3> = (0..n).map(|i| {
+        let prev = if i > 0 { Some(i - 1) } else if closed { Some(n - 1) } else { None };
+        let next = if i < num_segments { Some(i) } else { None };
+        match (prev, next) {
+            (Some(a), Some(b)) => {
+                let t = vec3_normalize(vec3_add(segment(a), segment(b)));
+                if vec3_length(t) > 0.0 { t } else { segment(b) }
+            },
+            (Some(a), None) => segment(a),
+            (None, Some(b)) => segment(b),
+            (None, None) => Vec3::default(),
+        }
+    }).collect();
+
+    let t0 = tangents[0];
+    let projected = vec3_sub(reference_normal, vec3_mul(t0, vec3_dot(reference_normal, t0)));
+    let mut normal = if vec3_length(projected) > 1e-12 { vec3_normalize(projected) } else { any_perpendicular(t0) };
+
+    let mut frames = Vec::with_capacity(n + 1);
+    let mut distance = 0.0;
+    for i in 0..n {
+        if i > 0 {
+            let v1 = vec3_sub(points[i], points[i - 1]);
+            let c1 = vec3_dot(v1, v1);
+            distance += c1.sqrt();
+            if c1 > 0.0 {
+                let n_l = reflect(normal, v1, c1);
+                let t_l = reflect(tangents[i - 1], v1, c1);
+                let v2 = vec3_sub(tangents[i], t_l);
+                let c2 = vec3_dot(v2, v2);
+                normal = if c2 > 0.0 { reflect(n_l, v2, c2) } else { n_l };
+            }
+            // Remove drift so the frame stays orthonormal
+            normal = vec3_normalize(vec3_sub(normal, vec3_mul(tangents[i], vec3_dot(normal, tangents[i]))));
+        }
+        let binormal = vec3_cross(tangents[i], normal);
+        frames.push(Frame { position: points[i], tangent: tangents[i], normal, binormal, distance });
+    }
+
+    if closed {
+        // Transport once more to the start and spread the remaining twist over the loop
+        let first = frames[0];
+        let v1 = vec3_sub(points[0], points[n - 1]);
+        let c1 = vec3_dot(v1, v1);
+        distance += c1.sqrt();
+        let mut end_normal = frames[n - 1].normal;
+        if c1 > 0.0 {
+            let n_l = reflect(end_normal, v1, c1);
+            let t_l = reflect(tangents[n - 1], v1, c1);
+            let v2 = vec3_sub(first.tangent, t_l);
+            let c2 = vec3_dot(v2, v2);
+            end_normal = if c2 > 0.0 { reflect(n_l, v2, c2) } else { n_l };
+        }
+        let twist = vec3_dot(vec3_cross(end_normal, first.normal), first.tangent)
+            .atan2(vec3_dot(end_normal, first.normal));
+        if distance > 0.0 {
+            for frame in &mut frames {
+                let (sin, cos) = (twist * frame.distance / distance).sin_cos();
+                let normal = vec3_add(vec3_mul(frame.normal, cos), vec3_mul(frame.binormal, sin));
+                frame.binormal = vec3_cross(frame.tangent, normal);
+                frame.normal = normal;
+            }
+        }
+        frames.push(Frame { distance, ..first });
+    }
+    frames
+}
+
+fn radius_at(opts: &SweepOpts, t: Real) -> Real {
+    let scale = match opts.radius_scale.len() {
+        0 => 1.0,
+        1 => opts.radius_scale[0],
+        len => {
+            let x = t.clamp(0.0, 1.0) * (len - 1) as Real;
+            let i = (x.floor() as usize).min(len - 2);
+            let f = x - i as Real;
+            opts.radius_scale[i] * (1.0 - f) + opts.radius_scale[i + 1] * f
+        },
+    };
+    opts.radius * scale
+}
+
+fn push_vertex(mesh: &mut SurfaceMesh, position: Vec3, normal: Vec3, u: Real, v: Real) -> u32 {
+    mesh.vertices.push(SurfaceVertex { position, normal, uv: Vec2 { x: u, y: v } });
+    (mesh.vertices.len() - 1) as u32
+}
+
+/// Sweeps the profile along a polyline and appends the result to `mesh`.
+/// Closed polylines should not repeat the first point at the end.
+pub fn sweep_polyline_into(mesh: &mut SurfaceMesh, points: &[Vec3], closed: bool, opts: &SweepOpts) {
+    // Coincident points have no tangent so drop them
+    let mut unique: Vec<Vec3> = Vec::with_capacity(points.len());
+    for &p in points {
+        if unique.last().into_iter().all(|&q| vec3_distance(p, q) > 0.0) {
+            unique.push(p);
+        }
+    }
+    let closed = closed && unique.len() > 2;
+    if closed && vec3_distance(unique[0], unique[unique.len() - 1]) <= 1e-9 {
+        unique.pop();
+    }
+    if unique.len() < 2 { return }
+
+    let frames = transport_frames(&unique, closed, opts.reference_normal);
+    let length = frames[frames.len() - 1].distance;
+    let (columns, angles): (usize, Vec<Real>) = match opts.profile {
+        SweepProfile::Tube => {
+            let sides = opts.sides.max(3);
+            (sides + 1, (0..=sides).map(|j| j as Real / sides as Real * std::f64::consts::TAU).collect())
+        },
+        SweepProfile::Ribbon => (2, Vec::new()),
+    };
+
+    let base = mesh.vertices.len() as u32;
+    for frame in &frames {
+        let radius = radius_at(opts, if length > 0.0 { frame.distance / length } else { 0.0 });
+        let v = frame.distance * opts.uv_scale;
+        match opts.profile {
+            SweepProfile::Tube => {
+                for (j, &angle) in angles.iter().enumerate() {
+                    let (sin, cos) = angle.sin_cos();
+                    let normal = vec3_add(vec3_mul(frame.normal, cos), vec3_mul(frame.binormal, sin));
+                    let u = j as Real / (columns - 1) as Real;
+                    push_vertex(mesh, vec3_add(frame.position, vec3_mul(normal, radius)), normal, u, v);
+                }
+            },
+            SweepProfile::Ribbon => {
+                let side = vec3_mul(frame.binormal, radius);
+                push_vertex(mesh, vec3_sub(frame.position, side), frame.normal, 0.0, v);
+                push_vertex(mesh, vec3_add(frame.position, side), frame.normal, 1.0, v);
+            },
+        }
+    }
+
+    let columns = columns as u32;
+    for i in 0..frames.len() as u32 - 1 {
+        for j in 0..columns - 1 {
+            let a = base + i * columns + j;
+            let (b, c, d) = (a + 1, a + columns + 1, a + columns);
+            mesh.indices.extend_from_slice(&[a, b, c, a, c, d]);
+        }
+    }
+
+    if opts.caps && !closed && opts.profile == SweepProfile::Tube {
+        for &(frame, end) in &[(frames[0], false), (frames[frames.len() - 1], true)] {
+            let radius = radius_at(opts, if end { 1.0 } else { 0.0 });
+            let normal = if end { frame.tangent } else { vec3_mul(frame.tangent, -1.0) };
+            let center = push_vertex(mesh, frame.position, normal, 0.5, 0.5);
+            for &angle in &angles {
+                let (sin, cos) = angle.sin_cos();
+                let dir = vec3_add(vec3_mul(frame.normal, cos), vec3_mul(frame.binormal, sin));
+                let (u, v) = (0.5 + 0.5 * cos, 0.5 + 0.5 * if end { sin } else { -sin });
+                push_vertex(mesh, vec3_add(frame.position, vec3_mul(dir, radius)), normal, u, v);
+            }
+            for j in 0..columns - 1 {
+                let (a, b) = (center + 1 + j, center + 2 + j);
+                if end {
+                    mesh.indices.extend_from_slice(&[center, a, b]);
+                } else {
+                    mesh.indices.extend_from_slice(&[center, b, a]);
+                }
+            }
+        }
+    }
+}
+
+/// Sweeps the profile along a polyline, see `sweep_polyline_into()`.
+pub fn sweep_polyline(points: &[Vec3], closed: bool, opts: &SweepOpts) -> SurfaceMesh {
+    let mut mesh = SurfaceMesh::default();
+    sweep_polyline_into(&mut mesh, points, closed, opts);
+    mesh
+}
+
+/// Sweeps every segment of `curve`, segments ending at their first point are treated as closed.
+/// NURBS curves can be converted with `tessellate_nurbs_curve()` first.
+pub fn sweep_line_curve(curve: &LineCurve, opts: &SweepOpts) -> SurfaceMesh {
+    let mut mesh = SurfaceMesh::default();
+    for segment in curve.segments.iter() {
+        let begin = segment.index_begin as usize;
+        let points: Vec<Vec3> = (begin..begin + segment.num_indices as usize)
+            .map(|i| curve.control_points[curve.point_indices[i] as usize])
+            .collect();
+        let closed = points.len() > 2 && vec3_distance(points[0], points[points.len() - 1]) <= 1e-9;
+        sweep_polyline_into(&mut mesh, &points, closed, opts);
+    }
+    mesh
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use crate::generated::{NurbsCurve, NurbsSurface, NurbsBasis, Vec2, Vec3, evaluate_nurbs_curve, evaluate_nurbs_surface};
+use crate::prelude::Real;
+use crate::math::{vec3_add, vec3_sub, vec3_mul, vec3_dot, vec3_length, vec3_distance, vec3_cross, vec3_normalize};
+use crate::trim::{SurfaceMesh, SurfaceVertex};
+
+pub(crate) fn surface_vertex(surface: &NurbsSurface, u: Real, v: Real) -> SurfaceVertex {
+    let point = evaluate_nurbs_surface(surface, u, v);
+    let mut normal = vec3_normalize(vec3_cross(point.derivative_u, point.derivative_v));
+    if surface.flip_normals {
+        normal = vec3_mul(normal, -1.0);
+    }
+    SurfaceVertex { position: point.position, normal, uv: Vec2 { x: u, y: v } }
+}
+
+// Lattice coordinates of every span need to fit in `u64`
+const MAX_DEPTH: u32 = 30;
+
+/// Limits for adaptive tessellation, a limit of zero is ignored.
+pub struct AdaptiveTessellateOpts {
+    /// Maximum distance between the surface and the tessellation.
+    pub max_chordal_error: Real,
+    /// Maximum angle in radians between normals (or curve tangents) of adjacent vertices.
+    pub max_normal_angle: Real,
+    /// Maximum distance between adjacent vertices in scene units, zero disables the limit.
+    pub max_edge_length: Real,
+    /// Each span is split into at most `2^max_depth` segments per direction, clamped to 30.
+    pub max_depth: u32,
+}
+
+impl Default for AdaptiveTessellateOpts {
+    fn default() -> Self {
+        AdaptiveTessellateOpts {
+            max_chordal_error: 1e-3,
+            max_normal_angle: 10.0f64.to_radians(),
+            max_edge_length: 0.0,
+            max_depth: 8,
+        }
+    }
+}
+
+fn angle_between(a: Vec3, b: Vec3) -> Real {
+    let (la, lb) = (vec3_length(a), vec3_length(b));
+    if la <= 0.0 || lb <= 0.0 { return 0.0 }
+    (vec3_dot(a, b) / (la * lb)).clamp(-1.0, 1.0).acos()
+}
+
+fn distance_to_segment(p: Vec3, a: Vec3, b: Vec3) -> Real {
+    let ab = vec3_sub(b, a);
+    let len2 = vec3_dot(ab, ab);
+    let t = if len2 > 0.0 { (vec3_dot(vec3_sub(p, a), ab) / len2).clamp(0.0, 1.0) } else { 0.0 };
+    vec3_distance(p, vec3_add(a, vec3_mul(ab, t)))
+}
+
+impl AdaptiveTessellateOpts {
+    fn exceeds(&self, chordal_error: Real, angle: Real, length: Real) -> bool {
+        (self.max_chordal_error > 0.0 && chordal_error > self.max_chordal_error)
+            || (self.max_normal_angle > 0.0 && angle > self.max_normal_angle)
+            || (self.max_edge_length > 0.0 && length > self.max_edge_length)
+    }
+}
+
+// Parameters on a lattice of `2^depth` steps per span so that shared points evaluate identically
+struct Lattice<'a> {
+    spans: &'a [Real],
+    resolution: u64,
+}
+
+impl Lattice<'_> {
+    fn new(basis: &NurbsBasis, depth: u32) -> Lattice<'_> {
+        Lattice { spans: &basis.spans, resolution: 1 << depth.min(MAX_DEPTH) }
+    }
+
+    fn num_spans(&self) -> u64 {
+        self.spans.len().saturating_sub(1) as u64
+    }
+
+    fn param(&self, x: u64) -> Real {
+        let (span, local) = ((x / self.resolution) as usize, x % self.resolution);
+        if span + 1 >= self.spans.len() { return self.spans[self.spans.len() - 1] }
+        let (a, b) = (self.spans[span], self.spans[span + 1]);
+        if local == 0 { a } else { a + (b - a) * local as Real / self.resolution as Real }
+    }
+}
+
+/// Samples a curve by recursively splitting spans until `opts` is satisfied,
+/// returns the parameters of the samples in increasing order.
+pub fn tessellate_nurbs_curve_adaptive(curve: &NurbsCurve, opts: &AdaptiveTessellateOpts) -> Vec<Real> {
+    let lattice = Lattice::new(&curve.basis, opts.max_depth);
+    let mut params = Vec::new();
+    let num_spans = lattice.num_spans();
+    if num_spans == 0 { return params }
+
+    let mut stack = Vec::new();
+    for span in (0..num_spans).rev() {
+        stack.push((span * lattice.resolution, (span + 1) * lattice.resolution));
+    }
+    while let Some((x0, x1)) = stack.pop() {
+        let (t0, t1) = (lattice.param(x0), lattice.param(x1));
+        let (p0, p1) = (evaluate_nurbs_curve(curve, t0), evaluate_nurbs_curve(curve, t1));
+        let error = [0.25, 0.5, 0.75].iter()
+            .map(|&f| distance_to_segment(evaluate_nurbs_curve(curve, t0 + (t1 - t0) * f).position, p0.position, p1.position))
+            .fold(0.0, Real::max);
+        let angle = angle_between(p0.derivative, p1.derivative);
+        let length = vec3_distance(p0.position, p1.position);
+        if x1 - x0 > 1 && opts.exceeds(error, angle, length) {
+            let mid = (x0 + x1) / 2;
+            stack.push((mid, x1));
+            stack.push((x0, mid));
+        } else {
+            params.push(t0);
+        }
+    }
+    params.push(lattice.param(num_spans * lattice.resolution));
+    params
+}
+
+#[derive(Clone, Copy)]
+struct Cell {
+    x0: u64,
+    x1: u64,
+    y0: u64,
+    y1: u64,
+}
+
+/// Tessellates a surface with a quadtree per span that is refined until `opts`
+/// is satisfied, neighboring cells of different sizes are stitched without cracks.
+pub fn tessellate_nurbs_surface_adaptive(surface: &NurbsSurface, opts: &AdaptiveTessellateOpts) -> SurfaceMesh {
+    let lattice_u = Lattice::new(&surface.basis_u, opts.max_depth);
+    let lattice_v = Lattice::new(&surface.basis_v, opts.max_depth);
+    let mut mesh = SurfaceMesh::default();
+    if lattice_u.num_spans() == 0 || lattice_v.num_spans() == 0 { return mesh }
+
+    let mut leaves: Vec<Cell> = Vec::new();
+    let mut stack = Vec::new();
+    for sv in (0..lattice_v.num_spans()).rev() {
+        for su in (0..lattice_u.num_spans()).rev() {
+            let (ru, rv) = (lattice_u.resolution, lattice_v.resolution);
+            stack.push(Cell { x0: su * ru, x1: (su + 1) * ru, y0: sv * rv, y1: (sv + 1) * rv });
+        }
+    }
+
+    while let Some(cell) = stack.pop() {
+        let (u0, u1) = (lattice_u.param(cell.x0), lattice_u.param(cell.x1));
+        let (v0, v1) = (lattice_v.param(cell.y0), lattice_v.param(cell.y1));
+        let fs = [0.0, 0.25, 0.5, 0.75, 1.0];
+        let grid: Vec<Vec<SurfaceVertex>> = fs.iter().map(|&fv| {
+            fs.iter().map(|&fu| surface_vertex(surface, u0 + (u1 - u0) * fu, v0 + (v1 - v0) * fv)).collect()
+        }).collect();
+
+        // Deviation from straight lines along each direction on the border and middle isolines
+        let (mut error_u, mut error_v, mut angle_u, mut angle_v, mut length_u, mut length_v) = (0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        for &k in &[0, 2, 4] {
+            let (a, b) = (grid[k][0], grid[k][4]);
+            let (c, d) = (grid[0][k], grid[4][k]);
+            for (row, p) in grid[1..4].iter().zip(&grid[k][1..4]) {
+                error_u = Real::max(error_u, distance_to_segment(p.position, a.position, b.position));
+                error_v = Real::max(error_v, distance_to_segment(row[k].position, c.position, d.position));
+            }
+            angle_u = Real::max(angle_u, angle_between(a.normal, b.normal));
+            angle_v = Real::max(angle_v, angle_between(c.normal, d.normal));
+            length_u = Real::max(length_u, vec3_distance(a.position, b.position));
+            length_v = Real::max(length_v, vec3_distance(c.position, d.position));
+        }
+
+        let split_u = cell.x1 - cell.x0 > 1 && opts.exceeds(error_u, angle_u, length_u);
+        let split_v = cell.y1 - cell.y0 > 1 && opts.exceeds(error_v, angle_v, length_v);
+        let (xm, ym) = ((cell.x0 + cell.x1) / 2, (cell.y0 + cell.y1) / 2);
+        match (split_u, split_v) {
+            (false, false) => leaves.push(cell),
+            (true, false) => {
+                stack.push(Cell { x0: xm, ..cell });
+                stack.push(Cell { x1: xm, ..cell });
+            },
+            (false, true) => {
+                stack.push(Cell { y0: ym, ..cell });
+                stack.push(Cell { y1: ym, ..cell });
+            },
+            (true, true) => {
+                stack.push(Cell { x0: xm, y0: ym, ..cell });
+                stack.push(Cell { x1: xm, y0: ym, ..cell });
+                stack.push(Cell { x0: xm, y1: ym, ..cell });
+                stack.push(Cell { x1: xm, y1: ym, ..cell });
+            },
+        }
+    }
+
+    // Corners of all leaves along each lattice line, used to find T-junctions
+    let mut rows: HashMap<u64, Vec<u64>> = HashMap::new();
+    let mut columns: HashMap<u64, Vec<u64>> = HashMap::new();
+    for c in &leaves {
+        for &(x, y) in &[(c.x0, c.y0), (c.x1, c.y0), (c.x1, c.y1), (c.x0, c.y1)] {
+            rows.entry(y).or_default().push(x);
+            columns.entry(x).or_default().push(y);
+        }
+    }
+    for line in rows.values_mut().chain(columns.values_mut()) {
+        line.sort_unstable();
+        line.dedup();
+    }
+    let between = |line: &Vec<u64>, a: u64, b: u64| {
+        let (lo, hi) = (a.min(b), a.max(b));
+        let start = line.partition_point(|&x| x <= lo);
+        let end = line.partition_point(|&x| x < hi);
+        let mut points = line[start..end.max(start)].to_vec();
+        if a > b {
+            points.reverse();
+        }
+        points
+    };
+
+    let mut lookup: HashMap<(u64, u64), u32> = HashMap::new();
+    let mut vertex = |mesh: &mut SurfaceMesh, x: u64, y: u64| *lookup.entry((x, y)).or_insert_with(|| {
+        mesh.vertices.push(surface_vertex(surface, lattice_u.param(x), lattice_v.param(y)));
+        (mesh.vertices.len() - 1) as u32
+    });
+
+    for c in &leaves {
+        let mut boundary: Vec<(u64, u64)> = vec![(c.x0, c.y0)];
+        boundary.extend(between(&rows[&c.y0], c.x0, c.x1).into_iter().map(|x| (x, c.y0)));
+        boundary.push((c.x1, c.y0));
+        boundary.extend(between(&columns[&c.x1], c.y0, c.y1).into_iter().map(|y| (c.x1, y)));
+        boundary.push((c.x1, c.y1));
+        boundary.extend(between(&rows[&c.y1], c.x1, c.x0).into_iter().map(|x| (x, c.y1)));
+        boundary.push((c.x0, c.y1));
+        boundary.extend(between(&columns[&c.x0], c.y1, c.y0).into_iter().map(|y| (c.x0, y)));
+
+        let ring: Vec<u32> = boundary.iter().map(|&(x, y)| vertex(&mut mesh, x, y)).collect();
+        let mut triangles: Vec<[u32; 3]> = Vec::new();
+        if ring.len() == 4 {
+            // Split along the shorter diagonal
+            let p = |i: usize| mesh.vertices[ring[i] as usize].position;
+            if vec3_distance(p(0), p(2)) <= vec3_distance(p(1), p(3)) {
+                triangles.push([ring[0], ring[1], ring[2]]);
+                triangles.push([ring[0], ring[2], ring[3]]);
+            } else {
+                triangles.push([ring[0], ring[1], ring[3]]);
+                triangles.push([ring[1], ring[2], ring[3]]);
+            }
+        } else {
+            let u = (lattice_u.param(c.x0) + lattice_u.param(c.x1)) * 0.5;
+            let v = (lattice_v.param(c.y0) + lattice_v.param(c.y1)) * 0.5;
+            mesh.vertices.push(surface_vertex(surface, u, v));
+            let center = (mesh.vertices.len() - 1) as u32;
+            for i in 0..ring.len() {
+                triangles.push([center, ring[i], ring[(i + 1) % ring.len()]]);
+            }
+        }
+
+        for t in triangles {
+            if surface.flip_normals {
+                mesh.indices.extend_from_slice(&[t[0], t[2], t[1]]);
+            } else {
+                mesh.indices.extend_from_slice(&t);
+            }
+        }
+    }
+    mesh
+}
@@ -0,0 +1,103 @@
+use crate::generated::{Thumbnail, ThumbnailFormat};
+
+impl Thumbnail {
+    /// Returns the thumbnail as tightly packed RGBA rows from top to bottom,
+    /// `None` if the thumbnail is missing or has an unknown format.
+    pub fn to_rgba8(&self) -> Option<Vec<u8>> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let channels = match self.format {
+            ThumbnailFormat::Rgb24 => 3,
+            ThumbnailFormat::Rgba32 => 4,
+            _ => return None,
+        };
+        let stride = width * channels;
+        if width == 0 || height == 0 || self.data.len() < stride * height {
+            return None;
+        }
+
+        let mut result = Vec::with_capacity(width * height * 4);
+        for row in self.data[..stride * height].chunks_exact(stride).rev() {
+            for px in row.chunks_exact(channels) {
+                let alpha = if channels == 4 { px[3] } else { 255 };
+                result.extend_from_slice(&[px[0], px[1], px[2], alpha]);
+            }
+        }
+        Some(result)
+    }
+
+    pub fn to_png(&self) -> Option<Vec<u8>> {
+        let rgba = self.to_rgba8()?;
+        encode_png_rgba8(self.width, self.height, &rgba)
+    }
+}
+
+fn crc32(data: &[u8], mut crc: u32) -> u32 {
+    crc = !crc;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+fn write_chunk(out: &mut Vec<u8>, tag: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(tag);
+    out.extend_from_slice(data);
+    let crc = crc32(data, crc32(tag, 0));
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encodes top-down RGBA pixels as a PNG file using uncompressed deflate blocks.
+/// Returns `None` if `rgba` is not `width * height * 4` bytes long.
+pub fn encode_png_rgba8(width: u32, height: u32, rgba: &[u8]) -> Option<Vec<u8>> {
+    let stride = width as usize * 4;
+    if rgba.len() != stride * height as usize { return None }
+
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in rgba.chunks_exact(stride.max(1)).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        zlib.push(if blocks.peek().is_none() { 1 } else { 0 });
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib);
+    write_chunk(&mut out, b"IEND", &[]);
+    Some(out)
+}
@@ -0,0 +1,300 @@
+use crate::generated::{Mesh, Face, TopoEdge, TopoFlags, compute_topology, topo_next_vertex_edge, topo_prev_vertex_edge};
+
+const NO_INDEX: u32 = !0;
+
+fn opt_index(ix: u32) -> Option<usize> {
+    if ix == NO_INDEX { None } else { Some(ix as usize) }
+}
+
+/// Half-edge connectivity of a mesh.
+///
+/// Half-edges are indexed like `mesh.vertex_indices`, half-edge `i` starts
+/// at the vertex of index `i` and ends at the vertex of the next face corner.
+#[derive(Clone, Debug)]
+pub struct MeshTopology {
+    edges: Vec<TopoEdge>,
+    faces: Vec<Face>,
+    vertex_indices: Vec<u32>,
+    vertex_first: Vec<usize>,
+    vertex_edges: Vec<u32>,
+    boundary_vertices: Vec<bool>,
+    non_manifold_vertices: Vec<bool>,
+}
+
+impl MeshTopology {
+    pub fn new(mesh: &Mesh) -> MeshTopology {
+        let mut edges = vec![TopoEdge::default(); mesh.num_indices];
+        compute_topology(mesh, &mut edges);
+
+        let num_vertices = mesh.num_vertices;
+        let mut topo = MeshTopology {
+            edges,
+            faces: mesh.faces.to_vec(),
+            vertex_indices: mesh.vertex_indices.to_vec(),
+            vertex_first: vec![0; num_vertices + 1],
+            vertex_edges: Vec::new(),
+            boundary_vertices: vec![false; num_vertices],
+            non_manifold_vertices: vec![false; num_vertices],
+        };
+
+        // Bucket outgoing half-edges per vertex
+        for &v in &topo.vertex_indices {
+            topo.vertex_first[v as usize + 1] += 1;
+        }
+        for v in 0..num_vertices {
+            topo.vertex_first[v + 1] += topo.vertex_first[v];
+        }
+        let mut unordered = vec![0u32; topo.vertex_indices.len()];
+        let mut cursor = topo.vertex_first.clone();
+        for (he, &v) in topo.vertex_indices.iter().enumerate() {
+            unordered[cursor[v as usize]] = he as u32;
+            cursor[v as usize] += 1;
+        }
+
+        // Sort each bucket into fans so that consecutive half-edges are rotational neighbors
+        let mut visited = vec![false; topo.edges.len()];
+        let mut ordered = Vec::with_capacity(unordered.len());
+        for v in 0..num_vertices {
+            let bucket = &unordered[topo.vertex_first[v]..topo.vertex_first[v + 1]];
+            let mut num_fans = 0;
+            for &he in bucket {
+                let he = he as usize;
+                if visited[he] { continue }
+                num_fans += 1;
+
+                let mut start = he;
+                for _ in 0..bucket.len() {
+                    match topo.prev_vertex_edge(start) {
+                        Some(prev) if prev != he && !visited[prev] => start = prev,
+                        _ => break,
+                    }
+                }
+
+                let mut cur = start;
+                loop {
+                    visited[cur] = true;
+                    ordered.push(cur as u32);
+                    match topo.next_vertex_edge(cur) {
+                        Some(next) if !visited[next] => cur = next,
+                        _ => break,
+                    }
+                }
+            }
+
+            for &he in bucket {
+                let he = he as usize;
+                let incoming = topo.prev(he);
+                if topo.is_boundary_edge(he) || topo.is_boundary_edge(incoming) {
+                    topo.boundary_vertices[v] = true;
+                }
+                if topo.is_non_manifold_edge(he) || topo.is_non_manifold_edge(incoming) {
+                    topo.non_manifold_vertices[v] = true;
+                }
+            }
+            if num_fans > 1 {
+                topo.non_manifold_vertices[v] = true;
+            }
+        }
+        topo.vertex_edges = ordered;
+
+        topo
+    }
+
+    pub fn edges(&self) -> &[TopoEdge] {
+        &self.edges
+    }
+
+    pub fn num_half_edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.vertex_first.len() - 1
+    }
+
+    pub fn num_faces(&self) -> usize {
+        self.faces.len()
+    }
+
+    /// Vertex the half-edge starts from.
+    pub fn vertex(&self, he: usize) -> usize {
+        self.vertex_indices[he] as usize
+    }
+
+    /// Vertex the half-edge points to.
+    pub fn end_vertex(&self, he: usize) -> usize {
+        self.vertex(self.next(he))
+    }
+
+    pub fn next(&self, he: usize) -> usize {
+        self.edges[he].next as usize
+    }
+
+    pub fn prev(&self, he: usize) -> usize {
+        self.edges[he].prev as usize
+    }
+
+    /// Half-edge on the opposite side, `None` for boundary and non-manifold edges.
+    pub fn twin(&self, he: usize) -> Option<usize> {
+        opt_index(self.edges[he].twin)
+    }
+
+    pub fn face(&self, he: usize) -> usize {
+        self.edges[he].face as usize
+    }
+
+    /// Index into `mesh.edges[]` if the mesh has explicit edges.
+    pub fn mesh_edge(&self, he: usize) -> Option<usize> {
+        opt_index(self.edges[he].edge)
+    }
+
+    pub fn flags(&self, he: usize) -> TopoFlags {
+        self.edges[he].flags
+    }
+
+    /// Next outgoing half-edge around `vertex(he)`, `None` at a boundary.
+    pub fn next_vertex_edge(&self, he: usize) -> Option<usize> {
+        opt_index(topo_next_vertex_edge(&self.edges, he as u32))
+            .filter(|&next| self.vertex(next) == self.vertex(he))
+    }
+
+    /// Previous outgoing half-edge around `vertex(he)`, `None` at a boundary.
+    pub fn prev_vertex_edge(&self, he: usize) -> Option<usize> {
+        opt_index(topo_prev_vertex_edge(&self.edges, he as u32))
+            .filter(|&prev| self.vertex(prev) == self.vertex(he))
+    }
+
+    pub fn is_non_manifold_edge(&self, he: usize) -> bool {
+        self.edges[he].flags.has_any(TopoFlags::NON_MANIFOLD)
+    }
+
+    /// Edge used by only a single face.
+    pub fn is_boundary_edge(&self, he: usize) -> bool {
+        self.edges[he].twin == NO_INDEX && !self.is_non_manifold_edge(he)
+    }
+
+    pub fn is_boundary_vertex(&self, vertex: usize) -> bool {
+        self.boundary_vertices[vertex]
+    }
+
+    /// Vertex touching a non-manifold edge or joining multiple separate fans.
+    pub fn is_non_manifold_vertex(&self, vertex: usize) -> bool {
+        self.non_manifold_vertices[vertex]
+    }
+
+    pub fn is_closed(&self) -> bool {
+        (0..self.edges.len()).all(|he| !self.is_boundary_edge(he))
+    }
+
+    pub fn is_manifold(&self) -> bool {
+        !self.non_manifold_vertices.iter().any(|&v| v)
+    }
+
+    /// Half-edges of `face` in winding order.
+    pub fn face_edges(&self, face: usize) -> impl Iterator<Item = usize> {
+        let face = self.faces[face];
+        face.index_begin as usize..(face.index_begin + face.num_indices) as usize
+    }
+
+    /// Outgoing half-edges of `vertex` in rotational order, fan by fan.
+    pub fn vertex_edges(&self, vertex: usize) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_edges[self.vertex_first[vertex]..self.vertex_first[vertex + 1]]
+            .iter().map(|&he| he as usize)
+    }
+
+    /// Faces around `vertex` in rotational order.
+    pub fn vertex_faces(&self, vertex: usize) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_edges(vertex).map(move |he| self.face(he))
+    }
+
+    /// Vertices connected to `vertex` by an edge in rotational order.
+    /// Neighbors across non-manifold edges may be repeated.
+    pub fn vertex_neighbors(&self, vertex: usize) -> impl Iterator<Item = usize> + '_ {
+        self.vertex_edges(vertex).flat_map(move |he| {
+            let incoming = self.prev(he);
+            let open = if self.twin(incoming).is_none() { Some(self.vertex(incoming)) } else { None };
+            Some(self.end_vertex(he)).into_iter().chain(open)
+        })
+    }
+
+    /// Chains of boundary half-edges, closed loops end where they started.
+    pub fn boundary_loops(&self) -> BoundaryLoops<'_> {
+        BoundaryLoops { topo: self, visited: vec![false; self.edges.len()], index: 0 }
+    }
+
+    /// Groups of faces connected through shared vertices.
+    pub fn connected_components(&self) -> Components<'_> {
+        Components { topo: self, visited: vec![false; self.faces.len()], index: 0 }
+    }
+}
+
+pub struct BoundaryLoops<'a> {
+    topo: &'a MeshTopology,
+    visited: Vec<bool>,
+    index: usize,
+}
+
+impl Iterator for BoundaryLoops<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let topo = self.topo;
+        while self.index < topo.edges.len() {
+            let start = self.index;
+            self.index += 1;
+            if self.visited[start] || !topo.is_boundary_edge(start) { continue }
+
+            let mut result = Vec::new();
+            let mut cur = start;
+            loop {
+                self.visited[cur] = true;
+                result.push(cur);
+                let next = topo.vertex_edges(topo.end_vertex(cur))
+                    .find(|&he| topo.is_boundary_edge(he) && (he == start || !self.visited[he]));
+                match next {
+                    Some(next) if next != start => cur = next,
+                    _ => break,
+                }
+            }
+            return Some(result);
+        }
+        None
+    }
+}
+
+pub struct Components<'a> {
+    topo: &'a MeshTopology,
+    visited: Vec<bool>,
+    index: usize,
+}
+
+impl Iterator for Components<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let topo = self.topo;
+        while self.index < topo.faces.len() {
+            let seed = self.index;
+            self.index += 1;
+            if self.visited[seed] { continue }
+
+            self.visited[seed] = true;
+            let mut result = vec![seed];
+            let mut ix = 0;
+            while ix < result.len() {
+                let face = result[ix];
+                ix += 1;
+                for he in topo.face_edges(face) {
+                    for neighbor in topo.vertex_faces(topo.vertex(he)) {
+                        if !self.visited[neighbor] {
+                            self.visited[neighbor] = true;
+                            result.push(neighbor);
+                        }
+                    }
+                }
+            }
+            return Some(result);
+        }
+        None
+    }
+}
@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use crate::generated::{Scene, NurbsSurface, NurbsCurve, NurbsTrimSurface, NurbsTrimBoundary, NurbsBasis, Vec2, Vec3};
+use crate::generated::{as_nurbs_surface, as_nurbs_curve, as_nurbs_trim_boundary, evaluate_nurbs_curve};
+use crate::prelude::Real;
+use crate::tessellate::surface_vertex;
+
+type Point = (Real, Real);
+
+/// Closed trim loop made of curves in the UV space of the trimmed surface.
+pub struct TrimLoop<'a> {
+    pub boundary: &'a NurbsTrimBoundary,
+    /// Curves in connection order, consecutive curves may be reversed relative to each other.
+    pub curves: Vec<&'a NurbsCurve>,
+    /// `OuterFlag` of the boundary.
+    pub is_outer: bool,
+    /// Winding of the loop in UV space, outer loops are usually counter-clockwise.
+    pub clockwise: bool,
+}
+
+pub struct TrimmedSurface<'a> {
+    pub trim: &'a NurbsTrimSurface,
+    pub surface: &'a NurbsSurface,
+    pub loops: Vec<TrimLoop<'a>>,
+}
+
+pub struct TrimTessellateOpts {
+    /// Grid cells per surface span, zero to use `NurbsSurface::span_subdivision_u`.
+    pub span_subdivision_u: usize,
+    pub span_subdivision_v: usize,
+    /// Segments per span of the trim curves.
+    pub curve_span_subdivision: usize,
+}
+
+impl Default for TrimTessellateOpts {
+    fn default() -> Self {
+        TrimTessellateOpts {
+            span_subdivision_u: 0,
+            span_subdivision_v: 0,
+            curve_span_subdivision: 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SurfaceVertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub uv: Vec2,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct SurfaceMesh {
+    pub vertices: Vec<SurfaceVertex>,
+    /// Triangle list, counter-clockwise when viewed from the normal side.
+    pub indices: Vec<u32>,
+}
+
+impl SurfaceMesh {
+    pub fn num_triangles(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+/// Returns the trimmed surfaces of `scene` that reference a NURBS surface.
+pub fn trimmed_surfaces(scene: &Scene) -> Vec<TrimmedSurface<'_>> {
+    scene.nurbs_trim_surfaces.iter().filter_map(|trim| TrimmedSurface::new(trim)).collect()
+}
+
+fn sample_basis(basis: &NurbsBasis, subdivision: usize) -> Vec<Real> {
+    let mut ts = Vec::new();
+    for span in basis.spans.windows(2) {
+        for i in 0..subdivision {
+            ts.push(span[0] + (span[1] - span[0]) * i as Real / subdivision as Real);
+        }
+    }
+    if let Some(&last) = basis.spans.last() {
+        ts.push(last);
+    }
+    ts
+}
+
+fn signed_area(points: &[Point]) -> Real {
+    let mut area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        area += a.0 * b.1 - b.0 * a.1;
+    }
+    area * 0.5
+}
+
+fn cross(o: Point, a: Point, b: Point) -> Real {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+fn point_in_polygon(p: Point, polygon: &[Point]) -> bool {
+    let mut inside = false;
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        if (a.1 > p.1) != (b.1 > p.1) && p.0 < a.0 + (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+impl<'a> TrimLoop<'a> {
+    fn new(boundary: &'a NurbsTrimBoundary) -> TrimLoop<'a> {
+        let curves = boundary.element.connections_dst.iter()
+            .filter_map(|c| as_nurbs_curve(&c.src))
+            .collect();
+        let is_outer = boundary.element.props.find_prop("OuterFlag").is_some_and(|p| p.value_int != 0);
+        let mut trim_loop = TrimLoop { boundary, curves, is_outer, clockwise: false };
+        trim_loop.clockwise = signed_area(&trim_loop.sample_points(TrimTessellateOpts::default().curve_span_subdivision)) < 0.0;
+        trim_loop
+    }
+
+    /// Samples the loop into a closed polyline in UV space, without repeating the first point.
+    pub fn sample(&self, span_subdivision: usize) -> Vec<Vec2> {
+        self.sample_points(span_subdivision).into_iter().map(|(x, y)| Vec2 { x, y }).collect()
+    }
+
+    fn sample_points(&self, span_subdivision: usize) -> Vec<Point> {
+        let mut points: Vec<Point> = Vec::new();
+        for (ix, curve) in self.curves.iter().enumerate() {
+            let mut samples: Vec<Point> = sample_basis(&curve.basis, span_subdivision.max(1)).into_iter()
+                .map(|t| evaluate_nurbs_curve(curve, t).position)
+                .map(|p| (p.x, p.y))
+                .collect();
+            if samples.is_empty() { continue }
+
+            // Chain curves end to end, reversing them if they are stored backwards
+            let dist = |a: Point, b: Point| (a.0 - b.0).hypot(a.1 - b.1);
+            let flip = match points.last() {
+                Some(&last) => dist(last, samples[samples.len() - 1]) < dist(last, samples[0]),
+                None => match self.curves.get(ix + 1).map(|next| {
+                    let basis = &next.basis;
+                    let a = evaluate_nurbs_curve(next, basis.t_min).position;
+                    let b = evaluate_nurbs_curve(next, basis.t_max).position;
+                    ((a.x, a.y), (b.x, b.y))
+                }) {
+                    Some((a, b)) => {
+                        let first = samples[0];
+                        dist(first, a).min(dist(first, b)) < dist(samples[samples.len() - 1], a).min(dist(samples[samples.len() - 1], b))
+                    },
+                    None => false,
+                },
+            };
+            if flip {
+                samples.reverse();
+            }
+            points.extend(samples);
+        }
+
+        let mut result: Vec<Point> = Vec::with_capacity(points.len());
+        for p in points {
+            if result.last() != Some(&p) {
+                result.push(p);
+            }
+        }
+        while result.len() > 1 && result.first() == result.last() {
+            result.pop();
+        }
+        result
+    }
+}
+
+impl<'a> TrimmedSurface<'a> {
+    /// Resolves the surface and boundaries connected to `trim`, `None` if there is no surface.
+    pub fn new(trim: &'a NurbsTrimSurface) -> Option<TrimmedSurface<'a>> {
+        let children = &trim.element.connections_dst;
+        let surface = children.iter().find_map(|c| as_nurbs_surface(&c.src))?;
+        let loops = children.iter()
+            .filter_map(|c| as_nurbs_trim_boundary(&c.src))
+            .map(TrimLoop::new)
+            .collect();
+        Some(TrimmedSurface { trim, surface, loops })
+    }
+
+    /// Tessellates the trimmed region of the surface into a watertight triangle mesh.
+    /// Cells where intersecting trim loops prevent bridging a hole are left empty.
+    pub fn tessellate(&self, opts: &TrimTessellateOpts) -> SurfaceMesh {
+        let surface = self.surface;
+        let subdivision = |opt: usize, surface: u32| if opt > 0 { opt } else if surface > 0 { surface as usize } else { 4 };
+        let grid_u = sample_basis(&surface.basis_u, subdivision(opts.span_subdivision_u, surface.span_subdivision_u));
+        let grid_v = sample_basis(&surface.basis_v, subdivision(opts.span_subdivision_v, surface.span_subdivision_v));
+        if grid_u.len() < 2 || grid_v.len() < 2 { return SurfaceMesh::default() }
+
+        let loops: Vec<Vec<Point>> = self.loops.iter()
+            .map(|l| l.sample_points(opts.curve_span_subdivision))
+            .filter(|l| l.len() >= 3)
+            .collect();
+        let grid = Grid::new(grid_u, grid_v);
+        let loops = grid.prepare_loops(loops);
+
+        let mut triangles: Vec<[Point; 3]> = Vec::new();
+        let mut cells = vec![CellLoops::default(); grid.num_cells()];
+        for (ix, l) in loops.iter().enumerate() {
+            grid.split_loop(ix, l, &mut cells);
+        }
+        for (cell, cell_loops) in cells.iter().enumerate() {
+            grid.triangulate_cell(cell, cell_loops, &loops, &mut triangles);
+        }
+
+        let mut mesh = SurfaceMesh::default();
+        let mut lookup: HashMap<(u64, u64), u32> = HashMap::new();
+        for tri in &triangles {
+            let tri = if surface.flip_normals { [tri[0], tri[2], tri[1]] } else { *tri };
+            for &(u, v) in &tri {
+                let index = *lookup.entry((u.to_bits(), v.to_bits())).or_insert_with(|| {
+                    mesh.vertices.push(surface_vertex(surface, u, v));
+                    (mesh.vertices.len() - 1) as u32
+                });
+                mesh.indices.push(index);
+            }
+        }
+        mesh
+    }
+}
+
+#[derive(Clone, Default)]
+struct CellLoops {
+    /// Open pieces of loops entering and exiting the cell, region on the left.
+    pieces: Vec<Vec<Point>>,
+    /// Loops that lie completely within the cell.
+    closed: Vec<usize>,
+}
+
+struct Grid {
+    us: Vec<Real>,
+    vs: Vec<Real>,
+}
+
+fn find_cell(lines: &[Real], x: Real) -> usize {
+    let ix = lines.partition_point(|&l| l <= x);
+    ix.clamp(1, lines.len() - 1) - 1
+}
+
+impl Grid {
+    fn new(us: Vec<Real>, vs: Vec<Real>) -> Grid {
+        Grid { us, vs }
+    }
+
+    fn num_cells(&self) -> usize {
+        (self.us.len() - 1) * (self.vs.len() - 1)
+    }
+
+    fn cell_bounds(&self, cell: usize) -> (Point, Point) {
+        let nu = self.us.len() - 1;
+        let (x, y) = (cell % nu, cell / nu);
+        ((self.us[x], self.vs[y]), (self.us[x + 1], self.vs[y + 1]))
+    }
+
+    fn cell_at(&self, p: Point) -> usize {
+        find_cell(&self.vs, p.1) * (self.us.len() - 1) + find_cell(&self.us, p.0)
+    }
+
+    /// Clamps loops to the domain, moves vertices off grid lines and orients
+    /// loops so that the trimmed region is always on the left.
+    fn prepare_loops(&self, loops: Vec<Vec<Point>>) -> Vec<Vec<Point>> {
+        let nudge = |x: Real, lines: &[Real]| {
+            let (min, max) = (lines[0], lines[lines.len() - 1]);
+            let x = x.clamp(min, max);
+            let eps = (max - min) * 1e-9;
+            match lines.iter().find(|&&l| (x - l).abs() <= eps) {
+                Some(&l) if l >= max => l - eps * 2.0,
+                Some(&l) => l + eps * 2.0,
+                None => x,
+            }
+        };
+        let mut loops: Vec<Vec<Point>> = loops.into_iter()
+            .map(|l| l.into_iter().map(|(u, v)| (nudge(u, &self.us), nudge(v, &self.vs))).collect())
+            .collect();
+
+        for ix in 0..loops.len() {
+            let depth = (0..loops.len())
+                .filter(|&other| other != ix && point_in_polygon(loops[ix][0], &loops[other]))
+                .count();
+            let ccw = signed_area(&loops[ix]) > 0.0;
+            if ccw != (depth % 2 == 0) {
+                loops[ix].reverse();
+            }
+        }
+        loops
+    }
+
+    /// Inserts grid line crossings into loop `ix` and distributes its pieces to cells.
+    fn split_loop(&self, ix: usize, points: &[Point], cells: &mut [CellLoops]) {
+        let mut split: Vec<Point> = Vec::new();
+        for (i, &a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            split.push(a);
+            let mut crossings: Vec<(Real, Point)> = Vec::new();
+            for &u in &self.us {
+                if (a.0 < u && u < b.0) || (b.0 < u && u < a.0) {
+                    let t = (u - a.0) / (b.0 - a.0);
+                    crossings.push((t, (u, a.1 + (b.1 - a.1) * t)));
+                }
+            }
+            for &v in &self.vs {
+                if (a.1 < v && v < b.1) || (b.1 < v && v < a.1) {
+                    let t = (v - a.1) / (b.1 - a.1);
+                    crossings.push((t, (a.0 + (b.0 - a.0) * t, v)));
+                }
+            }
+            crossings.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(std::cmp::Ordering::Equal));
+            split.extend(crossings.into_iter().map(|c| c.1));
+        }
+
+        let n = split.len();
+        let segment_cell = |i: usize| {
+            let (a, b) = (split[i], split[(i + 1) % n]);
+            self.cell_at(((a.0 + b.0) * 0.5, (a.1 + b.1) * 0.5))
+        };
+        let start = match (0..n).find(|&i| segment_cell(i) != segment_cell((i + n - 1) % n)) {
+            Some(start) => start,
+            None => {
+                cells[segment_cell(0)].closed.push(ix);
+                return;
+            }
+        };
+
+        let mut piece = vec![split[start]];
+        for k in 0..n {
+            let i = (start + k) % n;
+            let cell = segment_cell(i);
+            piece.push(split[(i + 1) % n]);
+            if segment_cell((i + 1) % n) != cell || k == n - 1 {
+                cells[cell].pieces.push(std::mem::replace(&mut piece, vec![split[(i + 1) % n]]));
+            }
+        }
+    }
+
+    fn triangulate_cell(&self, cell: usize, cell_loops: &CellLoops, loops: &[Vec<Point>], triangles: &mut Vec<[Point; 3]>) {
+        let (min, max) = self.cell_bounds(cell);
+        let corners = [min, (max.0, min.1), max, (min.0, max.1)];
+
+        let mut outers: Vec<Vec<Point>> = Vec::new();
+        if cell_loops.pieces.is_empty() {
+            let center = ((min.0 + max.0) * 0.5, (min.1 + max.1) * 0.5);
+            let inside = loops.is_empty() || loops.iter().filter(|l| point_in_polygon(center, l)).count() % 2 == 1;
+            if inside {
+                outers.push(corners.to_vec());
+            }
+        } else {
+            outers.extend(trace_cell(&cell_loops.pieces, min, max));
+        }
+
+        let mut holes: Vec<Vec<Point>> = Vec::new();
+        for &ix in &cell_loops.closed {
+            if signed_area(&loops[ix]) > 0.0 {
+                outers.push(loops[ix].clone());
+            } else {
+                holes.push(loops[ix].clone());
+            }
+        }
+
+        // Assign each hole to the smallest polygon containing it
+        let mut polygon_holes: Vec<Vec<Vec<Point>>> = vec![Vec::new(); outers.len()];
+        for hole in holes {
+            let owner = (0..outers.len())
+                .filter(|&o| point_in_polygon(hole[0], &outers[o]))
+                .min_by(|&a, &b| signed_area(&outers[a]).partial_cmp(&signed_area(&outers[b])).unwrap_or(std::cmp::Ordering::Equal));
+            if let Some(owner) = owner {
+                polygon_holes[owner].push(hole);
+            }
+        }
+
+        let scale = (max.0 - min.0) * (max.1 - min.1);
+        for (outer, holes) in outers.into_iter().zip(polygon_holes) {
+            // Holes can only fail to bridge if the trim loops intersect, leave the region open
+            if let Some(polygon) = bridge_holes(outer, holes) {
+                triangulate_polygon(polygon, scale * 1e-14, triangles);
+            }
+        }
+    }
+}
+
+// Position of a point on the cell boundary as counter-clockwise distance in `[0, 4)`
+fn perimeter(p: Point, min: Point, max: Point) -> Real {
+    let (w, h) = (max.0 - min.0, max.1 - min.1);
+    if p.1 == min.1 {
+        (p.0 - min.0) / w
+    } else if p.0 == max.0 {
+        1.0 + (p.1 - min.1) / h
+    } else if p.1 == max.1 {
+        2.0 + (max.0 - p.0) / w
+    } else {
+        3.0 + (max.1 - p.1) / h
+    }
+}
+
+// Connects loop pieces into closed polygons by walking the cell boundary counter-clockwise
+fn trace_cell(pieces: &[Vec<Point>], min: Point, max: Point) -> Vec<Vec<Point>> {
+    let corners = [(0.0, min), (1.0, (max.0, min.1)), (2.0, max), (3.0, (min.0, max.1))];
+    let entries: Vec<Real> = pieces.iter().map(|p| perimeter(p[0], min, max)).collect();
+    let mut visited = vec![false; pieces.len()];
+    let mut polygons = Vec::new();
+
+    for first in 0..pieces.len() {
+        if visited[first] { continue }
+        let mut polygon: Vec<Point> = Vec::new();
+        let mut cur = first;
+        loop {
+            visited[cur] = true;
+            polygon.extend_from_slice(&pieces[cur]);
+            let exit = perimeter(pieces[cur][pieces[cur].len() - 1], min, max);
+            let next = (0..pieces.len())
+                .filter(|&p| p == first || !visited[p])
+                .min_by(|&a, &b| {
+                    let da = (entries[a] - exit).rem_euclid(4.0);
+                    let db = (entries[b] - exit).rem_euclid(4.0);
+                    da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(first);
+            let dist = (entries[next] - exit).rem_euclid(4.0);
+            let mut passed: Vec<(Real, Point)> = corners.iter()
+                .map(|&(at, corner)| ((at - exit).rem_euclid(4.0), corner))
+                .filter(|&(d, _)| d > 0.0 && d < dist)
+                .collect();
+            passed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            polygon.extend(passed.into_iter().map(|(_, corner)| corner));
+            if next == first { break }
+            cur = next;
+        }
+
+        polygon.dedup();
+        while polygon.len() > 1 && polygon.first() == polygon.last() {
+            polygon.pop();
+        }
+        if polygon.len() >= 3 {
+            polygons.push(polygon);
+        }
+    }
+    polygons
+}
+
+fn segments_intersect(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+    ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+}
+
+// Merges holes into the outer polygon with zero-width bridges, `None` if a hole
+// cannot be connected to it without crossing an edge
+fn bridge_holes(mut outer: Vec<Point>, mut holes: Vec<Vec<Point>>) -> Option<Vec<Point>> {
+    let rightmost = |h: &Vec<Point>| (0..h.len()).max_by(|&a, &b| h[a].0.partial_cmp(&h[b].0).unwrap_or(std::cmp::Ordering::Equal)).unwrap();
+    holes.sort_by(|a, b| b[rightmost(b)].0.partial_cmp(&a[rightmost(a)].0).unwrap_or(std::cmp::Ordering::Equal));
+
+    for (hi, hole) in holes.iter().enumerate() {
+        let edges = |poly: &[Point]| (0..poly.len()).map(move |i| (poly[i], poly[(i + 1) % poly.len()])).collect::<Vec<_>>();
+        let obstacles: Vec<(Point, Point)> = edges(&outer).into_iter()
+            .chain(holes[hi..].iter().flat_map(|h| edges(h)))
+            .collect();
+
+        // The rightmost hole vertex sees the outer polygon unless the loops
+        // intersect, in which case any other visible pair is used
+        let mut hole_order: Vec<usize> = (0..hole.len()).collect();
+        hole_order.sort_by(|&a, &b| hole[b].0.partial_cmp(&hole[a].0).unwrap_or(std::cmp::Ordering::Equal));
+        let (mi, vi) = hole_order.into_iter().find_map(|mi| {
+            let m = hole[mi];
+            let dist = |p: Point| (p.0 - m.0).hypot(p.1 - m.1);
+            let mut candidates: Vec<usize> = (0..outer.len()).collect();
+            candidates.sort_by(|&a, &b| dist(outer[a]).partial_cmp(&dist(outer[b])).unwrap_or(std::cmp::Ordering::Equal));
+            candidates.into_iter()
+                .find(|&c| !obstacles.iter().any(|&(a, b)| segments_intersect(m, outer[c], a, b)))
+                .map(|vi| (mi, vi))
+        })?;
+
+        let mut merged = Vec::with_capacity(outer.len() + hole.len() + 2);
+        merged.extend_from_slice(&outer[..=vi]);
+        merged.extend(hole[mi..].iter().chain(&hole[..=mi]).copied());
+        merged.extend_from_slice(&outer[vi..]);
+        outer = merged;
+    }
+    Some(outer)
+}
+
+fn point_in_triangle(p: Point, a: Point, b: Point, c: Point) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+// Ear clipping for a counter-clockwise polygon that may touch itself along bridges
+fn triangulate_polygon(polygon: Vec<Point>, min_area: Real, triangles: &mut Vec<[Point; 3]>) {
+    let mut poly = polygon;
+    while poly.len() > 3 {
+        let n = poly.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (poly[(i + n - 1) % n], poly[i], poly[(i + 1) % n]);
+            if cross(a, b, c) <= min_area { return false }
+            !poly.iter().any(|&p| p != a && p != b && p != c && point_in_triangle(p, a, b, c))
+        });
+        // Degenerate leftovers: clip the most convex vertex to guarantee progress
+        let i = ear.unwrap_or_else(|| (0..n).max_by(|&x, &y| {
+            let cx = cross(poly[(x + n - 1) % n], poly[x], poly[(x + 1) % n]);
+            let cy = cross(poly[(y + n - 1) % n], poly[y], poly[(y + 1) % n]);
+            cx.partial_cmp(&cy).unwrap_or(std::cmp::Ordering::Equal)
+        }).unwrap());
+        let (a, b, c) = (poly[(i + n - 1) % n], poly[i], poly[(i + 1) % n]);
+        if cross(a, b, c) > 0.0 {
+            triangles.push([a, b, c]);
+        }
+        poly.remove(i);
+    }
+    if poly.len() == 3 && cross(poly[0], poly[1], poly[2]) > 0.0 {
+        triangles.push([poly[0], poly[1], poly[2]]);
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+use crate::generated::{Vec3, Scene, Mesh, Node, Face, SkinDeformer, Texture, TextureType, TopoEdge, TopoFlags};
+use crate::generated::{compute_topology, get_weighted_face_normal, matrix_determinant, matrix_to_transform};
+use crate::prelude::Real;
+use crate::math::{vec3_sub, vec3_mul, vec3_add, vec3_dot, vec3_length};
+use crate::resolve::TextureResolver;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FindingKind {
+    DegenerateFace,
+    ZeroAreaFace,
+    NonPlanarFace,
+    NonManifoldEdge,
+    TooManySkinInfluences,
+    UnnormalizedSkinWeights,
+    MissingTextureFile,
+    NonUniformJointScale,
+    NegativeDeterminant,
+    EmptyMaterialSlot,
+}
+
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    pub element_id: u32,
+    pub index: Option<usize>,
+    pub message: String,
+}
+
+/// Configures which checks `validate()` runs, `None` disables a check.
+pub struct Rules {
+    pub degenerate_faces: Option<Severity>,
+    pub zero_area_faces: Option<Severity>,
+    pub non_planar_faces: Option<Severity>,
+    pub non_manifold_edges: Option<Severity>,
+    pub too_many_skin_influences: Option<Severity>,
+    pub unnormalized_skin_weights: Option<Severity>,
+    pub missing_texture_files: Option<Severity>,
+    pub non_uniform_joint_scale: Option<Severity>,
+    pub negative_determinant: Option<Severity>,
+    pub empty_material_slots: Option<Severity>,
+    pub max_skin_influences: usize,
+    pub skin_weight_tolerance: Real,
+    pub min_face_area: Real,
+    pub planarity_tolerance: Real,
+    pub scale_tolerance: Real,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            degenerate_faces: Some(Severity::Error),
+            zero_area_faces: Some(Severity::Error),
+            non_planar_faces: Some(Severity::Warning),
+            non_manifold_edges: Some(Severity::Error),
+            too_many_skin_influences: Some(Severity::Warning),
+            unnormalized_skin_weights: Some(Severity::Warning),
+            missing_texture_files: Some(Severity::Error),
+            non_uniform_joint_scale: Some(Severity::Warning),
+            negative_determinant: Some(Severity::Warning),
+            empty_material_slots: Some(Severity::Warning),
+            max_skin_influences: 4,
+            skin_weight_tolerance: 1e-3,
+            min_face_area: 1e-12,
+            planarity_tolerance: 1e-3,
+            scale_tolerance: 1e-3,
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct ValidationReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.findings.iter().any(|f| f.severity == Severity::Error)
+    }
+
+    pub fn count(&self, kind: FindingKind) -> usize {
+        self.findings.iter().filter(|f| f.kind == kind).count()
+    }
+}
+
+struct Validator<'a> {
+    rules: &'a Rules,
+    // Resolves relative texture paths against the directory of the scene file
+    resolver: TextureResolver,
+    findings: Vec<Finding>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, severity: Option<Severity>, kind: FindingKind, element_id: u32, index: Option<usize>, message: String) {
+        if let Some(severity) = severity {
+            self.findings.push(Finding { kind, severity, element_id, index, message });
+        }
+    }
+
+    fn check_face(&mut self, mesh: &Mesh, face_ix: usize, face: Face) {
+        let id = mesh.element.element_id;
+        if face.num_indices < 3 {
+            let message = format!("mesh '{}' face {} has only {} vertices", mesh.element.name, face_ix, face.num_indices);
+            self.report(self.rules.degenerate_faces, FindingKind::DegenerateFace, id, Some(face_ix), message);
+            return;
+        }
+        if !mesh.vertex_position.exists { return }
+
+        let normal = get_weighted_face_normal(&mesh.vertex_position, face);
+        let area = vec3_length(normal) * 0.5;
+        if area <= self.rules.min_face_area {
+            let message = format!("mesh '{}' face {} has zero area", mesh.element.name, face_ix);
+            self.report(self.rules.zero_area_faces, FindingKind::ZeroAreaFace, id, Some(face_ix), message);
+            return;
+        }
+
+        if face.num_indices > 3 && self.rules.non_planar_faces.is_some() {
+            let begin = face.index_begin as usize;
+            let end = begin + face.num_indices as usize;
+            let unit_normal = vec3_mul(normal, 1.0 / vec3_length(normal));
+            let mut center = Vec3::default();
+            for ix in begin..end {
+                center = vec3_add(center, mesh.vertex_position[ix]);
+            }
+            center = vec3_mul(center, 1.0 / face.num_indices as Real);
+
+            let size = area.sqrt();
+            let max_deviation = (begin..end)
+                .map(|ix| vec3_dot(vec3_sub(mesh.vertex_position[ix], center), unit_normal).abs())
+                .fold(0.0, Real::max);
+            if max_deviation > self.rules.planarity_tolerance * size {
+                let message = format!("mesh '{}' face {} is non-planar (deviation {})", mesh.element.name, face_ix, max_deviation);
+                self.report(self.rules.non_planar_faces, FindingKind::NonPlanarFace, id, Some(face_ix), message);
+            }
+        }
+    }
+
+    fn check_mesh(&mut self, mesh: &Mesh) {
+        let id = mesh.element.element_id;
+        for (face_ix, &face) in mesh.faces.iter().enumerate() {
+            self.check_face(mesh, face_ix, face);
+        }
+
+        if self.rules.non_manifold_edges.is_some() && mesh.num_indices > 0 {
+            let mut topo = vec![TopoEdge::default(); mesh.num_indices];
+            compute_topology(mesh, &mut topo);
+            let edges: BTreeSet<u32> = topo.iter()
+                .filter(|t| t.flags.has_any(TopoFlags::NON_MANIFOLD))
+                .map(|t| t.edge)
+                .collect();
+            for edge in edges {
+                let message = format!("mesh '{}' edge {} is non-manifold", mesh.element.name, edge);
+                self.report(self.rules.non_manifold_edges, FindingKind::NonManifoldEdge, id, Some(edge as usize), message);
+            }
+        }
+
+        if mesh.num_faces > 0 && mesh.materials.is_empty() {
+            let message = format!("mesh '{}' has no materials", mesh.element.name);
+            self.report(self.rules.empty_material_slots, FindingKind::EmptyMaterialSlot, id, None, message);
+        }
+        for part in &mesh.material_parts {
+            if part.num_faces == 0 && (part.index as usize) < mesh.materials.len() {
+                let message = format!("mesh '{}' material slot {} has no faces", mesh.element.name, part.index);
+                self.report(self.rules.empty_material_slots, FindingKind::EmptyMaterialSlot, id, Some(part.index as usize), message);
+            }
+        }
+    }
+
+    fn check_skin(&mut self, skin: &SkinDeformer) {
+        let id = skin.element.element_id;
+        for (vertex_ix, vertex) in skin.vertices.iter().enumerate() {
+            if vertex.num_weights as usize > self.rules.max_skin_influences {
+                let message = format!("skin '{}' vertex {} has {} influences (max {})",
+                    skin.element.name, vertex_ix, vertex.num_weights, self.rules.max_skin_influences);
+                self.report(self.rules.too_many_skin_influences, FindingKind::TooManySkinInfluences, id, Some(vertex_ix), message);
+            }
+
+            if vertex.num_weights == 0 { continue }
+            let begin = vertex.weight_begin as usize;
+            let end = begin + vertex.num_weights as usize;
+            let total: Real = skin.weights.as_ref()[begin..end].iter().map(|w| w.weight).sum();
+            if (total - 1.0).abs() > self.rules.skin_weight_tolerance {
+                let message = format!("skin '{}' vertex {} weights sum to {}", skin.element.name, vertex_ix, total);
+                self.report(self.rules.unnormalized_skin_weights, FindingKind::UnnormalizedSkinWeights, id, Some(vertex_ix), message);
+            }
+        }
+    }
+
+    fn check_texture(&mut self, texture: &Texture) {
+        if texture.type_ != TextureType::File || !texture.content.is_empty() { return }
+        let id = texture.element.element_id;
+        if !texture.has_file {
+            let message = format!("texture '{}' does not refer to a file", texture.element.name);
+            self.report(self.rules.missing_texture_files, FindingKind::MissingTextureFile, id, None, message);
+            return;
+        }
+
+        if !self.resolver.resolve_texture(texture).is_resolved() {
+            let message = format!("texture '{}' file '{}' not found", texture.element.name, texture.filename);
+            self.report(self.rules.missing_texture_files, FindingKind::MissingTextureFile, id, None, message);
+        }
+    }
+
+    fn check_joint(&mut self, node: &Node) {
+        let scale = matrix_to_transform(&node.node_to_world).scale;
+        let min = scale.x.abs().min(scale.y.abs()).min(scale.z.abs());
+        let max = scale.x.abs().max(scale.y.abs()).max(scale.z.abs());
+        if max - min > self.rules.scale_tolerance * max {
+            let message = format!("skinned joint '{}' has non-uniform scale {}", node.element.name, scale);
+            self.report(self.rules.non_uniform_joint_scale, FindingKind::NonUniformJointScale, node.element.element_id, None, message);
+        }
+    }
+
+    fn check_node(&mut self, node: &Node) {
+        if matrix_determinant(&node.geometry_to_world) < 0.0 {
+            let message = format!("node '{}' has a negative determinant transform", node.element.name);
+            self.report(self.rules.negative_determinant, FindingKind::NegativeDeterminant, node.element.element_id, None, message);
+        }
+    }
+}
+
+/// Runs the checks enabled in `rules` over `scene`.
+pub fn validate(scene: &Scene, rules: &Rules) -> ValidationReport {
+    let mut validator = Validator { rules, resolver: TextureResolver::for_scene(scene), findings: Vec::new() };
+
+    for mesh in &scene.meshes {
+        validator.check_mesh(mesh);
+    }
+    for skin in &scene.skin_deformers {
+        validator.check_skin(skin);
+    }
+    if rules.missing_texture_files.is_some() {
+        for texture in &scene.textures {
+            validator.check_texture(texture);
+        }
+    }
+    if rules.non_uniform_joint_scale.is_some() {
+        let mut joint_ids = BTreeSet::new();
+        for cluster in &scene.skin_clusters {
+            if let Some(bone) = cluster.bone_node.as_ref() {
+                if joint_ids.insert(bone.element.element_id) {
+                    validator.check_joint(bone);
+                }
+            }
+        }
+    }
+    if rules.negative_determinant.is_some() {
+        for node in &scene.nodes {
+            validator.check_node(node);
+        }
+    }
+
+    ValidationReport { findings: validator.findings }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}: {} (element {})", finding.severity, finding.message, finding.element_id)?;
+        }
+        Ok(())
+    }
+}
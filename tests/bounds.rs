@@ -0,0 +1,70 @@
+use ufbx::{self, Aabb, SceneBvh};
+
+fn assert_close(a: &Aabb, b: &Aabb) {
+    let pairs = [(a.min.x, b.min.x), (a.min.y, b.min.y), (a.min.z, b.min.z), (a.max.x, b.max.x), (a.max.y, b.max.y), (a.max.z, b.max.z)];
+    for &(x, y) in &pairs {
+        assert!((x - y).abs() < 1e-6, "{:?} != {:?}", a, b);
+    }
+}
+
+fn contains(outer: &Aabb, inner: &Aabb) -> bool {
+    let eps = 1e-9;
+    outer.min.x <= inner.min.x + eps && outer.min.y <= inner.min.y + eps && outer.min.z <= inner.min.z + eps
+        && outer.max.x >= inner.max.x - eps && outer.max.y >= inner.max.y - eps && outer.max.z >= inner.max.z - eps
+}
+
+#[test]
+fn mesh_and_node_bounds() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let node = scene.find_node("Cube").expect("expected to find 'Cube'");
+    let mesh = node.mesh.as_ref().expect("expected 'Cube' to have a mesh");
+
+    let local = mesh.local_bounds();
+    let unit = Aabb {
+        min: ufbx::Vec3 { x: -1.0, y: -1.0, z: -1.0 },
+        max: ufbx::Vec3 { x: 1.0, y: 1.0, z: 1.0 },
+    };
+    assert_close(&local, &unit);
+
+    let world = node.world_bounds();
+    assert!((world.size().x - 200.0).abs() < 1e-3);
+    assert!((world.size().y - 200.0).abs() < 1e-3);
+    assert_close(&scene.bounds(), &world);
+
+    assert!(scene.root_node.world_bounds().is_empty());
+}
+
+#[test]
+fn instanced_scene_bounds() {
+    let scene = ufbx::load_file("tests/data/instanced_materials.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let top = scene.find_node("ConeTop").expect("expected to find 'ConeTop'").world_bounds();
+    let bottom = scene.find_node("ConeBottom").expect("expected to find 'ConeBottom'").world_bounds();
+    assert!(top.min.y > bottom.min.y);
+    assert_close(&scene.bounds(), &top.union(&bottom));
+    assert_close(&scene.bounds(), &SceneBvh::new(&scene).bounds());
+}
+
+#[test]
+fn animated_scene_bounds() {
+    let scene = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let anim = &scene.anim;
+    let range = anim.time_begin..=anim.time_end;
+    let animated = ufbx::animated_bounds(&scene, anim, range, 11).expect("expected to evaluate bounds");
+
+    let node_index = scene.find_node("pCube1").expect("expected to find 'pCube1'").element.typed_id as usize;
+    assert_close(&animated.node_bounds[node_index], &animated.bounds);
+
+    for i in 0..11 {
+        let time = anim.time_begin + (anim.time_end - anim.time_begin) * i as f64 / 10.0;
+        let state = ufbx::evaluate_scene(&scene, anim, time, ufbx::EvaluateOpts::default())
+            .expect("expected to evaluate scene");
+        assert!(contains(&animated.bounds, &state.bounds()));
+    }
+
+    let first = ufbx::evaluate_scene(&scene, anim, anim.time_begin, ufbx::EvaluateOpts::default())
+        .expect("expected to evaluate scene").bounds();
+    assert!(animated.bounds.size().y > first.size().y + 1e-3 || animated.bounds.size().x > first.size().x + 1e-3);
+}
@@ -0,0 +1,80 @@
+use ufbx::{self, SceneBvh, Vec3};
+
+fn vec3(x: f64, y: f64, z: f64) -> Vec3 {
+    Vec3 { x, y, z }
+}
+
+#[test]
+fn raycast_cube() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let bvh = SceneBvh::new(&scene);
+    assert_eq!(bvh.num_instances(), 1);
+
+    let hit = bvh.raycast(vec3(10.0, 500.0, 20.0), vec3(0.0, -1.0, 0.0), f64::INFINITY)
+        .expect("expected to hit the cube");
+    assert!((hit.t - 400.0).abs() < 1e-3);
+    assert!((hit.position.y - 100.0).abs() < 1e-3);
+    assert!((hit.position.x - 10.0).abs() < 1e-6);
+    assert!((hit.position.z - 20.0).abs() < 1e-6);
+    assert!(hit.normal.y > 0.999);
+    assert!(hit.geometric_normal.y.abs() > 0.999);
+    assert!((hit.barycentrics.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    assert!(hit.uv.x >= 0.0 && hit.uv.x <= 1.0 && hit.uv.y >= 0.0 && hit.uv.y <= 1.0);
+    assert_eq!(hit.node(&scene).element.name, "Cube");
+
+    let mesh = hit.mesh(&scene);
+    let face = mesh.faces[hit.face_index as usize];
+    for &ix in &hit.indices {
+        assert!(ix >= face.index_begin && ix < face.index_begin + face.num_indices);
+    }
+
+    assert!(bvh.raycast(vec3(10.0, 500.0, 20.0), vec3(0.0, -1.0, 0.0), 300.0).is_none());
+    assert!(bvh.raycast(vec3(500.0, 500.0, 0.0), vec3(0.0, -1.0, 0.0), f64::INFINITY).is_none());
+    assert!(bvh.occluded(vec3(0.0, 0.0, 500.0), vec3(0.0, 0.0, -1.0), 1000.0));
+    assert!(!bvh.occluded(vec3(0.0, 0.0, 500.0), vec3(0.0, 0.0, 1.0), 1000.0));
+
+    // Rays starting inside hit the far side
+    let inside = bvh.raycast(vec3(0.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0), f64::INFINITY)
+        .expect("expected to hit from inside");
+    assert!((inside.t - 100.0).abs() < 1e-3);
+}
+
+#[test]
+fn raycast_instances() {
+    let scene = ufbx::load_file("tests/data/instanced_materials.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let bvh = SceneBvh::new(&scene);
+    assert_eq!(bvh.num_instances(), 2);
+
+    let from_above = bvh.raycast(vec3(0.0, 10.0, 0.0), vec3(0.0, -1.0, 0.0), f64::INFINITY)
+        .expect("expected to hit from above");
+    let from_below = bvh.raycast(vec3(0.0, -10.0, 0.0), vec3(0.0, 1.0, 0.0), f64::INFINITY)
+        .expect("expected to hit from below");
+    assert_eq!(from_above.node(&scene).element.name, "ConeTop");
+    assert_eq!(from_below.node(&scene).element.name, "ConeBottom");
+    assert_eq!(from_above.mesh_index, from_below.mesh_index);
+    assert!(from_above.position.y > from_below.position.y);
+}
+
+#[test]
+fn raycast_evaluated() {
+    let scene = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let mut hits = Vec::new();
+    for &time in &[0.0, 0.5] {
+        let state = ufbx::evaluate_scene(&scene, &scene.anim, time, ufbx::EvaluateOpts::default())
+            .expect("expected to evaluate scene");
+        let bvh = SceneBvh::new(&state);
+        let bounds = bvh.bounds();
+        let center = bounds.center();
+
+        let hit = bvh.raycast(vec3(center.x, bounds.max.y + 10.0, center.z), vec3(0.0, -1.0, 0.0), f64::INFINITY)
+            .expect("expected to hit the animated cube");
+        assert!(hit.position.y <= bounds.max.y + 1e-9 && hit.position.y >= bounds.min.y - 1e-9);
+        assert_eq!(hit.node(&state).element.name, "pCube1");
+        hits.push(hit.position.y);
+    }
+    assert!((hits[0] - hits[1]).abs() > 1e-3);
+}
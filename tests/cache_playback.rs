@@ -0,0 +1,151 @@
+use std::path::Path;
+use ufbx::{self, CachePlayback, CachePlaybackError, CachePlaybackOpts, McChannel, McOpts, Vec3};
+
+mod common;
+use common::{assert_points_eq, load_cube_anim_with, temp_dir};
+
+fn rest_positions() -> Vec<Vec3> {
+    let scene = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    scene.meshes[0].vertices.to_vec()
+}
+
+fn animated(rest: &[Vec3], num_frames: usize) -> Vec<Vec<Vec3>> {
+    (0..num_frames).map(|f| rest.iter().enumerate().map(|(i, p)| Vec3 {
+        x: p.x + f as f64,
+        y: p.y * (1.0 + f as f64 * 0.5),
+        z: p.z - i as f64 * 0.25,
+    }).collect()).collect()
+}
+
+// Writes `frames` as a Maya cache and loads `cube_anim.fbx` with a cache deformer referencing it
+fn load_cached_cube(dir: &Path, frames: &[Vec<Vec3>]) -> ufbx::SceneRoot {
+    let xml_path = dir.join("cache.xml");
+    let channels = [McChannel { name: "pCubeShape1", interpretation: "positions", frames }];
+    let opts = McOpts { double_precision: true, frames_per_second: 24.0, start_frame: 1, ..Default::default() };
+    ufbx::write_maya_cache(&xml_path, &channels, &opts).unwrap();
+
+    let objects = format!(concat!(
+        "\tDeformer: 900, \"Deformer::\", \"VertexCacheDeformer\" {{\n\t\tVersion: 100\n",
+        "\t\tProperties70:  {{\n\t\t\tP: \"ChannelName\", \"KString\", \"\", \"\", \"pCubeShape1\"\n\t\t}}\n\t}}\n",
+        "\tCache: 901, \"Cache::\", \"\" {{\n\t\tType: \"Cache\"\n\t\tProperties70:  {{\n",
+        "\t\t\tP: \"CacheFileName\", \"KString\", \"XRefUrl\", \"\", \"cache.xml\"\n",
+        "\t\t\tP: \"CacheAbsoluteFileName\", \"KString\", \"XRefUrl\", \"\", \"{}\"\n",
+        "\t\t\tP: \"CacheFileType\", \"enum\", \"\", \"\",2\n\t\t}}\n\t}}\n"),
+        xml_path.to_str().unwrap());
+    let connections = "\tC: \"OO\",900,2245309148656\n\tC: \"OO\",901,900\n";
+    let opts = ufbx::LoadOpts { load_external_files: true, ..Default::default() };
+    load_cube_anim_with(&objects, connections, opts)
+}
+
+#[test]
+fn playback_frames() {
+    let dir = temp_dir("playback-frames");
+    let rest = rest_positions();
+    let frames = animated(&rest, 10);
+    let scene = load_cached_cube(&dir, &frames);
+    let mesh = &scene.meshes[0];
+    assert_eq!(mesh.cache_deformers.len(), 1);
+
+    for &background in &[true, false] {
+        let opts = CachePlaybackOpts { background, prefetch_frames: 3, ..Default::default() };
+        let mut playback = CachePlayback::new(mesh, opts).expect("expected playback");
+        assert_eq!(playback.channel_name(), "pCubeShape1");
+        assert_eq!(playback.num_frames(), 10);
+
+        let mut count = 0;
+        for (ix, result) in playback.iter().enumerate() {
+            let (time, positions) = result.expect("expected frame");
+            assert!((time - (ix + 1) as f64 / 24.0).abs() < 1e-9);
+            assert_points_eq(&positions, &frames[ix]);
+            count += 1;
+        }
+        assert_eq!(count, 10);
+
+        // Random access backwards and interpolation between frames
+        assert_points_eq(&playback.frame(2).unwrap(), &frames[2]);
+        let mid = playback.sample(3.25 / 24.0).unwrap();
+        let expected: Vec<Vec3> = frames[2].iter().zip(&frames[3]).map(|(a, b)| Vec3 {
+            x: a.x * 0.75 + b.x * 0.25, y: a.y * 0.75 + b.y * 0.25, z: a.z * 0.75 + b.z * 0.25,
+        }).collect();
+        assert_points_eq(&mid, &expected);
+        assert_points_eq(&playback.sample(-1.0).unwrap(), &frames[0]);
+        assert_points_eq(&playback.sample(100.0).unwrap(), &frames[9]);
+
+        // Matches the positions ufbx evaluates from the same cache
+        let eval_opts = ufbx::EvaluateOpts { evaluate_caches: true, evaluate_skinning: true, load_external_files: true, ..Default::default() };
+        let state = ufbx::evaluate_scene(&scene, &scene.anim, 5.0 / 24.0, eval_opts).expect("expected to evaluate");
+        assert_points_eq(&state.meshes[0].skinned_position.values, &playback.sample(5.0 / 24.0).unwrap());
+
+        assert!(matches!(playback.frame(10), Err(CachePlaybackError::FrameOutOfRange { frame: 10, num_frames: 10 })));
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn playback_vertex_remap() {
+    let dir = temp_dir("playback-remap");
+    let rest = rest_positions();
+    let frames = animated(&rest, 3);
+
+    // Cache stored in reverse vertex order without the first vertex
+    let reversed: Vec<Vec<Vec3>> = frames.iter().map(|f| f[1..].iter().rev().copied().collect()).collect();
+    let scene = load_cached_cube(&dir, &reversed);
+    let mesh = &scene.meshes[0];
+
+    match CachePlayback::new(mesh, CachePlaybackOpts::default()) {
+        Err(CachePlaybackError::PointCountMismatch { channel, frame, expected, found }) => {
+            assert_eq!((channel.as_str(), frame, expected, found), ("pCubeShape1", 0, rest.len(), rest.len() - 1));
+        },
+        _ => panic!("expected a point count mismatch"),
+    }
+
+    let bad = CachePlaybackOpts { vertex_remap: vec![100; rest.len() - 1], ..Default::default() };
+    assert!(matches!(CachePlayback::new(mesh, bad), Err(CachePlaybackError::InvalidRemap { index: 0, vertex: 100, .. })));
+
+    let remap: Vec<u32> = (1..rest.len() as u32).rev().collect();
+    let mut playback = CachePlayback::new(mesh, CachePlaybackOpts { vertex_remap: remap, ..Default::default() }).unwrap();
+    for (ix, expected) in frames.iter().enumerate() {
+        let positions = playback.frame(ix).unwrap();
+        assert_points_eq(&positions[1..], &expected[1..]);
+        assert_points_eq(&positions[..1], &rest[..1]);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn playback_seek() {
+    let dir = temp_dir("playback-seek");
+    let rest = rest_positions();
+    let frames = animated(&rest, 40);
+    let scene = load_cached_cube(&dir, &frames);
+
+    // Seeking past the prefetch window cancels the queued reads, later results must not be mixed up
+    let opts = CachePlaybackOpts { prefetch_frames: 30, ..Default::default() };
+    let mut playback = CachePlayback::new(&scene.meshes[0], opts).unwrap();
+    for &ix in &[0, 35, 5, 6, 39, 0, 38] {
+        assert_points_eq(&playback.frame(ix).unwrap(), &frames[ix]);
+    }
+
+    // Dropping with reads still queued
+    playback.frame(0).unwrap();
+    drop(playback);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn playback_errors() {
+    let scene = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    assert!(matches!(CachePlayback::new(&scene.meshes[0], CachePlaybackOpts::default()), Err(CachePlaybackError::NoPositionChannel)));
+
+    // Missing data files are reported when reading the frame
+    let dir = temp_dir("playback-errors");
+    let rest = rest_positions();
+    let scene = load_cached_cube(&dir, &animated(&rest, 2));
+    let mut playback = CachePlayback::new(&scene.meshes[0], CachePlaybackOpts { prefetch_frames: 0, ..Default::default() }).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let error = playback.frame(1).expect_err("expected an error");
+    assert!(matches!(error, CachePlaybackError::Io { .. }));
+    assert!(error.to_string().contains("cache.mc"));
+}
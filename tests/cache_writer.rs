@@ -0,0 +1,245 @@
+use std::convert::TryInto;
+use std::path::PathBuf;
+use ufbx::{self, McChannel, McFormat, McLayout, McOpts, Pc2Opts, Vec3};
+
+mod common;
+use common::{assert_points_eq, temp_dir};
+
+fn frames(num_frames: usize, num_points: usize, offset: f64) -> Vec<Vec<Vec3>> {
+    (0..num_frames).map(|f| (0..num_points).map(|p| Vec3 {
+        x: p as f64 + offset,
+        y: f as f64 * 0.5,
+        z: (p * f) as f64 * 0.125 - 1.0,
+    }).collect()).collect()
+}
+
+fn read_frame(frame: &ufbx::CacheFrame) -> Vec<Vec3> {
+    let mut data = vec![Vec3::default(); frame.data_count as usize];
+    let num = ufbx::read_geometry_cache_vec3(frame, &mut data, ufbx::GeometryCacheDataOpts::default());
+    assert_eq!(num, data.len());
+    data
+}
+
+#[test]
+fn cache_write_pc2() {
+    let dir = temp_dir("cache-pc2");
+    let path = dir.join("points.pc2");
+    let data = frames(5, 4, 0.0);
+    ufbx::write_pc2_file(&path, &data, &Pc2Opts { start_frame: 10.0, sample_rate: 2.0 }).unwrap();
+
+    let opts = ufbx::GeometryCacheOpts { frames_per_second: 30.0, ..Default::default() };
+    let cache = ufbx::load_geometry_cache(path.to_str().unwrap(), opts).expect("expected to load cache");
+    assert_eq!(cache.channels.len(), 1);
+    let channel = &cache.channels[0];
+    assert_eq!(channel.interpretation, ufbx::CacheInterpretation::VertexPosition);
+    assert_eq!(channel.frames.len(), 5);
+    for (ix, frame) in channel.frames.iter().enumerate() {
+        assert!((frame.time - (10.0 + ix as f64 * 2.0) / 30.0).abs() < 1e-9);
+        assert_points_eq(&read_frame(frame), &data[ix]);
+    }
+
+    let uneven = vec![vec![Vec3::default(); 2], vec![Vec3::default(); 3]];
+    assert!(ufbx::write_pc2(&mut Vec::new(), &uneven, &Pc2Opts::default()).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Leaf chunks of an MC file in order up to the terminating zero tag, the FOR4/FOR8 group headers are skipped
+fn read_iff_chunks(data: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+    let mut chunks = Vec::new();
+    let (mut pos, mut wide) = (0, false);
+    loop {
+        let tag: [u8; 4] = data[pos..pos + 4].try_into().unwrap();
+        if tag == [0; 4] {
+            assert_eq!(pos + if wide { 8 } else { 4 }, data.len());
+            break;
+        }
+        if &tag == b"FOR4" || &tag == b"FOR8" {
+            wide = &tag == b"FOR8";
+            pos += if wide { 20 } else { 12 };
+            continue;
+        }
+        let (size, header) = if wide {
+            (u64::from_be_bytes(data[pos + 8..pos + 16].try_into().unwrap()) as usize, 16)
+        } else {
+            (u32::from_be_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize, 8)
+        };
+        let alignment = if wide { 8 } else { 4 };
+        chunks.push((tag, data[pos + header..pos + header + size].to_vec()));
+        pos += header + (size + alignment - 1) / alignment * alignment;
+    }
+    chunks
+}
+
+fn read_be_u32(data: &[u8]) -> u32 {
+    u32::from_be_bytes(data.try_into().unwrap())
+}
+
+fn read_be_points(data: &[u8], double_precision: bool) -> Vec<Vec3> {
+    let values: Vec<f64> = if double_precision {
+        data.chunks_exact(8).map(|c| f64::from_be_bytes(c.try_into().unwrap())).collect()
+    } else {
+        data.chunks_exact(4).map(|c| f32::from_be_bytes(c.try_into().unwrap()) as f64).collect()
+    };
+    values.chunks_exact(3).map(|c| Vec3 { x: c[0], y: c[1], z: c[2] }).collect()
+}
+
+// Every combination of format, layout and precision for 6 frames starting at frame 3
+fn maya_cache_opts() -> Vec<McOpts> {
+    let mut opts = Vec::new();
+    for &format in &[McFormat::Mcc, McFormat::Mcx] {
+        for &layout in &[McLayout::OneFile, McLayout::OneFilePerFrame] {
+            for &double_precision in &[false, true] {
+                opts.push(McOpts {
+                    format,
+                    layout,
+                    double_precision,
+                    frames_per_second: 24.0,
+                    start_frame: 3,
+                    extra_info: vec!["baked <test>".into()],
+                });
+            }
+        }
+    }
+    opts
+}
+
+// Writes `channels` to a new temporary directory, returns `(dir, xml_path, paths)`
+fn write_maya_test_cache(name: &str, channels: &[McChannel], opts: &McOpts) -> (PathBuf, PathBuf, Vec<PathBuf>) {
+    let dir = temp_dir(&format!("{}-{:?}-{:?}-{}", name, opts.format, opts.layout, opts.double_precision));
+    let xml_path = dir.join("cache.xml");
+    let paths = ufbx::write_maya_cache(&xml_path, channels, opts).unwrap();
+    assert_eq!(paths.len(), if opts.layout == McLayout::OneFile { 2 } else { 7 });
+    (dir, xml_path, paths)
+}
+
+#[test]
+fn cache_write_maya() {
+    let positions = frames(6, 5, 0.0);
+    let normals = frames(6, 3, 100.0);
+    let channels = [
+        McChannel { name: "pCubeShape1", interpretation: "positions", frames: &positions },
+        McChannel { name: "pSphereShape1", interpretation: "normals", frames: &normals },
+    ];
+
+    for opts in &maya_cache_opts() {
+        let (dir, xml_path, paths) = write_maya_test_cache("cache-maya", &channels, opts);
+        let (layout, double_precision) = (opts.layout, opts.double_precision);
+
+        let xml = std::fs::read_to_string(&xml_path).unwrap();
+        assert!(xml.contains("<extra>baked &lt;test&gt;</extra>"));
+        assert!(xml.contains("<time Range=\"750-2000\"/>"));
+        assert!(xml.contains("ChannelName=\"pSphereShape1\""));
+        assert!(xml.contains("ChannelInterpretation=\"normals\""));
+
+        // Expected chunks of each data file, times are in ticks of 1/6000 seconds
+        let data_tag = if double_precision { b"DVCA" } else { b"FVCA" };
+        let mut expected = Vec::new();
+        for frame in 0..6 {
+            let time = (3 + frame as u32) * 250;
+            if layout == McLayout::OneFilePerFrame || frame == 0 {
+                let (start, end) = if layout == McLayout::OneFile { (750, 2000) } else { (time, time) };
+                expected.push(vec![(*b"VRSN", None), (*b"STIM", Some(start)), (*b"ETIM", Some(end))]);
+            }
+            let file = expected.last_mut().unwrap();
+            if layout == McLayout::OneFile {
+                file.push((*b"TIME", Some(time)));
+            }
+            for channel in &channels {
+                file.push((*b"CHNM", None));
+                file.push((*b"SIZE", Some(channel.frames[frame].len() as u32)));
+                file.push((*data_tag, None));
+            }
+        }
+
+        let (mut frame, mut channel) = (0, 0);
+        for (path, expected) in paths[1..].iter().zip(&expected) {
+            let chunks = read_iff_chunks(&std::fs::read(path).unwrap());
+            assert_eq!(chunks.len(), expected.len());
+            for ((tag, payload), (expected_tag, value)) in chunks.iter().zip(expected) {
+                assert_eq!(tag, expected_tag);
+                if let Some(value) = value {
+                    assert_eq!(read_be_u32(payload), *value);
+                }
+                if tag == b"CHNM" {
+                    assert_eq!(payload.as_slice(), format!("{}\0", channels[channel].name).as_bytes());
+                }
+                if tag == data_tag {
+                    assert_points_eq(&read_be_points(payload, double_precision), &channels[channel].frames[frame]);
+                    channel = (channel + 1) % channels.len();
+                    if channel == 0 {
+                        frame += 1;
+                    }
+                }
+            }
+        }
+        assert_eq!(frame, 6);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn cache_write_maya_load() {
+    let positions = frames(6, 5, 0.0);
+    let normals = frames(6, 3, 100.0);
+    let channels = [
+        McChannel { name: "pCubeShape1", interpretation: "positions", frames: &positions },
+        McChannel { name: "pSphereShape1", interpretation: "normals", frames: &normals },
+    ];
+
+    for opts in &maya_cache_opts() {
+        let (dir, xml_path, _) = write_maya_test_cache("cache-maya-load", &channels, opts);
+
+        let cache = ufbx::load_geometry_cache(xml_path.to_str().unwrap(), ufbx::GeometryCacheOpts::default())
+            .expect("expected to load cache");
+        assert_eq!(cache.extra_info.len(), 1);
+        assert_eq!(cache.extra_info[0], "baked <test>");
+        assert_eq!(cache.channels.len(), 2);
+
+        let expected = [
+            ("pCubeShape1", ufbx::CacheInterpretation::VertexPosition, &positions),
+            ("pSphereShape1", ufbx::CacheInterpretation::VertexNormal, &normals),
+        ];
+        for (channel, &(name, interpretation, data)) in cache.channels.iter().zip(&expected) {
+            assert_eq!(channel.name, name);
+            assert_eq!(channel.interpretation, interpretation);
+            assert_eq!(channel.frames.len(), 6);
+            for (ix, frame) in channel.frames.iter().enumerate() {
+                assert!((frame.time - (3.0 + ix as f64) / 24.0).abs() < 1e-9);
+                assert_eq!(frame.data_element_bytes, if opts.double_precision { 24 } else { 12 });
+                assert_points_eq(&read_frame(frame), &data[ix]);
+            }
+        }
+
+        // Sampling between frames interpolates linearly
+        let mut sampled = vec![Vec3::default(); 5];
+        ufbx::sample_geometry_cache_vec3(&cache.channels[0], 3.5 / 24.0, &mut sampled, ufbx::GeometryCacheDataOpts::default());
+        assert!((sampled[1].y - 0.25).abs() < 1e-6);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn cache_bake_roundtrip() {
+    let scene = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let anim = &scene.anim;
+    let mesh = &scene.meshes[0];
+    let times: Vec<f64> = (0..4).map(|i| anim.time_begin + (anim.time_end - anim.time_begin) * i as f64 / 3.0).collect();
+    let baked = ufbx::bake_vertex_positions(&scene, anim, mesh, &times).expect("expected to bake");
+    assert_eq!(baked.len(), 4);
+    assert!(baked.iter().all(|f| f.len() == mesh.num_vertices));
+
+    let dir = temp_dir("cache-bake");
+    let path = dir.join("baked.pc2");
+    ufbx::write_pc2_file(&path, &baked, &Pc2Opts::default()).unwrap();
+    let cache = ufbx::load_geometry_cache(path.to_str().unwrap(), ufbx::GeometryCacheOpts::default())
+        .expect("expected to load cache");
+    for (frame, expected) in cache.channels[0].frames.iter().zip(&baked) {
+        for (a, b) in read_frame(frame).iter().zip(expected) {
+            assert!((a.x - b.x).abs() < 1e-5 && (a.y - b.y).abs() < 1e-5 && (a.z - b.z).abs() < 1e-5);
+        }
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,168 @@
+use ufbx::{self, ClipConvention, Matrix4, Vec3};
+
+mod common;
+use common::{assert_near_vec3, load_cube_anim_with, vec3};
+
+// Adds a camera at (1, 2, 3) looking towards -Z to `cube_anim.fbx`
+fn load_camera(props: &str) -> ufbx::SceneRoot {
+    load_camera_with(props, ufbx::LoadOpts::default())
+}
+
+fn load_camera_with(props: &str, opts: ufbx::LoadOpts) -> ufbx::SceneRoot {
+    let objects = format!(concat!(
+        "\tNodeAttribute: 800, \"NodeAttribute::camera1\", \"Camera\" {{\n",
+        "\t\tProperties70:  {{\n{}\t\t}}\n\t\tTypeFlags: \"Camera\"\n\t}}\n",
+        "\tModel: 801, \"Model::camera1\", \"Camera\" {{\n\t\tVersion: 232\n\t\tProperties70:  {{\n",
+        "\t\t\tP: \"Lcl Translation\", \"Lcl Translation\", \"\", \"A\",1,2,3\n",
+        "\t\t\tP: \"Lcl Rotation\", \"Lcl Rotation\", \"\", \"A\",0,90,0\n\t\t}}\n\t}}\n"),
+        props);
+    load_cube_anim_with(&objects, "\tC: \"OO\",801,0\n\tC: \"OO\",800,801\n", opts)
+}
+
+fn prop(name: &str, value: f64) -> String {
+    format!("\t\t\tP: \"{}\", \"double\", \"Number\", \"\",{}\n", name, value)
+}
+
+fn enum_prop(name: &str, value: i32) -> String {
+    format!("\t\t\tP: \"{}\", \"enum\", \"\", \"\",{}\n", name, value)
+}
+
+fn ndc(m: &Matrix4, p: Vec3) -> Vec3 {
+    let c = m.transform_point(p);
+    Vec3 { x: c.x / c.w, y: c.y / c.w, z: c.z / c.w }
+}
+
+fn perspective_props(gate_fit: i32) -> String {
+    [
+        enum_prop("ApertureMode", 2), prop("FieldOfView", 90.0), prop("AspectWidth", 2.0), prop("AspectHeight", 1.0),
+        prop("NearPlane", 1.0), prop("FarPlane", 100.0), enum_prop("GateFit", gate_fit),
+    ].concat()
+}
+
+#[test]
+fn camera_view_matrix() {
+    let scene = load_camera(&perspective_props(0));
+    let node = scene.nodes.iter().find(|n| n.element.name == "camera1").unwrap();
+    let camera = node.camera.as_ref().unwrap();
+    let view = camera.view_matrix(node);
+
+    let to_view = |p: Vec3| {
+        let v = view.transform_point(p);
+        assert_eq!(v.w, 1.0);
+        vec3(v.x, v.y, v.z)
+    };
+    assert_near_vec3(to_view(vec3(1.0, 2.0, 3.0)), vec3(0.0, 0.0, 0.0));
+    assert_near_vec3(to_view(vec3(1.0, 2.0, -2.0)), vec3(0.0, 0.0, -5.0));
+    assert_near_vec3(to_view(vec3(2.0, 3.0, 3.0)), vec3(1.0, 1.0, 0.0));
+
+    // Projecting the world space point in front of the camera
+    let view_proj = camera.projection_matrix(2.0, ClipConvention::OpenGl).mul(&view);
+    let p = ndc(&view_proj, vec3(11.0, 7.0, -2.0));
+    assert!((p.x - 1.0).abs() < 1e-6 && (p.y - 1.0).abs() < 1e-6);
+
+    // Converting the camera axes on load results in the same view
+    let opts = ufbx::LoadOpts {
+        target_camera_axes: ufbx::CoordinateAxes {
+            right: ufbx::CoordinateAxis::PositiveX,
+            up: ufbx::CoordinateAxis::PositiveY,
+            front: ufbx::CoordinateAxis::PositiveZ,
+        },
+        ..Default::default()
+    };
+    let scene = load_camera_with(&perspective_props(0), opts);
+    let node = scene.nodes.iter().find(|n| n.element.name == "camera1").unwrap();
+    let converted = node.camera.as_ref().unwrap().view_matrix(node);
+    for (a, b) in converted.cols.iter().flatten().zip(view.cols.iter().flatten()) {
+        assert!((a - b).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn camera_perspective_conventions() {
+    let scene = load_camera(&perspective_props(0));
+    let camera = &scene.cameras[0];
+    assert!((camera.projection_plane.x - 2.0).abs() < 1e-6 && (camera.projection_plane.y - 1.0).abs() < 1e-6);
+
+    let expected = [
+        (ClipConvention::OpenGl, -1.0, 1.0),
+        (ClipConvention::ZeroToOne, 0.0, 1.0),
+        (ClipConvention::ReverseZ, 1.0, 0.0),
+    ];
+    for &(convention, near, far) in &expected {
+        let m = camera.projection_matrix(2.0, convention);
+        assert_near_vec3(ndc(&m, vec3(2.0, 1.0, -1.0)), vec3(1.0, 1.0, near));
+        assert_near_vec3(ndc(&m, vec3(-200.0, 100.0, -100.0)), vec3(-1.0, 1.0, far));
+        assert!(m.transform_point(vec3(0.0, 0.0, -50.0)).w > 0.0);
+    }
+}
+
+#[test]
+fn camera_gate_fit() {
+    let extents = |gate_fit: i32, aspect: f64| {
+        let scene = load_camera(&perspective_props(gate_fit));
+        scene.cameras[0].viewport_extents(aspect)
+    };
+    let assert_extents = |(x, y): (f64, f64), (ex, ey): (f64, f64)| {
+        assert!((x - ex).abs() < 1e-6 && (y - ey).abs() < 1e-6, "({}, {}) != ({}, {})", x, y, ex, ey);
+    };
+
+    // Gate is 2:1, fitting into a square or a 4:1 viewport
+    assert_extents(extents(0, 1.0), (2.0, 1.0));
+    assert_extents(extents(1, 1.0), (1.0, 1.0));
+    assert_extents(extents(2, 1.0), (2.0, 2.0));
+    assert_extents(extents(3, 1.0), (1.0, 1.0));
+    assert_extents(extents(3, 4.0), (2.0, 0.5));
+    assert_extents(extents(4, 1.0), (2.0, 2.0));
+    assert_extents(extents(4, 4.0), (4.0, 1.0));
+    assert_extents(extents(5, 4.0), (2.0, 1.0));
+
+    let scene = load_camera(&perspective_props(4));
+    let m = scene.cameras[0].projection_matrix(1.0, ClipConvention::ZeroToOne);
+    assert_near_vec3(ndc(&m, vec3(2.0, 1.0, -1.0)), vec3(1.0, 0.5, 0.0));
+}
+
+#[test]
+fn camera_film_offset_infinite() {
+    // 2x1 inch film at 25.4mm focal length, shifted half an inch right
+    let props = [
+        enum_prop("ApertureMode", 3), prop("FocalLength", 25.4), prop("FilmWidth", 2.0), prop("FilmHeight", 1.0),
+        prop("FilmOffsetX", 0.5), prop("NearPlane", 0.5),
+    ].concat();
+    let scene = load_camera(&props);
+    let camera = &scene.cameras[0];
+    assert_eq!(camera.film_offset(), (0.5, 0.0));
+
+    let m = camera.projection_matrix(2.0, ClipConvention::ReverseZ);
+    assert_near_vec3(ndc(&m, vec3(0.5, 0.0, -1.0)), vec3(0.0, 0.0, 0.5));
+    assert_near_vec3(ndc(&m, vec3(3.0, 0.5, -2.0)), vec3(1.0, 0.5, 0.25));
+    assert_near_vec3(ndc(&m, vec3(0.0, 0.0, -0.5)), vec3(-0.5, 0.0, 1.0));
+    assert!(ndc(&m, vec3(0.0, 0.0, -1e9)).z.abs() < 1e-6);
+
+    let m = camera.projection_matrix(2.0, ClipConvention::OpenGl);
+    assert!((ndc(&m, vec3(0.0, 0.0, -1e9)).z - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn camera_orthographic() {
+    let props = [
+        enum_prop("CameraProjectionType", 1), prop("OrthoZoom", 2.0), prop("AspectWidth", 2.0), prop("AspectHeight", 1.0),
+        enum_prop("GateFit", 1), prop("NearPlane", 1.0), prop("FarPlane", 11.0),
+    ].concat();
+    let scene = load_camera(&props);
+    let camera = &scene.cameras[0];
+    assert_eq!(camera.projection_mode, ufbx::ProjectionMode::Orthographic);
+    let size = camera.orthographic_size;
+    assert!((size.x - size.y * 2.0).abs() < 1e-6);
+
+    let expected = [
+        (ClipConvention::OpenGl, -1.0, 1.0),
+        (ClipConvention::ZeroToOne, 0.0, 1.0),
+        (ClipConvention::ReverseZ, 1.0, 0.0),
+    ];
+    for &(convention, near, far) in &expected {
+        let m = camera.projection_matrix(2.0, convention);
+        assert_eq!(m.transform_point(vec3(0.0, 0.0, -5.0)).w, 1.0);
+        assert_near_vec3(ndc(&m, vec3(size.x, -size.y, -1.0)), vec3(1.0, -1.0, near));
+        assert_near_vec3(ndc(&m, vec3(-size.x * 0.5, size.y, -11.0)), vec3(-0.5, 1.0, far));
+    }
+}
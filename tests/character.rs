@@ -0,0 +1,166 @@
+use ufbx::{self, HumanoidSlot, TPoseSource};
+
+mod common;
+use common::{assert_near, load_cube_anim_with};
+
+// (name, parent, translation)
+const BONES: &[(&str, Option<usize>, &str)] = &[
+    ("hips", None, "0,100,0"),
+    ("spine", Some(0), "0,10,0"),
+    ("thigh_l", Some(0), "10,-5,0"),
+    ("knee_l", Some(2), "0,-45,0"),
+    ("prop", None, "0,0,0"),
+];
+
+// (slot property, bone index)
+const LINKS: &[(&str, usize)] = &[
+    ("HipsLink", 0),
+    ("SpineLink", 1),
+    ("LeftUpLegLink", 2),
+    ("LeftLegLink", 3),
+    ("LeftUpLegLink", 1),
+    ("LeftHandFloorContactLink", 4),
+];
+
+const CHARACTERIZATION: &str = concat!(
+    "\t\tCHARACTERIZE: 1\n",
+    "\t\tBASE:  {\n\t\t\tLINK: \"Hips\" {\n",
+    "\t\t\t\tTOFFSETX: 1\n\t\t\t\tTOFFSETY: 2\n\t\t\t\tTOFFSETZ: 3\n",
+    "\t\t\t\tROFFSETX: 0\n\t\t\t\tROFFSETY: 90\n\t\t\t\tROFFSETZ: 0\n",
+    "\t\t\t\tSOFFSETX: 2\n\t\t\t\tSOFFSETY: 2\n\t\t\t\tSOFFSETZ: 2\n",
+    "\t\t\t}\n\t\t}\n",
+    "\t\tSPINE:  {\n\t\t\tLINK: \"Spine\" {\n\t\t\t\tTOFFSETY: 5\n\t\t\t}\n\t\t}\n",
+);
+
+// Stance with `hips` rotated 90 degrees around Z, `thigh_l` and `knee_l` are missing
+const CHARACTER_POSE: &str = concat!(
+    "\tCharacterPose: 960, \"CharacterPose::Character1_Pose\", \"CharacterPose\" {\n",
+    "\t\tPoseScene:  {\n\t\t\tObjects:  {\n",
+    "\t\t\t\tModel: 1960, \"Model::hips\", \"LimbNode\" {\n\t\t\t\t\tProperties70:  {\n",
+    "\t\t\t\t\t\tP: \"Lcl Translation\", \"Lcl Translation\", \"\", \"A\",0,95,0\n",
+    "\t\t\t\t\t\tP: \"Lcl Rotation\", \"Lcl Rotation\", \"\", \"A\",0,0,90\n",
+    "\t\t\t\t\t}\n\t\t\t\t}\n",
+    "\t\t\t\tModel: 1961, \"Model::spine\", \"LimbNode\" {\n\t\t\t\t\tProperties70:  {\n",
+    "\t\t\t\t\t\tP: \"Lcl Translation\", \"Lcl Translation\", \"\", \"A\",0,10,0\n",
+    "\t\t\t\t\t}\n\t\t\t\t}\n",
+    "\t\t\t}\n\t\t\tConnections:  {\n\t\t\t\tC: \"OO\",1960,0\n\t\t\t\tC: \"OO\",1961,1960\n\t\t\t}\n",
+    "\t\t}\n\t}\n");
+
+// Adds a skeleton linked to a character to `cube_anim.fbx`, `thigh_l` has a bind pose.
+// The character pose is connected to the character if `connect_pose` is set.
+fn load_character(retain_dom: bool, connect_pose: bool) -> ufbx::SceneRoot {
+    let mut objects = String::new();
+    let mut connections = String::new();
+    for (ix, &(name, parent, translation)) in BONES.iter().enumerate() {
+        objects += &format!(concat!(
+            "\tModel: {}, \"Model::{}\", \"LimbNode\" {{\n\t\tVersion: 232\n\t\tProperties70:  {{\n",
+            "\t\t\tP: \"Lcl Translation\", \"Lcl Translation\", \"\", \"A\",{}\n\t\t}}\n\t}}\n"),
+            900 + ix, name, translation);
+        connections += &format!("\tC: \"OO\",{},{}\n", 900 + ix, parent.map_or(0, |p| 900 + p));
+    }
+    objects += concat!(
+        "\tPose: 940, \"Pose::BIND_POSES\", \"BindPose\" {\n\t\tType: \"BindPose\"\n\t\tVersion: 100\n\t\tNbPoseNodes: 1\n",
+        "\t\tPoseNode:  {\n\t\t\tNode: 902\n\t\t\tMatrix: *16 {\n",
+        "\t\t\t\ta: 1,0,0,0,0,1,0,0,0,0,1,0,20,90,0,1\n\t\t\t}\n\t\t}\n\t}\n");
+    objects += &format!(
+        "\tConstraint: 950, \"Character::Character1\", \"Character\" {{\n\t\tType: \"Character\"\n{}\t}}\n",
+        CHARACTERIZATION);
+    connections += "\tC: \"OO\",950,0\n";
+    objects += CHARACTER_POSE;
+    if connect_pose {
+        connections += "\tC: \"OO\",960,950\n";
+    }
+    for &(prop, bone) in LINKS {
+        connections += &format!("\tC: \"OP\",{},950, \"{}\"\n", 900 + bone, prop);
+    }
+
+    let opts = ufbx::LoadOpts { retain_dom, ..Default::default() };
+    load_cube_anim_with(&objects, &connections, opts)
+}
+
+#[test]
+fn character_slot_names() {
+    for &slot in HumanoidSlot::ALL {
+        assert_eq!(HumanoidSlot::from_name(slot.name()), Some(slot));
+    }
+    assert_eq!(HumanoidSlot::from_name("LeftUpLeg"), Some(HumanoidSlot::LeftUpLeg));
+    assert_eq!(HumanoidSlot::from_name("LeftUpLegLink"), None);
+}
+
+#[test]
+fn character_links() {
+    let scene = load_character(true, true);
+    let defs = ufbx::character_definitions(&scene);
+    assert_eq!(defs.len(), 1);
+    let def = &defs[0];
+    assert_eq!(def.character.element.name, "Character1");
+    assert!(def.characterized);
+
+    // Duplicate and unknown slots are ignored
+    let slots: Vec<HumanoidSlot> = def.links.iter().map(|l| l.slot).collect();
+    assert_eq!(slots, [HumanoidSlot::Hips, HumanoidSlot::LeftUpLeg, HumanoidSlot::LeftLeg, HumanoidSlot::Spine]);
+    assert_eq!(def.node(HumanoidSlot::LeftUpLeg).unwrap().element.name, "thigh_l");
+    assert!(def.node(HumanoidSlot::Head).is_none());
+
+    let knee = scene.nodes.iter().find(|n| n.element.name == "knee_l").unwrap();
+    let prop = scene.nodes.iter().find(|n| n.element.name == "prop").unwrap();
+    assert_eq!(def.slot_of(knee), Some(HumanoidSlot::LeftLeg));
+    assert_eq!(def.slot_of(prop), None);
+
+    // T-pose from the character pose, then the bind pose if available
+    let hips = def.link(HumanoidSlot::Hips).unwrap();
+    assert_eq!(hips.tpose_source, TPoseSource::CharacterPose);
+    assert_near(hips.tpose.m13, 95.0);
+    assert_near(hips.tpose.m10, 1.0);
+    let spine = def.link(HumanoidSlot::Spine).unwrap();
+    assert_eq!(spine.tpose_source, TPoseSource::CharacterPose);
+    assert_near(spine.tpose.m03, -10.0);
+    assert_near(spine.tpose.m13, 95.0);
+    let thigh = def.link(HumanoidSlot::LeftUpLeg).unwrap();
+    assert_eq!(thigh.tpose_source, TPoseSource::BindPose);
+    assert_near(thigh.tpose.m03, 20.0);
+    assert_near(thigh.tpose.m13, 90.0);
+    let knee = def.link(HumanoidSlot::LeftLeg).unwrap();
+    assert_eq!(knee.tpose_source, TPoseSource::Scene);
+    assert_near(knee.tpose.m03, 10.0);
+    assert_near(knee.tpose.m13, 50.0);
+
+    let hips = def.link(HumanoidSlot::Hips).unwrap();
+    assert_near(hips.offset.translation.x, 1.0);
+    assert_near(hips.offset.translation.z, 3.0);
+    assert_near(hips.offset.rotation.y, (0.5f64).sqrt());
+    assert_near(hips.offset.rotation.w, (0.5f64).sqrt());
+    assert_near(hips.offset.scale.y, 2.0);
+    let spine = def.link(HumanoidSlot::Spine).unwrap();
+    assert_near(spine.offset.translation.y, 5.0);
+    assert_near(spine.offset.scale.x, 1.0);
+    assert_near(knee.offset.rotation.w, 1.0);
+}
+
+#[test]
+fn character_without_dom() {
+    let scene = load_character(false, true);
+    let def = scene.characters[0].definition();
+    assert!(!def.characterized);
+    assert_eq!(def.links.len(), 4);
+    let hips = def.link(HumanoidSlot::Hips).unwrap();
+    assert_near(hips.offset.translation.x, 0.0);
+    assert_near(hips.offset.rotation.w, 1.0);
+    assert_near(hips.offset.scale.x, 1.0);
+    assert_near(hips.tpose.m13, 100.0);
+    assert_eq!(hips.tpose_source, TPoseSource::Scene);
+    assert_eq!(def.link(HumanoidSlot::LeftUpLeg).unwrap().tpose_source, TPoseSource::BindPose);
+}
+
+#[test]
+fn character_pose_by_name() {
+    // Without a connection the pose is only found by name through the scene
+    let scene = load_character(true, false);
+    let def = scene.characters[0].definition();
+    assert_eq!(def.link(HumanoidSlot::Hips).unwrap().tpose_source, TPoseSource::Scene);
+
+    let defs = ufbx::character_definitions(&scene);
+    let hips = defs[0].link(HumanoidSlot::Hips).unwrap();
+    assert_eq!(hips.tpose_source, TPoseSource::CharacterPose);
+    assert_near(hips.tpose.m13, 95.0);
+}
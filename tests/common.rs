@@ -36,3 +36,135 @@ pub fn check_mesh_positions(node: &ufbx::Node, mesh: &ufbx::Mesh, ref_mesh: &ufb
     }
     assert!(ref_points.len() == 0);
 }
+
+pub fn vec3(x: f64, y: f64, z: f64) -> ufbx::Vec3 {
+    ufbx::Vec3 { x, y, z }
+}
+
+pub fn vec3_distance(a: ufbx::Vec3, b: ufbx::Vec3) -> f64 {
+    distance(a, b).sqrt()
+}
+
+pub fn vec3_sub(a: ufbx::Vec3, b: ufbx::Vec3) -> ufbx::Vec3 {
+    vec3(a.x - b.x, a.y - b.y, a.z - b.z)
+}
+
+pub fn vec3_dot(a: ufbx::Vec3, b: ufbx::Vec3) -> f64 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+pub fn vec3_cross(a: ufbx::Vec3, b: ufbx::Vec3) -> ufbx::Vec3 {
+    vec3(a.y * b.z - a.z * b.y, a.z * b.x - a.x * b.z, a.x * b.y - a.y * b.x)
+}
+
+pub fn vec3_length(a: ufbx::Vec3) -> f64 {
+    vec3_dot(a, a).sqrt()
+}
+
+pub fn assert_near(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+}
+
+pub fn assert_near_rel(a: f64, b: f64) {
+    assert!((a - b).abs() <= b.abs() * 1e-9, "{} != {}", a, b);
+}
+
+pub fn assert_near_vec3(a: ufbx::Vec3, b: ufbx::Vec3) {
+    assert!((a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6 && (a.z - b.z).abs() < 1e-6, "{:?} != {:?}", a, b);
+}
+
+pub fn assert_points_eq(a: &[ufbx::Vec3], b: &[ufbx::Vec3]) {
+    assert_eq!(a.len(), b.len());
+    for (a, b) in a.iter().zip(b) {
+        assert!((a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9 && (a.z - b.z).abs() < 1e-9, "{:?} != {:?}", a, b);
+    }
+}
+
+pub fn node_index(scene: &ufbx::Scene, name: &str) -> usize {
+    scene.nodes.iter().position(|n| n.element.name == name).expect("expected node")
+}
+
+pub fn translation(m: &ufbx::Matrix) -> ufbx::Vec3 {
+    ufbx::Vec3 { x: m.m03, y: m.m13, z: m.m23 }
+}
+
+/// Source of `cube_anim.fbx` with `objects` inserted before the cube model
+/// and `connections` after the connection of the cube to the root.
+pub fn cube_anim_source(objects: &str, connections: &str) -> String {
+    const MODEL: &str = "\tModel: 2244692774032,";
+    const CONNECTION: &str = "\tC: \"OO\",2244692774032,0\n";
+    std::fs::read_to_string("tests/data/cube_anim.fbx").expect("expected to read file")
+        .replace(MODEL, &format!("{}{}", objects, MODEL))
+        .replace(CONNECTION, &format!("{}{}", CONNECTION, connections))
+}
+
+pub fn load_cube_anim_with(objects: &str, connections: &str, opts: ufbx::LoadOpts) -> ufbx::SceneRoot {
+    let source = cube_anim_source(objects, connections);
+    ufbx::load_memory(source.as_bytes(), opts).expect("expected to load scene")
+}
+
+/// Obj source of an `n` by `n` quad grid with heights from `z` and UVs spanning `0..1`,
+/// the left half uses material "A" and the right half "B".
+pub fn grid_obj(n: usize, z: impl Fn(usize, usize) -> f64) -> String {
+    let mut s = String::new();
+    for y in 0..=n {
+        for x in 0..=n {
+            s += &format!("v {} {} {}\nvt {} {}\n", x, y, z(x, y), x as f64 / n as f64, y as f64 / n as f64);
+        }
+    }
+    let ix = |x: usize, y: usize| y * (n + 1) + x + 1;
+    for (material, range) in [("A", 0..n / 2), ("B", n / 2..n)].iter() {
+        s += &format!("usemtl {}\n", material);
+        for y in 0..n {
+            for x in range.clone() {
+                let corners = [ix(x, y), ix(x + 1, y), ix(x + 1, y + 1), ix(x, y + 1)];
+                s += &format!("f {0}/{0} {1}/{1} {2}/{2} {3}/{3}\n", corners[0], corners[1], corners[2], corners[3]);
+            }
+        }
+    }
+    s
+}
+
+pub fn load_obj_lod(source: &str) -> ufbx::LodMesh {
+    let opts = ufbx::LoadOpts {
+        file_format: ufbx::FileFormat::Obj,
+        ..Default::default()
+    };
+    let scene = ufbx::load_memory(source.as_bytes(), opts).expect("expected to load scene");
+    ufbx::LodMesh::from_mesh(&scene.meshes[0])
+}
+
+/// Flat grid from `grid_obj()` as a `LodMesh`.
+pub fn load_grid(n: usize) -> ufbx::LodMesh {
+    load_obj_lod(&grid_obj(n, |_, _| 0.0))
+}
+
+/// Source of `cube_anim.fbx` with a file texture connected to the diffuse color of `lambert1`.
+pub fn textured_cube_source(absolute: &str, relative: &str) -> String {
+    let texture = format!(concat!(
+        "\tTexture: 700, \"Texture::diffuse\", \"\" {{\n\t\tType: \"TextureVideoClip\"\n\t\tVersion: 202\n",
+        "\t\tTextureName: \"Texture::diffuse\"\n\t\tFileName: \"{}\"\n\t\tRelativeFilename: \"{}\"\n\t}}\n"),
+        absolute, relative);
+    cube_anim_source(&texture, "\tC: \"OP\",700,2242872361376, \"DiffuseColor\"\n")
+}
+
+/// Empty directory under the system temp directory, unique to the test process.
+pub fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("ufbx-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    dir
+}
+
+/// Source of a material whose diffuse color is fed by a chain of `depth` nested layered textures
+/// ending in a file texture.
+pub fn nested_layers_source(depth: usize) -> String {
+    let mut objects = String::from("\tMaterial: 1000, \"Material::mat\", \"\" {\n\t\tShadingModel: \"phong\"\n\t}\n");
+    let mut connections = String::from("\tC: \"OP\",2000,1000, \"DiffuseColor\"\n");
+    for ix in 0..depth {
+        objects += &format!("\tLayeredTexture: {}, \"LayeredTexture::layer{}\", \"\" {{\n\t}}\n", 2000 + ix, ix);
+        connections += &format!("\tC: \"OO\",{},{}\n", 2001 + ix, 2000 + ix);
+    }
+    objects += &format!("\tTexture: {}, \"Texture::leaf\", \"\" {{\n\t\tType: \"TextureVideoClip\"\n\t\tFileName: \"leaf.png\"\n\t}}\n", 2000 + depth);
+    format!("; FBX 7.4.0 project file\nFBXHeaderExtension:  {{\n\tFBXHeaderVersion: 1003\n\tFBXVersion: 7400\n}}\nObjects:  {{\n{}}}\nConnections:  {{\n{}}}\n", objects, connections)
+}
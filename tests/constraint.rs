@@ -0,0 +1,271 @@
+use ufbx::{self, ConstraintOpts, Vec3};
+
+mod common;
+use common::{assert_near_vec3, load_cube_anim_with, node_index, translation, vec3, vec3_distance};
+
+struct Model<'a> {
+    id: u64,
+    name: &'a str,
+    parent: u64,
+    translation: (f64, f64, f64),
+    rotation: (f64, f64, f64),
+    props: &'a str,
+}
+
+fn model(id: u64, name: &str, parent: u64, translation: (f64, f64, f64)) -> Model<'_> {
+    Model { id, name, parent, translation, rotation: (0.0, 0.0, 0.0), props: "" }
+}
+
+struct ConstraintDesc<'a> {
+    kind: &'a str,
+    props: &'a str,
+    // (node id, connection property)
+    connections: &'a [(u64, &'a str)],
+}
+
+// Adds nulls and constraints to `cube_anim.fbx`, constraints get ids starting from 700
+fn load_rig(models: &[Model], constraints: &[ConstraintDesc]) -> ufbx::SceneRoot {
+    let mut objects = String::new();
+    let mut connections = String::new();
+    for m in models {
+        objects += &format!(concat!(
+            "\tModel: {}, \"Model::{}\", \"Null\" {{\n\t\tVersion: 232\n\t\tProperties70:  {{\n",
+            "\t\t\tP: \"Lcl Translation\", \"Lcl Translation\", \"\", \"A\",{},{},{}\n",
+            "\t\t\tP: \"Lcl Rotation\", \"Lcl Rotation\", \"\", \"A\",{},{},{}\n{}\t\t}}\n\t}}\n"),
+            m.id, m.name, m.translation.0, m.translation.1, m.translation.2, m.rotation.0, m.rotation.1, m.rotation.2, m.props);
+        connections += &format!("\tC: \"OO\",{},{}\n", m.id, m.parent);
+    }
+    for (ix, c) in constraints.iter().enumerate() {
+        let id = 700 + ix;
+        objects += &format!(
+            "\tConstraint: {}, \"Constraint::c{}\", \"Constraint\" {{\n\t\tType: \"{}\"\n\t\tProperties70:  {{\n{}\t\t}}\n\t}}\n",
+            id, ix, c.kind, c.props);
+        for &(node, prop) in c.connections {
+            connections += &format!("\tC: \"OP\",{},{}, \"{}\"\n", node, id, prop);
+        }
+    }
+    load_cube_anim_with(&objects, &connections, ufbx::LoadOpts::default())
+}
+
+fn position(pose: &ufbx::ConstraintPose, ix: usize) -> Vec3 {
+    translation(&pose.node_to_world[ix])
+}
+
+fn solve(scene: &ufbx::Scene) -> ufbx::ConstraintPose {
+    let mut pose = ufbx::ConstraintPose::from_scene(scene);
+    pose.solve(scene, &ConstraintOpts::default());
+    pose
+}
+
+#[test]
+fn constraint_position_weights() {
+    let models = [
+        model(10, "parent", 0, (1.0, 0.0, 0.0)),
+        model(11, "node", 10, (0.0, 0.0, 7.0)),
+        model(12, "child", 11, (0.0, 1.0, 0.0)),
+        model(13, "t1", 0, (2.0, 0.0, 0.0)),
+        model(14, "t2", 0, (0.0, 4.0, 0.0)),
+    ];
+    let props = "\t\t\tP: \"t2.Weight\", \"Number\", \"\", \"A\",300\n";
+    let scene = load_rig(&models, &[ConstraintDesc {
+        kind: "Position From Positions",
+        props,
+        connections: &[(11, "Constrained Object"), (13, "Source"), (14, "Source")],
+    }]);
+    let constraint = &scene.constraints[0];
+    assert_eq!(constraint.type_, ufbx::ConstraintType::Position);
+    assert_eq!(constraint.targets.len(), 2);
+
+    let pose = solve(&scene);
+    let (node, child) = (node_index(&scene, "node"), node_index(&scene, "child"));
+    assert_near_vec3(position(&pose, node), vec3(0.5, 3.0, 0.0));
+    assert_near_vec3(pose.local_transforms[node].translation, vec3(-0.5, 3.0, 0.0));
+    assert_near_vec3(position(&pose, child), vec3(0.5, 4.0, 0.0));
+
+    // Masked axis and half constraint weight
+    let props = format!("{}{}{}", props,
+        "\t\t\tP: \"AffectY\", \"bool\", \"\", \"\",0\n",
+        "\t\t\tP: \"Weight\", \"Number\", \"\", \"A\",50\n");
+    let scene = load_rig(&models, &[ConstraintDesc {
+        kind: "Position From Positions",
+        props: &props,
+        connections: &[(11, "Constrained Object"), (13, "Source"), (14, "Source")],
+    }]);
+    assert_eq!(scene.constraints[0].constrain_translation, [true, false, true]);
+    let pose = solve(&scene);
+    assert_near_vec3(pose.local_transforms[node].translation, vec3(-0.25, 0.0, 3.5));
+
+    // Inactive constraints are skipped
+    let props = "\t\t\tP: \"Active\", \"bool\", \"\", \"\",0\n";
+    let scene = load_rig(&models, &[ConstraintDesc {
+        kind: "Position From Positions",
+        props,
+        connections: &[(11, "Constrained Object"), (13, "Source")],
+    }]);
+    assert_near_vec3(position(&solve(&scene), node), vec3(1.0, 0.0, 7.0));
+}
+
+#[test]
+fn constraint_inherit_modes() {
+    // `ignore` does not inherit the scale of `scaled`, `zero` has zero scale
+    let models = [
+        Model { props: "\t\t\tP: \"Lcl Scaling\", \"Lcl Scaling\", \"\", \"A\",2,2,2\n", ..model(10, "scaled", 0, (1.0, 0.0, 0.0)) },
+        Model { props: "\t\t\tP: \"InheritType\", \"enum\", \"\", \"\",2\n", ..model(11, "ignore", 10, (1.0, 0.0, 0.0)) },
+        model(12, "child", 11, (0.0, 1.0, 0.0)),
+        Model { props: "\t\t\tP: \"Lcl Scaling\", \"Lcl Scaling\", \"\", \"A\",0,0,0\n", ..model(13, "zero", 0, (0.0, 0.0, 0.0)) },
+        model(14, "target", 0, (4.0, 3.0, 0.0)),
+    ];
+    let scene = load_rig(&models, &[]);
+    let (ignore, child, zero) = (node_index(&scene, "ignore"), node_index(&scene, "child"), node_index(&scene, "zero"));
+    assert_eq!(scene.nodes[ignore].inherit_mode, ufbx::InheritMode::IgnoreParentScale);
+
+    // Recomputing the loaded pose matches ufbx
+    let mut pose = ufbx::ConstraintPose::from_scene(&scene);
+    pose.update_world(&scene.nodes[0]);
+    for (node, world) in scene.nodes.iter().zip(&pose.node_to_world) {
+        assert_near_vec3(translation(world), translation(&node.node_to_world));
+        assert_near_vec3(vec3(world.m00, world.m11, world.m22), vec3(node.node_to_world.m00, node.node_to_world.m11, node.node_to_world.m22));
+    }
+
+    let scene = load_rig(&models, &[
+        ConstraintDesc { kind: "Position From Positions", props: "", connections: &[(11, "Constrained Object"), (14, "Source")] },
+        ConstraintDesc { kind: "Position From Positions", props: "", connections: &[(13, "Constrained Object"), (14, "Source")] },
+    ]);
+    let pose = solve(&scene);
+    assert_near_vec3(position(&pose, ignore), vec3(4.0, 3.0, 0.0));
+    assert_near_vec3(pose.local_transforms[ignore].translation, vec3(1.5, 1.5, 0.0));
+    assert_near_vec3(position(&pose, child), vec3(4.0, 4.0, 0.0));
+    assert_near_vec3(position(&pose, zero), vec3(4.0, 3.0, 0.0));
+    assert_near_vec3(pose.local_transforms[zero].scale, vec3(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn constraint_rotation_parent() {
+    let mut target = model(13, "t1", 0, (1.0, 2.0, 3.0));
+    target.rotation = (0.0, 90.0, 0.0);
+    let models = [model(10, "rot", 0, (0.0, 0.0, 0.0)), model(11, "par", 0, (5.0, 0.0, 0.0)), target];
+    let scene = load_rig(&models, &[
+        ConstraintDesc {
+            kind: "Rotation From Rotations",
+            props: "\t\t\tP: \"Rotation\", \"Vector3D\", \"Vector\", \"\",0,0,90\n",
+            connections: &[(10, "Constrained Object"), (13, "Source")],
+        },
+        ConstraintDesc {
+            kind: "Parent-Child",
+            props: "\t\t\tP: \"t1.Offset T\", \"Vector3D\", \"Vector\", \"\",1,0,0\n",
+            connections: &[(11, "Constrained object (Child)"), (13, "Source (Parent)")],
+        },
+    ]);
+    let pose = solve(&scene);
+
+    // Target rotation followed by the 90 degree Z offset: X -> Y -> Y, Y -> -X -> -X
+    let rot = &pose.node_to_world[node_index(&scene, "rot")];
+    assert_near_vec3(vec3(rot.m00, rot.m10, rot.m20), vec3(0.0, 1.0, 0.0));
+    assert_near_vec3(vec3(rot.m01, rot.m11, rot.m21), vec3(0.0, 0.0, 1.0));
+    assert_near_vec3(position(&pose, node_index(&scene, "rot")), vec3(0.0, 0.0, 0.0));
+
+    // Offset is in the target space, rotated 90 degrees around Y
+    let par = &pose.node_to_world[node_index(&scene, "par")];
+    assert_near_vec3(position(&pose, node_index(&scene, "par")), vec3(1.0, 2.0, 2.0));
+    assert_near_vec3(vec3(par.m00, par.m10, par.m20), vec3(0.0, 0.0, -1.0));
+}
+
+#[test]
+fn constraint_aim() {
+    let models = [
+        model(10, "aimer", 0, (0.0, 1.0, 0.0)),
+        model(11, "target", 0, (0.0, 1.0, 5.0)),
+        model(12, "up", 0, (3.0, 1.0, 0.0)),
+    ];
+    let scene = load_rig(&models, &[ConstraintDesc {
+        kind: "Aim",
+        props: "",
+        connections: &[(10, "Constrained Object"), (11, "Aim At Object")],
+    }]);
+    let pose = solve(&scene);
+    let m = &pose.node_to_world[node_index(&scene, "aimer")];
+    assert_near_vec3(vec3(m.m00, m.m10, m.m20), vec3(0.0, 0.0, 1.0));
+    assert_near_vec3(vec3(m.m01, m.m11, m.m21), vec3(0.0, 1.0, 0.0));
+
+    // Aim -Y at the target with +Z pointing towards the up object
+    let props = concat!(
+        "\t\t\tP: \"AimVector\", \"Vector3D\", \"Vector\", \"\",0,-1,0\n",
+        "\t\t\tP: \"UpVector\", \"Vector3D\", \"Vector\", \"\",0,0,1\n",
+        "\t\t\tP: \"WorldUpType\", \"enum\", \"\", \"\",1\n");
+    let scene = load_rig(&models, &[ConstraintDesc {
+        kind: "Aim",
+        props,
+        connections: &[(10, "Constrained Object"), (11, "Aim At Object"), (12, "World Up Object")],
+    }]);
+    assert_eq!(scene.constraints[0].aim_up_type, ufbx::ConstraintAimUpType::ToNode);
+    let pose = solve(&scene);
+    let m = &pose.node_to_world[node_index(&scene, "aimer")];
+    assert_near_vec3(vec3(-m.m01, -m.m11, -m.m21), vec3(0.0, 0.0, 1.0));
+    assert_near_vec3(vec3(m.m02, m.m12, m.m22), vec3(1.0, 0.0, 0.0));
+}
+
+#[test]
+fn constraint_single_chain_ik() {
+    let models = [
+        model(10, "root", 0, (0.0, 0.0, 0.0)),
+        model(11, "mid", 10, (0.0, 2.0, 0.0)),
+        model(12, "end", 11, (0.0, 2.0, 0.0)),
+        model(13, "goal", 0, (2.0, 2.0, 0.0)),
+        model(14, "pole", 0, (0.0, 2.0, -5.0)),
+    ];
+    let connections = [(10, "First Joint"), (12, "End Joint"), (13, "Effector"), (14, "Pole Vector Object")];
+    let scene = load_rig(&models, &[ConstraintDesc { kind: "Single Chain IK", props: "", connections: &connections }]);
+    let pose = solve(&scene);
+
+    let (root, mid, end) = (node_index(&scene, "root"), node_index(&scene, "mid"), node_index(&scene, "end"));
+    let (a, b, c) = (position(&pose, root), position(&pose, mid), position(&pose, end));
+    assert_near_vec3(c, vec3(2.0, 2.0, 0.0));
+    assert!((vec3_distance(a, b) - 2.0).abs() < 1e-6 && (vec3_distance(b, c) - 2.0).abs() < 1e-6);
+    // Bends towards the pole object
+    assert!(b.z < -1.0);
+
+    // Out of reach goals straighten the chain
+    let mut far = models.iter().map(|m| model(m.id, m.name, m.parent, m.translation)).collect::<Vec<_>>();
+    far[3].translation = (10.0, 0.0, 0.0);
+    let scene = load_rig(&far, &[ConstraintDesc { kind: "Single Chain IK", props: "", connections: &connections }]);
+    let pose = solve(&scene);
+    assert_near_vec3(position(&pose, end), vec3(4.0, 0.0, 0.0));
+
+    // Longer chains use the iterative solver
+    let chain = [
+        model(10, "root", 0, (0.0, 0.0, 0.0)),
+        model(11, "j1", 10, (0.0, 1.0, 0.0)),
+        model(12, "j2", 11, (0.0, 1.0, 0.0)),
+        model(15, "end", 12, (0.0, 1.0, 0.0)),
+        model(13, "goal", 0, (1.5, 1.5, 0.0)),
+    ];
+    let connections = [(10, "First Joint"), (15, "End Joint"), (13, "Effector")];
+    let scene = load_rig(&chain, &[ConstraintDesc { kind: "Single Chain IK", props: "", connections: &connections }]);
+    let pose = ufbx::evaluate_constraints(&scene, &scene.anim, 0.0, &ConstraintOpts { ik_iterations: 64 }).unwrap();
+    assert!(vec3_distance(position(&pose, node_index(&scene, "end")), vec3(1.5, 1.5, 0.0)) < 1e-3);
+}
+
+#[test]
+fn constraint_bake() {
+    // Follow the animated cube
+    let models = [model(10, "follower", 0, (0.0, 0.0, 0.0))];
+    let scene = load_rig(&models, &[ConstraintDesc {
+        kind: "Position From Positions",
+        props: "\t\t\tP: \"Translation\", \"Vector3D\", \"Vector\", \"\",0,1,0\n",
+        connections: &[(10, "Constrained Object"), (2244692774032, "Source")],
+    }]);
+    let baked = ufbx::bake_anim(&scene, &scene.anim, ufbx::BakeOpts::default()).expect("expected to bake");
+    let result = ufbx::bake_constraints(&scene, &scene.anim, &baked, &ConstraintOpts::default()).expect("expected to bake constraints");
+    assert_eq!(result.len(), 1);
+
+    let follower = node_index(&scene, "follower");
+    let node = &result[0];
+    assert_eq!(node.typed_id as usize, follower);
+    assert!(node.translation_keys.len() > 2);
+    let cube = &scene.nodes[node_index(&scene, "pCube1")];
+    for key in &node.translation_keys {
+        let state = ufbx::evaluate_scene(&scene, &scene.anim, key.time, ufbx::EvaluateOpts::default()).unwrap();
+        let cube = &state.nodes[cube.element.typed_id as usize].node_to_world;
+        assert_near_vec3(key.value, vec3(cube.m03, cube.m13 + 1.0, cube.m23));
+    }
+}
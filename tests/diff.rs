@@ -0,0 +1,109 @@
+use ufbx;
+
+mod common;
+
+#[test]
+fn diff_identical() {
+    let a = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let b = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let diff = ufbx::diff(&a, &b, ufbx::DiffOpts::default());
+    assert!(diff.is_empty(), "expected no changes, got:\n{}", diff);
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn diff_different_scenes() {
+    let a = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let b = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let diff = ufbx::diff(&a, &b, ufbx::DiffOpts::default());
+    assert!(diff.removed_nodes.iter().any(|p| p == "Cube"));
+    assert!(diff.removed_nodes.iter().any(|p| p == "Light"));
+    assert!(diff.added_nodes.iter().any(|p| p == "pCube1"));
+    assert!(diff.added_materials.iter().any(|p| p == "lambert1"));
+    assert!(diff.anim_changes.iter().any(|c| c.element == "pCube1" && c.old.is_none()));
+
+    let text = diff.to_string();
+    assert!(text.contains("- node Cube\n"));
+    assert!(text.contains("+ node pCube1\n"));
+}
+
+#[test]
+fn diff_evaluated() {
+    let scene = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let anim = scene.anim.as_ref();
+    let begin = scene.evaluate(anim, 0.0, Default::default())
+        .expect("failed to evaluate animation");
+    let end = scene.evaluate(anim, 12.0/24.0, Default::default())
+        .expect("failed to evaluate animation");
+
+    let diff = ufbx::diff(&begin, &end, ufbx::DiffOpts::default());
+    assert!(diff.added_nodes.is_empty());
+    assert!(diff.removed_nodes.is_empty());
+    assert!(diff.mesh_changes.is_empty());
+
+    let change = diff.transform_changes.iter().find(|c| c.path == "pCube1")
+        .expect("expected pCube1 transform to change");
+    assert!((change.translation_delta - 2.0).abs() < 0.001);
+    assert!(change.rotation_delta_deg > 1.0);
+
+    let material = diff.material_changes.iter().find(|c| c.name == "lambert1")
+        .expect("expected lambert1 to change");
+    assert!(material.props.iter().any(|p| p.name == "DiffuseColor"));
+}
+
+#[test]
+fn diff_duplicate_names() {
+    // Second `lambert1` material, animated by the same curve node as the original one.
+    // It is loaded before the original, which becomes `lambert1#1`.
+    let duplicate = |color: &str| common::load_cube_anim_with(&format!(concat!(
+        "\tMaterial: 600, \"Material::lambert1\", \"\" {{\n\t\tVersion: 102\n\t\tProperties70:  {{\n",
+        "\t\t\tP: \"DiffuseColor\", \"Color\", \"\", \"A+\",{}\n\t\t}}\n\t}}\n"), color),
+        "\tC: \"OP\",2243155097120,600, \"DiffuseColor\"\n", ufbx::LoadOpts::default());
+    let base = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let red = duplicate("1,0,0");
+    let blue = duplicate("0,0,1");
+
+    let diff = ufbx::diff(&base, &red, ufbx::DiffOpts::default());
+    assert_eq!(diff.added_materials, ["lambert1#1"]);
+    assert!(diff.anim_changes.iter().any(|c| c.element == "lambert1#1" && c.old.is_none()));
+    assert!(!diff.anim_changes.iter().any(|c| c.element == "lambert1"));
+
+    let diff = ufbx::diff(&red, &blue, ufbx::DiffOpts::default());
+    assert!(diff.added_materials.is_empty() && diff.removed_materials.is_empty());
+    assert_eq!(diff.material_changes.len(), 1);
+    assert_eq!(diff.material_changes[0].name, "lambert1");
+}
+
+#[test]
+fn diff_mesh_added_removed() {
+    let with_mesh = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let source = std::fs::read_to_string("tests/data/cube_anim.fbx").expect("expected to read file")
+        .replace("\tC: \"OO\",2245309148656,2244692774032\n", "");
+    let without_mesh = ufbx::load_memory(source.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let diff = ufbx::diff(&with_mesh, &without_mesh, ufbx::DiffOpts::default());
+    assert_eq!(diff.mesh_changes.len(), 1);
+    let change = &diff.mesh_changes[0];
+    assert_eq!(change.path, "pCube1");
+    assert_eq!(change.old.map(|s| s.num_vertices), Some(with_mesh.meshes[0].num_vertices));
+    assert!(change.new.is_none());
+    assert!(change.topology_changed);
+    assert!(diff.to_string().contains("- mesh pCube1: vertices 8, faces 6"));
+
+    let diff = ufbx::diff(&without_mesh, &with_mesh, ufbx::DiffOpts::default());
+    assert_eq!(diff.mesh_changes.len(), 1);
+    assert!(diff.mesh_changes[0].old.is_none());
+    assert!(diff.mesh_changes[0].new.is_some());
+    assert!(diff.to_string().contains("+ mesh pCube1: vertices 8, faces 6"));
+}
@@ -0,0 +1,113 @@
+use std::fs;
+use ufbx;
+
+mod common;
+use common::temp_dir;
+
+const EMBEDDED_FBX: &str = r#"; FBX 7.4.0 project file
+FBXHeaderExtension:  {
+	FBXHeaderVersion: 1003
+	FBXVersion: 7400
+}
+Objects:  {
+	Video: 1000, "Video::diffuse", "Clip" {
+		Type: "Clip"
+		FileName: "C:\\textures\\wood.png"
+		RelativeFilename: "textures\\wood.png"
+		Content: , "iVBORw0KGgoAAAAA"
+	}
+	Video: 1001, "Video::other", "Clip" {
+		Type: "Clip"
+		FileName: "D:/other/WOOD.png"
+		RelativeFilename: "other/WOOD.png"
+		Content: , "/9j/4AAQSkZJRg=="
+	}
+	Texture: 2000, "Texture::diffuse", "" {
+		Type: "TextureVideoClip"
+		FileName: "C:\\textures\\wood.png"
+		RelativeFilename: "textures\\wood.png"
+	}
+	Texture: 2001, "Texture::diffuse2", "" {
+		Type: "TextureVideoClip"
+		FileName: "C:\\textures\\wood.png"
+		RelativeFilename: "textures\\wood.png"
+	}
+	Texture: 2002, "Texture::other", "" {
+		Type: "TextureVideoClip"
+		FileName: "D:/other/WOOD.png"
+		RelativeFilename: "other/WOOD.png"
+	}
+}
+Connections:  {
+	C: "OO",1000,2000
+	C: "OO",1000,2001
+	C: "OO",1001,2002
+}
+"#;
+
+#[test]
+fn detect_formats() {
+    assert_eq!(ufbx::detect_image_format(b"\x89PNG\r\n\x1a\n...."), Some(ufbx::ImageFormat::Png));
+    assert_eq!(ufbx::detect_image_format(b"\xff\xd8\xff\xe0"), Some(ufbx::ImageFormat::Jpeg));
+    assert_eq!(ufbx::detect_image_format(b"DDS \x7c\0\0\0"), Some(ufbx::ImageFormat::Dds));
+    assert_eq!(ufbx::detect_image_format(b"RIFF\0\0\0\0WEBPVP8 "), Some(ufbx::ImageFormat::Webp));
+    assert_eq!(ufbx::detect_image_format(b"RIFF\0\0\0\0WAVEfmt "), None);
+    assert_eq!(ufbx::detect_image_format(b""), None);
+}
+
+#[test]
+fn embedded_dedup_and_rename() {
+    let scene = ufbx::load_memory(EMBEDDED_FBX.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let files: Vec<_> = ufbx::embedded_files(&scene, ufbx::EmbeddedOpts::default()).collect();
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].filename, "wood.png");
+    assert_eq!(files[0].kind, ufbx::EmbeddedKind::Image(ufbx::ImageFormat::Png));
+    assert_eq!(files[1].filename, "WOOD_1.png");
+    assert_eq!(files[1].kind, ufbx::EmbeddedKind::Image(ufbx::ImageFormat::Jpeg));
+
+    let opts = ufbx::EmbeddedOpts { rename_collisions: false };
+    let names: Vec<_> = ufbx::embedded_files(&scene, opts).map(|f| f.filename).collect();
+    assert_eq!(names, ["wood.png", "WOOD.png"]);
+
+    let dir = temp_dir("embedded");
+    let paths = ufbx::extract_embedded(&scene, &dir, ufbx::EmbeddedOpts::default())
+        .expect("failed to extract");
+    assert_eq!(paths.len(), 2);
+    assert_eq!(fs::read(&paths[0]).unwrap(), files[0].data);
+    assert_eq!(fs::read(&paths[1]).unwrap(), files[1].data);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn embedded_hostile_names() {
+    let source = concat!(
+        "; FBX 7.4.0 project file\nFBXHeaderExtension:  {\n\tFBXHeaderVersion: 1003\n\tFBXVersion: 7400\n}\n",
+        "Objects:  {\n",
+        "\tVideo: 1000, \"Video::../../evil\", \"Clip\" {\n\t\tType: \"Clip\"\n\t\tContent: , \"iVBORw0KGgoAAAAA\"\n\t}\n",
+        "\tVideo: 1001, \"Video::..\\\\..\\\\evil\", \"Clip\" {\n\t\tType: \"Clip\"\n\t\tContent: , \"/9j/4AAQSkZJRg==\"\n\t}\n",
+        "\tVideo: 1002, \"Video::evil/..\", \"Clip\" {\n\t\tType: \"Clip\"\n\t\tContent: , \"R0lGODlhAQABAA==\"\n\t}\n",
+        "}\n");
+    let scene = ufbx::load_memory(source.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let names: Vec<_> = ufbx::embedded_files(&scene, ufbx::EmbeddedOpts::default()).map(|f| f.filename).collect();
+    assert_eq!(names, ["evil.png", "evil.jpg", "embedded.gif"]);
+
+    let root = temp_dir("embedded-hostile");
+    let dir = root.join("out");
+    let paths = ufbx::extract_embedded(&scene, &dir, ufbx::EmbeddedOpts::default())
+        .expect("failed to extract");
+    assert_eq!(paths.len(), 3);
+    assert!(paths.iter().all(|p| p.parent() == Some(dir.as_path())));
+    assert_eq!(fs::read_dir(&root).unwrap().count(), 1);
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn embedded_none() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    assert_eq!(ufbx::embedded_files(&scene, ufbx::EmbeddedOpts::default()).count(), 0);
+}
@@ -0,0 +1,100 @@
+use std::f64::consts::PI;
+use ufbx::{self, LightConvention, LightUnitOpts, PhotometricUnit};
+
+mod common;
+use common::{assert_near_rel, cube_anim_source};
+
+// (light type, decay, intensity property, extra properties, scaling)
+type LightDesc<'a> = (i32, i32, f64, &'a str, &'a str);
+
+// Adds lights to `cube_anim.fbx` saved from `app` in centimeters
+fn load_lights(app: &str, lights: &[LightDesc]) -> ufbx::SceneRoot {
+    let mut objects = String::new();
+    let mut connections = String::new();
+    for (ix, &(light_type, decay, intensity, extra, scaling)) in lights.iter().enumerate() {
+        let (attrib_id, model_id) = (800 + ix * 2, 801 + ix * 2);
+        objects += &format!(concat!(
+            "\tNodeAttribute: {}, \"NodeAttribute::light{}\", \"Light\" {{\n\t\tProperties70:  {{\n",
+            "\t\t\tP: \"LightType\", \"enum\", \"\", \"\",{}\n",
+            "\t\t\tP: \"DecayType\", \"enum\", \"\", \"\",{}\n",
+            "\t\t\tP: \"Intensity\", \"Number\", \"\", \"A\",{}\n{}",
+            "\t\t}}\n\t\tTypeFlags: \"Light\"\n\t}}\n",
+            "\tModel: {}, \"Model::light{}\", \"Light\" {{\n\t\tVersion: 232\n\t\tProperties70:  {{\n",
+            "\t\t\tP: \"Lcl Scaling\", \"Lcl Scaling\", \"\", \"A\",{}\n\t\t}}\n\t}}\n"),
+            attrib_id, ix, light_type, decay, intensity, extra, model_id, ix, scaling);
+        connections += &format!("\tC: \"OO\",{},0\n\tC: \"OO\",{},{}\n", model_id, attrib_id, model_id);
+    }
+    let source = cube_anim_source(&objects, &connections)
+        .replace("\"Maya\"", &format!("\"{}\"", app));
+    ufbx::load_memory(source.as_bytes(), ufbx::LoadOpts::default()).expect("expected to load scene")
+}
+
+#[test]
+fn light_units_blender() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    assert_eq!(ufbx::detect_light_convention(&scene), LightConvention::Blender);
+
+    // Default 1000 W point light
+    let lights = ufbx::photometric_lights(&scene, &LightUnitOpts::default());
+    assert_eq!(lights.len(), 1);
+    assert_eq!(lights[0].unit, PhotometricUnit::Lumen);
+    assert_near_rel(lights[0].value, 1000.0 * 683.0);
+    assert!(!lights[0].approximate);
+}
+
+#[test]
+fn light_units_maya() {
+    let scene = load_lights("Maya", &[
+        (0, 2, 100000.0, "", "1,1,1"),
+        (2, 0, 100.0, "\t\t\tP: \"InnerAngle\", \"Number\", \"\", \"A\",40\n\t\t\tP: \"OuterAngle\", \"Number\", \"\", \"A\",60\n", "1,1,1"),
+        (1, 0, 200.0, "", "1,1,1"),
+        (3, 2, 100.0, "", "2,3,1"),
+    ]);
+    assert_eq!(ufbx::detect_light_convention(&scene), LightConvention::Maya);
+    let lights = ufbx::photometric_lights(&scene, &LightUnitOpts::default());
+
+    // Quadratic decay in centimeters
+    assert_eq!(lights[0].unit, PhotometricUnit::Lumen);
+    assert_near_rel(lights[0].value, 1000.0 * 683.0 * 1e-4 * 4.0 * PI);
+    assert!(!lights[0].approximate);
+
+    // No decay matches the illuminance at the reference distance
+    assert_eq!(lights[1].unit, PhotometricUnit::Candela);
+    assert_near_rel(lights[1].value, 683.0);
+    assert!(lights[1].approximate);
+    assert_near_rel(lights[1].inner_cone_angle, 20f64.to_radians());
+    assert_near_rel(lights[1].outer_cone_angle, 30f64.to_radians());
+    let far = scene.lights[1].to_photometric(&scene, &LightUnitOpts { reference_distance: 2.0, ..Default::default() });
+    assert_near_rel(far.value, 683.0 * 4.0);
+
+    assert_eq!(lights[2].unit, PhotometricUnit::Lux);
+    assert_near_rel(lights[2].value, 2.0 * 683.0);
+    assert!(!lights[2].approximate);
+
+    // 2x3 scaled area light spans 4x6 centimeters
+    assert_eq!(lights[3].unit, PhotometricUnit::Nit);
+    assert_near_rel(lights[3].value, 683.0 * 1e-4 / (24.0 * 1e-4));
+}
+
+#[test]
+fn light_units_max() {
+    let scene = load_lights("3ds Max", &[
+        (0, 2, 150000.0, "", "1,1,1"),
+        (0, 0, 100.0, "", "1,1,1"),
+    ]);
+    assert_eq!(ufbx::detect_light_convention(&scene), LightConvention::Max);
+    let lights = ufbx::photometric_lights(&scene, &LightUnitOpts::default());
+
+    // Photometric lights store candela directly
+    assert_near_rel(lights[0].value, 150000.0 * 4.0 * PI);
+    assert!(!lights[0].approximate);
+    assert_near_rel(lights[1].value, 683.0 * 4.0 * PI);
+    assert!(lights[1].approximate);
+
+    // Forcing another convention and efficacy
+    let opts = LightUnitOpts { convention: Some(LightConvention::Blender), lumens_per_watt: 100.0, ..Default::default() };
+    let lights = ufbx::photometric_lights(&scene, &opts);
+    assert_near_rel(lights[0].value, 1500.0 * 100.0);
+    assert!(!lights[1].approximate);
+}
@@ -0,0 +1,240 @@
+use ufbx::{self, LimitSurface, LimitSurfaceOpts, MeshTopology, SubdivisionBoundary, Vec3};
+
+mod common;
+use common::{grid_obj, vec3_distance};
+
+fn corner_param(corner: usize, t: f64) -> (f64, f64) {
+    let params = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+    let (a, b) = (params[corner % 4], params[(corner + 1) % 4]);
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+// Vertex of `subdivided`, `level` subdivisions of `mesh`, at the dyadic `(u, v)` of a quad `face`
+fn subdivided_point(mesh: &ufbx::Mesh, subdivided: &ufbx::Mesh, level: usize, face: usize, u: f64, v: f64) -> Vec3 {
+    let (mut ix, mut u, mut v) = (mesh.faces[face].index_begin as usize, u, v);
+    for _ in 0..level {
+        let (corner, s, t) = match (u >= 0.5, v >= 0.5) {
+            (false, false) => (0, 2.0 * u, 2.0 * v),
+            (true, false) => (1, 2.0 * v, 2.0 - 2.0 * u),
+            (true, true) => (2, 2.0 - 2.0 * u, 2.0 - 2.0 * v),
+            (false, true) => (3, 2.0 - 2.0 * v, 2.0 * u),
+        };
+        ix = (ix + corner) * 4;
+        u = s;
+        v = t;
+    }
+    assert!(u == 0.0 && v == 0.0, "({}, {}) needs more than {} levels", u, v, level);
+    subdivided.vertices[subdivided.vertex_indices[ix] as usize]
+}
+
+// Richardson extrapolation of the subdivision vertices at `level` and `level + 1`,
+// away from extraordinary vertices they approach the limit as `4^-level`
+fn extrapolated_limit(mesh: &ufbx::Mesh, coarse: &ufbx::Mesh, fine: &ufbx::Mesh, level: usize, face: usize, u: f64, v: f64) -> Vec3 {
+    let a = subdivided_point(mesh, coarse, level, face, u, v);
+    let b = subdivided_point(mesh, fine, level + 1, face, u, v);
+    Vec3 { x: (4.0 * b.x - a.x) / 3.0, y: (4.0 * b.y - a.y) / 3.0, z: (4.0 * b.z - a.z) / 3.0 }
+}
+
+// `cube_anim.fbx` with every edge of the cube creased by `crease`
+fn load_creased_cube(crease: f64) -> ufbx::SceneRoot {
+    let creases = vec![crease.to_string(); 12].join(",");
+    let layer = format!(concat!(
+        "\t\tLayerElementEdgeCrease: 0 {{\n\t\t\tVersion: 101\n\t\t\tName: \"\"\n",
+        "\t\t\tMappingInformationType: \"ByEdge\"\n\t\t\tReferenceInformationType: \"Direct\"\n",
+        "\t\t\tEdgeCrease: *12 {{\n\t\t\t\ta: {}\n\t\t\t}}\n\t\t}}\n"), creases);
+    let normals = "\t\tLayerElementNormal: 0 {";
+    let source = common::cube_anim_source("", "").replacen(normals, &format!("{}{}", layer, normals), 1);
+    ufbx::load_memory(source.as_bytes(), ufbx::LoadOpts::default()).expect("expected to load scene")
+}
+
+#[test]
+fn limit_regular_grid() {
+    let opts = ufbx::LoadOpts {
+        file_format: ufbx::FileFormat::Obj,
+        ..Default::default()
+    };
+    let scene = ufbx::load_memory(grid_obj(6, |_, _| 0.0).as_bytes(), opts).expect("expected to load scene");
+    let mesh = &scene.meshes[0];
+    let limit = LimitSurface::new(mesh, &LimitSurfaceOpts::default()).expect("expected limit surface");
+
+    // B-splines reproduce the plane away from the boundary
+    for face in mesh.faces.iter().enumerate().map(|(ix, _)| ix) {
+        let first = mesh.vertices[mesh.vertex_indices[mesh.faces[face].index_begin as usize] as usize];
+        if first.x < 2.0 || first.x > 3.0 || first.y < 2.0 || first.y > 3.0 { continue }
+        for &(u, v) in &[(0.0, 0.0), (0.25, 0.75), (0.5, 0.5), (1.0, 0.3)] {
+            let p = limit.evaluate(face, u, v).unwrap();
+            assert!(vec3_distance(p.position, Vec3 { x: first.x + u, y: first.y + v, z: 0.0 }) < 1e-9);
+            assert!(vec3_distance(p.du, Vec3 { x: 1.0, y: 0.0, z: 0.0 }) < 1e-9);
+            assert!(vec3_distance(p.dv, Vec3 { x: 0.0, y: 1.0, z: 0.0 }) < 1e-9);
+            assert!(vec3_distance(p.normal, Vec3 { x: 0.0, y: 0.0, z: 1.0 }) < 1e-9);
+            assert!((p.uv.x - p.position.x / 6.0).abs() < 1e-9);
+            assert!((p.uv.y - p.position.y / 6.0).abs() < 1e-9);
+        }
+    }
+
+    // Corners stay in place with sharp corners and are pulled inwards without
+    let sharp = LimitSurface::new(mesh, &LimitSurfaceOpts { boundary: SubdivisionBoundary::SharpCorners, ..Default::default() }).unwrap();
+    let smooth = LimitSurface::new(mesh, &LimitSurfaceOpts { boundary: SubdivisionBoundary::SharpNone, ..Default::default() }).unwrap();
+    let corner = sharp.vertex_limit_positions()[0];
+    assert_eq!((corner.x, corner.y, corner.z), (0.0, 0.0, 0.0));
+    let corner = smooth.vertex_limit_positions()[0];
+    assert!(corner.x > 0.0 && corner.y > 0.0);
+}
+
+#[test]
+fn limit_cube_vertices() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let mesh = &scene.meshes[0];
+    let limit = LimitSurface::new(mesh, &LimitSurfaceOpts::default()).expect("expected limit surface");
+    let limits = limit.vertex_limit_positions();
+    assert_eq!(limits.len(), mesh.num_vertices);
+
+    // Vertex points of a deep subdivision converge towards the limit
+    let level = 7;
+    let subdivided = mesh.subdivide(level, ufbx::SubdivideOpts::default()).expect("expected to subdivide");
+    for (ix, &v) in mesh.vertex_indices.iter().enumerate() {
+        let deep = subdivided.vertices[subdivided.vertex_indices[ix << (2 * level)] as usize];
+        assert!(vec3_distance(limits[v as usize], deep) < 1e-3);
+
+        let face = (0..mesh.faces.len()).find(|&f| {
+            let face = mesh.faces[f];
+            ix >= face.index_begin as usize && ix < (face.index_begin + face.num_indices) as usize
+        }).unwrap();
+        let (u, v) = corner_param(ix - mesh.faces[face].index_begin as usize, 0.0);
+        assert!(vec3_distance(limit.evaluate(face, u, v).unwrap().position, limits[mesh.vertex_indices[ix] as usize]) < 1e-9);
+    }
+}
+
+#[test]
+fn limit_cube_watertight() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let mesh = &scene.meshes[0];
+    let topo = MeshTopology::new(mesh);
+    let limit = LimitSurface::new(mesh, &LimitSurfaceOpts::default()).expect("expected limit surface");
+
+    let center = limit.evaluate(0, 0.5, 0.5).unwrap();
+    for he in 0..topo.num_half_edges() {
+        let twin = topo.twin(he).expect("cube is closed");
+        let (face, twin_face) = (topo.face(he), topo.face(twin));
+        let corner = he - mesh.faces[face].index_begin as usize;
+        let twin_corner = twin - mesh.faces[twin_face].index_begin as usize;
+        for &t in &[0.1, 0.3, 0.5, 0.8] {
+            let (u, v) = corner_param(corner, t);
+            let (tu, tv) = corner_param(twin_corner, 1.0 - t);
+            let a = limit.evaluate(face, u, v).unwrap();
+            let b = limit.evaluate(twin_face, tu, tv).unwrap();
+            assert!(vec3_distance(a.position, b.position) < 1e-9);
+            assert!(vec3_distance(a.normal, b.normal) < 1e-6);
+        }
+    }
+
+    // The cube shrinks towards a sphere and the normal points outwards
+    let face_center = (0..4).fold(Vec3::default(), |sum, c| {
+        let p = mesh.vertices[mesh.vertex_indices[mesh.faces[0].index_begin as usize + c] as usize];
+        Vec3 { x: sum.x + p.x * 0.25, y: sum.y + p.y * 0.25, z: sum.z + p.z * 0.25 }
+    });
+    let radius = vec3_distance(face_center, Vec3::default());
+    assert!(vec3_distance(center.position, Vec3::default()) < radius);
+    let cos = (center.normal.x * face_center.x + center.normal.y * face_center.y + center.normal.z * face_center.z) / radius;
+    assert!(cos > 0.99);
+}
+
+#[test]
+fn limit_sharp_interior() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let mesh = &scene.meshes[0];
+    let opts = LimitSurfaceOpts { boundary: SubdivisionBoundary::SharpInterior, ..Default::default() };
+    let limit = LimitSurface::new(mesh, &opts).expect("expected limit surface");
+
+    // Everything is sharp so the limit is the bilinear control mesh
+    for (v, p) in limit.vertex_limit_positions().iter().enumerate() {
+        assert!(vec3_distance(*p, mesh.vertices[v]) < 1e-9);
+    }
+    let face = mesh.faces[0];
+    let corners: Vec<Vec3> = (0..4).map(|c| mesh.vertices[mesh.vertex_indices[face.index_begin as usize + c] as usize]).collect();
+    let p = limit.evaluate(0, 0.25, 0.5).unwrap().position;
+    let expected = |k: fn(&Vec3) -> f64| {
+        let a = k(&corners[0]) * 0.75 + k(&corners[1]) * 0.25;
+        let b = k(&corners[3]) * 0.75 + k(&corners[2]) * 0.25;
+        (a + b) * 0.5
+    };
+    assert!((p.x - expected(|p| p.x)).abs() < 1e-9);
+    assert!((p.y - expected(|p| p.y)).abs() < 1e-9);
+    assert!((p.z - expected(|p| p.z)).abs() < 1e-9);
+}
+
+#[test]
+fn limit_cube_extraordinary() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let mesh = &scene.meshes[0];
+    let limit = LimitSurface::new(mesh, &LimitSurfaceOpts::default()).expect("expected limit surface");
+    let coarse = mesh.subdivide(6, ufbx::SubdivideOpts::default()).expect("expected to subdivide");
+    let fine = mesh.subdivide(7, ufbx::SubdivideOpts::default()).expect("expected to subdivide");
+
+    // Every corner of the cube has valence 3, the points closest to them
+    // are evaluated deeper than `max_level`
+    let h = 1e-5;
+    for face in 0..mesh.faces.len() {
+        for &(u, v) in &[(0.125, 0.0625), (0.03125, 0.25), (0.75, 0.03125), (0.5, 0.5)] {
+            let p = limit.evaluate(face, u, v).unwrap();
+            let reference = extrapolated_limit(mesh, &coarse, &fine, 6, face, u, v);
+            assert!(vec3_distance(p.position, reference) < 1e-6, "{:?} != {:?}", p.position, reference);
+
+            let (a, b) = (limit.evaluate(face, u - h, v).unwrap().position, limit.evaluate(face, u + h, v).unwrap().position);
+            let du = Vec3 { x: (b.x - a.x) / (2.0 * h), y: (b.y - a.y) / (2.0 * h), z: (b.z - a.z) / (2.0 * h) };
+            assert!(vec3_distance(p.du, du) < 1e-4, "{:?} != {:?}", p.du, du);
+        }
+    }
+}
+
+#[test]
+fn limit_fractional_crease() {
+    let mut corners = Vec::new();
+    for &crease in &[0.05, 0.1, 0.35, 0.4] {
+        let scene = load_creased_cube(crease);
+        let mesh = &scene.meshes[0];
+        assert_eq!(mesh.edge_crease.len(), mesh.num_edges);
+
+        // Creases decay over several levels, which happens locally past `max_level`
+        let limit = LimitSurface::new(mesh, &LimitSurfaceOpts { max_level: 1, ..Default::default() })
+            .expect("expected limit surface");
+        let coarse = mesh.subdivide(6, ufbx::SubdivideOpts::default()).expect("expected to subdivide");
+        let fine = mesh.subdivide(7, ufbx::SubdivideOpts::default()).expect("expected to subdivide");
+        for &(u, v) in &[(0.125, 0.0625), (0.5, 0.0625), (0.5, 0.5)] {
+            let p = limit.evaluate(0, u, v).unwrap().position;
+            let reference = extrapolated_limit(mesh, &coarse, &fine, 6, 0, u, v);
+            assert!(vec3_distance(p, reference) < 1e-6, "{:?} != {:?}", p, reference);
+        }
+        corners.push(limit.vertex_limit_positions()[0]);
+    }
+
+    // A crease of 0.05 is not rounded up to fully sharp
+    assert!(vec3_distance(corners[0], corners[1]) > 1e-3);
+    assert!(vec3_distance(corners[2], corners[3]) > 1e-3);
+}
+
+#[test]
+fn limit_invalid_arguments() {
+    let opts = ufbx::LoadOpts {
+        file_format: ufbx::FileFormat::Obj,
+        ..Default::default()
+    };
+    let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nf 1 2 3 4\nf 2 5 3\n";
+    let scene = ufbx::load_memory(source.as_bytes(), opts).expect("expected to load scene");
+    let mesh = &scene.meshes[0];
+
+    // `max_level` is clamped to 1
+    let limit = LimitSurface::new(mesh, &LimitSurfaceOpts { max_level: 0, ..Default::default() })
+        .expect("expected limit surface");
+    assert!(limit.evaluate(0, 0.5, 0.5).is_some());
+
+    assert!(limit.evaluate(1, 0.5, 0.5).is_none());
+    assert!(limit.evaluate(2, 0.5, 0.5).is_none());
+    assert!(limit.evaluate_corner(1, 2, 0.5, 0.5).is_some());
+    assert!(limit.evaluate_corner(1, 3, 0.5, 0.5).is_none());
+    assert!(limit.evaluate_corner(2, 0, 0.5, 0.5).is_none());
+}
@@ -0,0 +1,196 @@
+use ufbx;
+
+const MATERIALX_FBX: &str = r#"; FBX 7.4.0 project file
+FBXHeaderExtension:  {
+	FBXHeaderVersion: 1003
+	FBXVersion: 7400
+}
+Objects:  {
+	Material: 1000, "Material::mat", "" {
+		ShadingModel: "unknown"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|ClassIDa", "int", "Integer", "",-804315648
+			P: "3dsMax|ClassIDb", "int", "Integer", "",31173939
+			P: "3dsMax|main", "Compound", "", ""
+			P: "3dsMax|main|basecolor", "ColorAndAlpha", "", "A",0.5,0.25,1,1
+			P: "3dsMax|main|base_color_map", "Reference", "", "A"
+			P: "3dsMax|main|useGlossiness", "int", "Integer", "",1
+			P: "3dsMax|main|glossiness", "Float", "", "A",0.75
+			P: "3dsMax|main|ao_map", "Reference", "", "A"
+			P: "3dsMax|main|norm_map", "Reference", "", "A"
+		}
+	}
+	Texture: 2000, "Texture::select", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "MULTIOUTPUT_TO_OSLMap"
+			P: "3dsMax|parameters", "Compound", "", ""
+			P: "3dsMax|parameters|sourceMap", "Reference", "", "A"
+			P: "3dsMax|parameters|outputChannelIndex", "int", "Integer", "",2
+		}
+	}
+	Texture: 3000, "Texture::noise", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "OSLMap"
+			P: "3dsMax|params", "Compound", "", ""
+			P: "3dsMax|params|OSLShaderName", "KString", "", "", "Noise"
+			P: "3dsMax|parameters", "Compound", "", ""
+			P: "3dsMax|parameters|scale", "float", "Number", "",2.5
+			P: "3dsMax|parameters|color", "ColorRGB", "Color", "",1,0,0
+			P: "3dsMax|parameters|color_map", "Reference", "", "A"
+		}
+	}
+	Texture: 5000, "Texture::bump", "" {
+		Type: "TextureVideoClip"
+		FileName: "C:\textures\bump & normal.png"
+		RelativeFilename: "textures\bump & normal.png"
+		Properties70:  {
+			P: "UVSet", "KString", "", "", "detail"
+			P: "Translation", "Vector", "", "A",0.5,0.25,0
+			P: "Scaling", "Vector", "", "A",2,2,1
+		}
+	}
+	Texture: 6000, "Texture::mask", "" {
+		Type: "TextureVideoClip"
+		FileName: "mask.png"
+		RelativeFilename: "mask.png"
+	}
+}
+Connections:  {
+	C: "OP",2000,1000, "3dsMax|main|base_color_map"
+	C: "OP",3000,2000, "3dsMax|parameters|sourceMap"
+	C: "OP",6000,3000, "3dsMax|parameters|color_map"
+	C: "OP",5000,1000, "3dsMax|main|norm_map"
+	C: "OP",6000,1000, "3dsMax|main|ao_map"
+}
+"#;
+
+fn load_material(scene: &ufbx::Scene) -> &ufbx::Material {
+    scene.materials.iter().find(|m| m.element.name == "mat").expect("expected to find 'mat'")
+}
+
+#[test]
+fn materialx_standard_surface() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let mtlx = ufbx::export_materialx(&scene.materials, &ufbx::MaterialXOpts::default());
+
+    assert!(mtlx.starts_with("<?xml version=\"1.0\"?>\n<materialx version=\"1.38\">\n"));
+    assert!(mtlx.ends_with("</materialx>\n"));
+    assert!(mtlx.contains("<standard_surface name=\"Material_surface\" type=\"surfaceshader\">"));
+    assert!(mtlx.contains("<input name=\"specular_roughness\" type=\"float\" value=\"0.5\" />"));
+    assert!(mtlx.contains("<surfacematerial name=\"Material\" type=\"material\">"));
+    assert!(mtlx.contains("<input name=\"surfaceshader\" type=\"surfaceshader\" nodename=\"Material_surface\" />"));
+}
+
+#[test]
+fn materialx_textures_and_shaders() {
+    let scene = ufbx::load_memory(MATERIALX_FBX.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let material = load_material(&scene);
+    let opts = ufbx::MaterialXOpts { surface: ufbx::MaterialXSurface::OpenPbrSurface, ..Default::default() };
+    let mtlx = ufbx::export_materialx(std::iter::once(material), &opts);
+
+    assert!(mtlx.contains("<materialx version=\"1.39\">"));
+    assert!(mtlx.contains("<open_pbr_surface name=\"mat_surface\" type=\"surfaceshader\">"));
+
+    // Shader passthrough with the select-output index
+    assert!(mtlx.contains("<Noise name=\"noise\" type=\"color3\">"));
+    assert!(mtlx.contains("<input name=\"scale\" type=\"float\" value=\"2.5\" />"));
+    assert!(mtlx.contains("<input name=\"color\" type=\"color3\" nodename=\"mask_image\" />"));
+    assert!(mtlx.contains("<input name=\"base_color\" type=\"color3\" nodename=\"noise\" output=\"out2\" />"));
+
+    // Normal map with UV set and transform
+    assert!(mtlx.contains("<input name=\"file\" type=\"filename\" value=\"textures/bump &amp; normal.png\" />"));
+    assert!(mtlx.contains("<input name=\"geomprop\" type=\"string\" value=\"detail\" />"));
+    assert!(mtlx.contains("<place2d name=\"bump_place2d\" type=\"vector2\">"));
+    assert!(mtlx.contains("<input name=\"offset\" type=\"vector2\" value=\"0.5, 0.25\" />"));
+    assert!(mtlx.contains("<input name=\"scale\" type=\"vector2\" value=\"2, 2\" />"));
+    assert!(mtlx.contains("<normalmap name=\"mat_geometry_normal_normalmap\" type=\"vector3\">"));
+    assert!(mtlx.contains("<input name=\"geometry_normal\" type=\"vector3\" nodename=\"mat_geometry_normal_normalmap\" />"));
+
+    // Glossiness is inverted to roughness
+    assert!(mtlx.contains("<input name=\"specular_roughness\" type=\"float\" value=\"0.25\" />"));
+
+    // Ambient occlusion has no surface equivalent
+    assert!(mtlx.contains("<!-- ufbx: material 'mat' input 'ambient_occlusion' (texture 'mask') has no open_pbr_surface equivalent -->"));
+}
+
+#[test]
+fn materialx_no_passthrough() {
+    let scene = ufbx::load_memory(MATERIALX_FBX.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let material = load_material(&scene);
+    let opts = ufbx::MaterialXOpts { passthrough_shaders: false, ..Default::default() };
+    let mtlx = ufbx::export_materialx(std::iter::once(material), &opts);
+
+    assert!(!mtlx.contains("<Noise"));
+    assert!(mtlx.contains("<!-- ufbx: material 'mat' input 'base_color' uses unmappable Shader texture 'select' -->"));
+}
+
+#[test]
+fn materialx_shader_cycle() {
+    // `noise` and `noise2` use each other as the color map
+    let noise2 = concat!(
+        "\tTexture: 3001, \"Texture::noise2\", \"\" {\n\t\tType: \"TextureVideoClip\"\n\t\tProperties70:  {\n",
+        "\t\t\tP: \"3dsMax\", \"Compound\", \"\", \"\"\n",
+        "\t\t\tP: \"3dsMax|MaxTexture\", \"KString\", \"\", \"\", \"OSLMap\"\n",
+        "\t\t\tP: \"3dsMax|params\", \"Compound\", \"\", \"\"\n",
+        "\t\t\tP: \"3dsMax|params|OSLShaderName\", \"KString\", \"\", \"\", \"Noise\"\n",
+        "\t\t\tP: \"3dsMax|parameters\", \"Compound\", \"\", \"\"\n",
+        "\t\t\tP: \"3dsMax|parameters|color\", \"ColorRGB\", \"Color\", \"\",0,1,0\n",
+        "\t\t\tP: \"3dsMax|parameters|color_map\", \"Reference\", \"\", \"A\"\n",
+        "\t\t}\n\t}\n");
+    let source = MATERIALX_FBX
+        .replace("\tTexture: 5000,", &format!("{}\tTexture: 5000,", noise2))
+        .replace("\tC: \"OP\",6000,3000, \"3dsMax|parameters|color_map\"\n", concat!(
+            "\tC: \"OP\",3001,3000, \"3dsMax|parameters|color_map\"\n",
+            "\tC: \"OP\",3000,3001, \"3dsMax|parameters|color_map\"\n"));
+    let scene = ufbx::load_memory(source.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let mtlx = ufbx::export_materialx(std::iter::once(load_material(&scene)), &ufbx::MaterialXOpts::default());
+
+    // The back edge falls back to the color value of `noise2`
+    assert!(mtlx.contains("<!-- ufbx: noise is part of a shader cycle, the connection is cut -->"));
+    assert!(mtlx.contains("<Noise name=\"noise2\" type=\"color3\">\n    <input name=\"color\" type=\"color3\" value=\"0, 1, 0\" />\n  </Noise>"));
+    assert!(mtlx.contains("<input name=\"color\" type=\"color3\" nodename=\"noise2\" />"));
+    assert!(mtlx.contains("<input name=\"base_color\" type=\"color3\" nodename=\"noise\" output=\"out2\" />"));
+}
+
+#[test]
+fn materialx_deep_shader_chain() {
+    // `noise` is fed by a chain of nested shaders `deep0 <- deep1 <- ...`
+    let depth = 5000;
+    let mut textures = String::new();
+    let mut connections = String::from("\tC: \"OP\",7000,3000, \"3dsMax|parameters|color_map\"\n");
+    for ix in 0..depth {
+        textures += &format!(concat!(
+            "\tTexture: {}, \"Texture::deep{}\", \"\" {{\n\t\tType: \"TextureVideoClip\"\n\t\tProperties70:  {{\n",
+            "\t\t\tP: \"3dsMax\", \"Compound\", \"\", \"\"\n",
+            "\t\t\tP: \"3dsMax|MaxTexture\", \"KString\", \"\", \"\", \"OSLMap\"\n",
+            "\t\t\tP: \"3dsMax|params\", \"Compound\", \"\", \"\"\n",
+            "\t\t\tP: \"3dsMax|params|OSLShaderName\", \"KString\", \"\", \"\", \"Noise\"\n",
+            "\t\t\tP: \"3dsMax|parameters\", \"Compound\", \"\", \"\"\n",
+            "\t\t\tP: \"3dsMax|parameters|color\", \"ColorRGB\", \"Color\", \"\",0,1,0\n",
+            "\t\t\tP: \"3dsMax|parameters|color_map\", \"Reference\", \"\", \"A\"\n",
+            "\t\t}}\n\t}}\n"), 7000 + ix, ix);
+        if ix + 1 < depth {
+            connections += &format!("\tC: \"OP\",{},{}, \"3dsMax|parameters|color_map\"\n", 7001 + ix, 7000 + ix);
+        }
+    }
+    let source = MATERIALX_FBX
+        .replace("\tTexture: 5000,", &format!("{}\tTexture: 5000,", textures))
+        .replace("\tC: \"OP\",6000,3000, \"3dsMax|parameters|color_map\"\n", &connections);
+    let scene = ufbx::load_memory(source.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let mtlx = ufbx::export_materialx(std::iter::once(load_material(&scene)), &ufbx::MaterialXOpts::default());
+
+    assert!(mtlx.contains("<Noise name=\"deep4999\" type=\"color3\">\n    <input name=\"color\" type=\"color3\" value=\"0, 1, 0\" />\n  </Noise>"));
+    assert!(mtlx.contains("<input name=\"color\" type=\"color3\" nodename=\"deep1\" />"));
+    assert!(mtlx.contains("<input name=\"color\" type=\"color3\" nodename=\"deep0\" />"));
+    assert!(mtlx.find("name=\"deep4999\"") < mtlx.find("name=\"deep0\""));
+}
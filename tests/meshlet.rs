@@ -0,0 +1,83 @@
+use ufbx::{self, MeshletOpts};
+
+mod common;
+use common::load_grid;
+
+#[test]
+fn meshlets_cover_parts() {
+    let lod = load_grid(32);
+    let opts = MeshletOpts { max_vertices: 64, max_triangles: 96 };
+    let parts = lod.build_meshlets(&opts);
+    assert_eq!(parts.len(), 2);
+
+    for (part, meshlet_part) in lod.parts.iter().zip(&parts) {
+        assert_eq!(meshlet_part.index, part.index);
+
+        let mut expected: Vec<[u32; 3]> = lod.indices[part.index_begin..part.index_begin + part.num_triangles * 3]
+            .chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+        let mut found = Vec::new();
+        for meshlet in &meshlet_part.meshlets {
+            assert!(meshlet.vertices.len() <= 64);
+            assert!(meshlet.triangles.len() <= 96);
+            for t in &meshlet.triangles {
+                found.push(t.map(|c| meshlet.vertices[c as usize]));
+            }
+            for &v in &meshlet.vertices {
+                let p = lod.vertices[v as usize].position;
+                let d = ((p.x - meshlet.center.x).powi(2) + (p.y - meshlet.center.y).powi(2) + (p.z - meshlet.center.z).powi(2)).sqrt();
+                assert!(d <= meshlet.radius + 1e-9);
+            }
+        }
+        expected.sort();
+        found.sort();
+        assert_eq!(found, expected);
+
+        // Mostly full meshlets, 1024 triangles need at least 11
+        assert!(meshlet_part.meshlets.len() <= 20, "got {} meshlets", meshlet_part.meshlets.len());
+    }
+}
+
+#[test]
+fn meshlets_deterministic() {
+    let lod = load_grid(16);
+    let a = lod.build_meshlets(&MeshletOpts::default());
+    let b = lod.build_meshlets(&MeshletOpts::default());
+    assert_eq!(a.len(), b.len());
+    for (pa, pb) in a.iter().zip(&b) {
+        assert_eq!(pa.meshlets.len(), pb.meshlets.len());
+        for (ma, mb) in pa.meshlets.iter().zip(&pb.meshlets) {
+            assert_eq!(ma.vertices, mb.vertices);
+            assert_eq!(ma.triangles, mb.triangles);
+        }
+    }
+}
+
+#[test]
+fn meshlet_normal_cone() {
+    let lod = load_grid(4);
+    let parts = lod.build_meshlets(&MeshletOpts::default());
+    let meshlet = &parts[0].meshlets[0];
+    assert!((meshlet.cone_axis.z - 1.0).abs() < 1e-9);
+    assert!(meshlet.cone_cutoff < 1e-6);
+
+    let below = ufbx::Vec3 { x: 1.0, y: 2.0, z: -10.0 };
+    let above = ufbx::Vec3 { x: 1.0, y: 2.0, z: 10.0 };
+    assert!(meshlet.is_backfacing(below));
+    assert!(!meshlet.is_backfacing(above));
+}
+
+#[test]
+fn meshlet_opts_clamped() {
+    let lod = load_grid(8);
+    for &(max_vertices, max_triangles) in &[(0, 0), (1000, 1000), (2, 1)] {
+        let parts = lod.build_meshlets(&MeshletOpts { max_vertices, max_triangles });
+        for (part, meshlet_part) in lod.parts.iter().zip(&parts) {
+            let num_triangles: usize = meshlet_part.meshlets.iter().map(|m| m.triangles.len()).sum();
+            assert_eq!(num_triangles, part.num_triangles);
+            for meshlet in &meshlet_part.meshlets {
+                assert!(meshlet.vertices.len() <= max_vertices.clamp(3, 256));
+                assert!(meshlet.triangles.len() <= max_triangles.max(1));
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use ufbx;
+
+#[test]
+fn metallic_roughness_phong() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let material = scene.materials.iter().find(|m| m.element.name == "Material")
+        .expect("expected to find 'Material'");
+
+    let mr = material.to_metallic_roughness();
+    assert!((mr.base_color_factor.x - 0.8).abs() < 0.001);
+    assert_eq!(mr.base_color_factor.w, 1.0);
+    assert_eq!(mr.alpha_mode, ufbx::AlphaMode::Opaque);
+    assert_eq!(mr.metallic_factor, 0.0);
+    assert!((mr.roughness_factor - 0.5).abs() < 0.001);
+    assert_eq!(mr.emissive_strength, 1.0);
+    assert!(mr.base_color_texture.is_none());
+    assert!(!mr.is_packed());
+    assert!(mr.dropped.is_empty(), "unexpected dropped features: {:?}", mr.dropped);
+}
+
+#[test]
+fn metallic_roughness_lambert() {
+    let scene = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let material = scene.materials.iter().find(|m| m.element.name == "lambert1")
+        .expect("expected to find 'lambert1'");
+
+    let mr = material.to_metallic_roughness();
+    assert!((mr.base_color_factor.x - 0.7407 * 0.8).abs() < 0.001);
+    assert_eq!(mr.roughness_factor, 1.0);
+    assert_eq!(mr.alpha_mode, ufbx::AlphaMode::Opaque);
+}
+
+#[test]
+fn metallic_roughness_emissive() {
+    let scene = ufbx::load_file("tests/data/legacy_blob.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let material = scene.materials.iter().find(|m| m.element.name == "Left")
+        .expect("expected to find 'Left'");
+
+    let mr = material.to_metallic_roughness();
+    assert!((mr.emissive_factor.x - 0.8235).abs() < 0.001);
+    assert_eq!(mr.emissive_factor.y, 0.0);
+    assert_eq!(mr.emissive_strength, 1.0);
+}
+
+const SPEC_GLOSS_FBX: &str = r#"; FBX 7.4.0 project file
+FBXHeaderExtension:  {
+	FBXHeaderVersion: 1003
+	FBXVersion: 7400
+}
+Objects:  {
+	Material: 1000, "Material::specgloss", "" {
+		ShadingModel: "unknown"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|ClassIDa", "int", "Integer", "",-804315648
+			P: "3dsMax|ClassIDb", "int", "Integer", "",31173939
+			P: "3dsMax|main", "Compound", "", ""
+			P: "3dsMax|main|useGlossiness", "int", "Integer", "",1
+			P: "3dsMax|main|glossiness", "Float", "", "A",0.75
+		}
+	}
+}
+"#;
+
+#[test]
+fn metallic_roughness_glossiness() {
+    let scene = ufbx::load_memory(SPEC_GLOSS_FBX.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let material = &scene.materials[0];
+    assert_eq!(material.shader_type, ufbx::ShaderType::E3DsMaxPbrSpecGloss);
+
+    let mr = material.to_metallic_roughness();
+    assert!(mr.roughness_from_glossiness);
+    assert!((mr.roughness_factor - 0.25).abs() < 0.001);
+    assert!(mr.dropped.contains(&ufbx::DroppedFeature::SpecularGlossinessWorkflow));
+}
+
+#[test]
+fn metallic_roughness_pack_textures() {
+    let scene = ufbx::load_memory(SPEC_GLOSS_FBX.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let mut mr = scene.materials[0].to_metallic_roughness();
+
+    // RGB metalness read from red, grayscale glossiness
+    let metal = [10, 0, 0, 20, 0, 0];
+    let gloss = [200, 100];
+    let metallic = ufbx::ChannelImage { pixels: &metal, components: 3, channel: 0 };
+    let roughness = ufbx::ChannelImage { pixels: &gloss, components: 1, channel: 0 };
+
+    let packed = mr.pack_textures(2, 1, Some(metallic), Some(roughness)).expect("expected packed image");
+    assert_eq!(packed, [255, 55, 10, 255, 255, 155, 20, 255]);
+    let packed = mr.pack_textures(2, 1, Some(metallic), None).expect("expected packed image");
+    assert_eq!(packed, [255, 255, 10, 255, 255, 255, 20, 255]);
+
+    mr.roughness_from_glossiness = false;
+    let packed = mr.pack_textures(2, 1, None, Some(roughness)).expect("expected packed image");
+    assert_eq!(packed, [255, 200, 255, 255, 255, 100, 255, 255]);
+
+    assert!(mr.pack_textures(3, 1, Some(metallic), None).is_none());
+    assert!(mr.pack_textures(2, 1, None, Some(ufbx::ChannelImage { channel: 1, ..roughness })).is_none());
+}
@@ -0,0 +1,87 @@
+use ufbx::{self, LodMesh, OptimizeOpts};
+
+mod common;
+use common::{grid_obj, load_obj_lod};
+
+fn load_grid(n: usize) -> LodMesh {
+    load_obj_lod(&grid_obj(n, |x, y| ((x * y) % 3) as f64 * 0.1))
+}
+
+// Deterministic Fisher-Yates shuffle of the triangles in `indices`
+fn shuffle_triangles(indices: &mut [u32], seed: u64) {
+    let mut state = seed;
+    let num_tris = indices.len() / 3;
+    for i in (1..num_tris).rev() {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let j = (state >> 33) as usize % (i + 1);
+        for c in 0..3 {
+            indices.swap(i * 3 + c, j * 3 + c);
+        }
+    }
+}
+
+fn sorted_triangles(lod: &LodMesh, part: &ufbx::LodPart) -> Vec<[(i64, i64, i64); 3]> {
+    let mut tris: Vec<[(i64, i64, i64); 3]> = lod.indices[part.index_begin..part.index_begin + part.num_triangles * 3]
+        .chunks_exact(3)
+        .map(|t| {
+            let mut tri = [0, 1, 2].map(|c| {
+                let p = lod.vertices[t[c] as usize].position;
+                ((p.x * 10.0) as i64, (p.y * 10.0) as i64, (p.z * 10.0) as i64)
+            });
+            // Rotate the smallest corner first to keep the winding
+            let min = (0..3).min_by_key(|&c| tri[c]).unwrap();
+            tri.rotate_left(min);
+            tri
+        })
+        .collect();
+    tris.sort();
+    tris
+}
+
+#[test]
+fn optimize_parts() {
+    let mut lod = load_grid(32);
+    for part in lod.parts.clone() {
+        shuffle_triangles(&mut lod.indices[part.index_begin..part.index_begin + part.num_triangles * 3], 1);
+    }
+    let reference = lod.clone();
+
+    let stats = lod.optimize(&OptimizeOpts::default());
+    assert_eq!(stats.len(), 2);
+    for (part, stat) in lod.parts.iter().zip(&stats) {
+        assert_eq!(stat.index, part.index);
+        assert!(stat.before.acmr > 1.5, "shuffled ACMR {}", stat.before.acmr);
+        assert!(stat.after.acmr < 0.8, "optimized ACMR {}", stat.after.acmr);
+        assert!(stat.after.atvr < stat.before.atvr);
+    }
+
+    assert_eq!(lod.vertices.len(), reference.vertices.len());
+    for (part, ref_part) in lod.parts.iter().zip(&reference.parts) {
+        assert_eq!(sorted_triangles(&lod, part), sorted_triangles(&reference, ref_part));
+    }
+
+    // Vertices are stored in the order they are first used
+    let mut next = 0;
+    for &ix in &lod.indices {
+        assert!(ix <= next);
+        if ix == next {
+            next += 1;
+        }
+    }
+}
+
+#[test]
+fn vertex_fetch_remap() {
+    let mut indices = vec![4, 2, 0, 2, 4, 5];
+    let remap = ufbx::optimize_vertex_fetch(&mut indices, 6);
+    assert_eq!(indices, vec![0, 1, 2, 1, 0, 3]);
+    assert_eq!(remap, vec![2, u32::MAX, 1, u32::MAX, 0, 3]);
+
+    let vertices = vec!["a", "b", "c", "d", "e", "f"];
+    assert_eq!(ufbx::remap_vertex_buffer(&vertices, &remap), vec!["e", "c", "a", "f"]);
+
+    let stats = ufbx::analyze_vertex_cache(&indices, 4, 16);
+    assert_eq!(stats.num_misses, 4);
+    assert_eq!(stats.acmr, 2.0);
+    assert_eq!(stats.atvr, 1.0);
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::PathBuf;
+use ufbx;
+
+mod common;
+use common::temp_dir;
+
+#[test]
+fn resolve_relative_root() {
+    let root = temp_dir("resolve-root");
+    fs::create_dir_all(root.join("textures")).unwrap();
+    fs::write(root.join("textures").join("Diffuse.png"), b"png").unwrap();
+
+    let resolver = ufbx::TextureResolver {
+        relative_root: Some(root.clone()),
+        ..Default::default()
+    };
+
+    let res = resolver.resolve_filenames("C:\\missing\\Diffuse.png", "textures\\Diffuse.png", "");
+    assert_eq!(res.path, Some(root.join("textures").join("Diffuse.png")));
+    assert_eq!(res.candidates[0], PathBuf::from("C:/missing/Diffuse.png"));
+
+    let res = resolver.resolve_filenames("", "TEXTURES\\diffuse.PNG", "");
+    assert_eq!(res.path, Some(root.join("textures").join("Diffuse.png")));
+
+    let strict = ufbx::TextureResolver { case_insensitive: false, ..resolver };
+    let res = strict.resolve_filenames("", "TEXTURES\\diffuse.PNG", "");
+    assert!(!res.is_resolved());
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn resolve_search_paths() {
+    let root = temp_dir("resolve-search");
+    let search = root.join("search");
+    fs::create_dir_all(&search).unwrap();
+    fs::write(search.join("wood.jpg"), b"jpg").unwrap();
+
+    let resolver = ufbx::TextureResolver {
+        relative_root: Some(root.join("scene")),
+        search_paths: vec![search.clone()],
+        ..Default::default()
+    };
+
+    let res = resolver.resolve_filenames("D:/work/assets/wood.jpg", "../assets/wood.jpg", "");
+    assert_eq!(res.path, Some(search.join("wood.jpg")));
+
+    let res = resolver.resolve_filenames("D:/work/assets/stone.jpg", "../assets/stone.jpg", "");
+    assert!(!res.is_resolved());
+    assert!(res.candidates.contains(&PathBuf::from("D:/work/assets/stone.jpg")));
+    assert!(res.candidates.contains(&root.join("scene").join("../assets/stone.jpg")));
+    assert!(res.candidates.contains(&search.join("stone.jpg")));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn resolve_case_insensitive_order() {
+    // Each case variant lives in its own directory so that this also works on
+    // case-insensitive filesystems, the files are told apart by their contents
+    let root = temp_dir("resolve-case");
+    let variants = ["tex.png", "Tex.png", "TEX.png"];
+    for (ix, name) in variants.iter().enumerate() {
+        let dir = root.join(format!("dir{}", ix));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(name), name.as_bytes()).unwrap();
+    }
+
+    for (ix, name) in variants.iter().enumerate() {
+        let resolver = ufbx::TextureResolver { relative_root: Some(root.join(format!("dir{}", ix))), ..Default::default() };
+        let res = resolver.resolve_filenames("", "tEx.PNG", "");
+        let path = res.path.expect("expected to resolve");
+        assert_eq!(fs::read(&path).unwrap(), name.as_bytes());
+    }
+
+    // Variants sharing a directory are picked by sorted name, an exact match wins.
+    // This can only be set up on a case-sensitive filesystem.
+    let shared = root.join("shared");
+    fs::create_dir_all(&shared).unwrap();
+    for name in &variants {
+        fs::write(shared.join(name), name.as_bytes()).unwrap();
+    }
+    if fs::read_dir(&shared).unwrap().count() == variants.len() {
+        let resolver = ufbx::TextureResolver { relative_root: Some(shared.clone()), ..Default::default() };
+        let res = resolver.resolve_filenames("", "tEx.PNG", "");
+        assert_eq!(res.path, Some(shared.join("TEX.png")));
+        let res = resolver.resolve_filenames("", "tex.png", "");
+        assert_eq!(res.path, Some(shared.join("tex.png")));
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn resolve_scene_textures() {
+    let root = temp_dir("resolve-scene");
+    let path = root.join("scene.fbx");
+    fs::write(&path, common::textured_cube_source("C:/missing/diffuse.png", "textures\\diffuse.png")).unwrap();
+    let scene = ufbx::load_file(path.to_str().unwrap(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let resolver = ufbx::TextureResolver::for_scene(&scene);
+
+    let unresolved = resolver.unresolved(&scene);
+    assert_eq!(unresolved.len(), 1);
+    assert_eq!(unresolved[0].0.element.element_id, scene.textures[0].element.element_id);
+    assert!(unresolved[0].1.candidates.contains(&root.join("textures").join("diffuse.png")));
+
+    fs::create_dir_all(root.join("Textures")).unwrap();
+    fs::write(root.join("Textures").join("Diffuse.PNG"), b"png").unwrap();
+    assert!(resolver.unresolved(&scene).is_empty());
+    let res = resolver.resolve_texture(&scene.textures[0]);
+    assert_eq!(res.path, Some(root.join("Textures").join("Diffuse.PNG")));
+
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,207 @@
+use ufbx::{self, BoneMap, HumanoidSlot, RetargetOpts, Retargeter, Vec3};
+
+mod common;
+use common::{assert_near_vec3, load_cube_anim_with, node_index, translation};
+
+struct Bone {
+    name: &'static str,
+    parent: Option<usize>,
+    translation: (f64, f64, f64),
+    rotation: (f64, f64, f64),
+    // Linked character slot and world translation in the bind pose
+    slot: Option<&'static str>,
+    bind: Option<(f64, f64, f64)>,
+}
+
+fn bone(name: &'static str, parent: Option<usize>, translation: (f64, f64, f64)) -> Bone {
+    Bone { name, parent, translation, rotation: (0.0, 0.0, 0.0), slot: None, bind: None }
+}
+
+// Adds a skeleton to `cube_anim.fbx` with an optional bind pose and character
+fn load_skeleton(bones: &[Bone]) -> ufbx::SceneRoot {
+    let mut objects = String::new();
+    let mut connections = String::new();
+    for (ix, b) in bones.iter().enumerate() {
+        let (tx, ty, tz) = b.translation;
+        let (rx, ry, rz) = b.rotation;
+        objects += &format!(concat!(
+            "\tModel: {}, \"Model::{}\", \"LimbNode\" {{\n\t\tVersion: 232\n\t\tProperties70:  {{\n",
+            "\t\t\tP: \"Lcl Translation\", \"Lcl Translation\", \"\", \"A\",{},{},{}\n",
+            "\t\t\tP: \"Lcl Rotation\", \"Lcl Rotation\", \"\", \"A\",{},{},{}\n\t\t}}\n\t}}\n"),
+            900 + ix, b.name, tx, ty, tz, rx, ry, rz);
+        connections += &format!("\tC: \"OO\",{},{}\n", 900 + ix, b.parent.map_or(0, |p| 900 + p));
+    }
+
+    let pose_nodes: Vec<String> = bones.iter().enumerate().filter_map(|(ix, b)| {
+        let (x, y, z) = b.bind?;
+        Some(format!("\t\tPoseNode:  {{\n\t\t\tNode: {}\n\t\t\tMatrix: *16 {{\n\t\t\t\ta: 1,0,0,0,0,1,0,0,0,0,1,0,{},{},{},1\n\t\t\t}}\n\t\t}}\n", 900 + ix, x, y, z))
+    }).collect();
+    if !pose_nodes.is_empty() {
+        objects += &format!("\tPose: 940, \"Pose::BIND_POSES\", \"BindPose\" {{\n\t\tType: \"BindPose\"\n\t\tVersion: 100\n\t\tNbPoseNodes: {}\n{}\t}}\n",
+            pose_nodes.len(), pose_nodes.concat());
+    }
+
+    if bones.iter().any(|b| b.slot.is_some()) {
+        objects += "\tConstraint: 950, \"Character::Character1\", \"Character\" {\n\t\tType: \"Character\"\n\t}\n";
+        connections += "\tC: \"OO\",950,0\n";
+        for (ix, b) in bones.iter().enumerate() {
+            if let Some(slot) = b.slot {
+                connections += &format!("\tC: \"OP\",{},950, \"{}Link\"\n", 900 + ix, slot);
+            }
+        }
+    }
+
+    load_cube_anim_with(&objects, &connections, ufbx::LoadOpts::default())
+}
+
+// Mocap skeleton posed away from its bind pose: hips moved forward and turned,
+// left thigh raised forward
+fn load_source() -> ufbx::SceneRoot {
+    load_skeleton(&[
+        Bone { rotation: (0.0, 90.0, 0.0), bind: Some((0.0, 100.0, 0.0)), slot: Some("Hips"), ..bone("mixamorig:Hips", None, (0.0, 100.0, 30.0)) },
+        Bone { bind: Some((0.0, 110.0, 0.0)), slot: Some("Spine"), ..bone("mixamorig:Spine", Some(0), (0.0, 10.0, 0.0)) },
+        Bone { rotation: (90.0, 0.0, 0.0), bind: Some((10.0, 95.0, 0.0)), slot: Some("LeftUpLeg"), ..bone("mixamorig:LeftUpLeg", Some(0), (10.0, -5.0, 0.0)) },
+        Bone { bind: Some((10.0, 50.0, 0.0)), slot: Some("LeftLeg"), ..bone("mixamorig:LeftLeg", Some(2), (0.0, -45.0, 0.0)) },
+        Bone { bind: Some((10.0, 5.0, 0.0)), slot: Some("LeftFoot"), ..bone("mixamorig:LeftFoot", Some(3), (0.0, -45.0, 0.0)) },
+    ])
+}
+
+// Game skeleton with half the leg length, the thigh points up in its rest frame
+fn load_target() -> ufbx::SceneRoot {
+    load_skeleton(&[
+        Bone { slot: Some("Hips"), ..bone("pelvis", None, (0.0, 50.0, 0.0)) },
+        Bone { slot: Some("Spine"), ..bone("spine_01", Some(0), (0.0, 5.0, 0.0)) },
+        Bone { rotation: (0.0, 0.0, 180.0), slot: Some("LeftUpLeg"), ..bone("thigh_l", Some(0), (5.0, -2.5, 0.0)) },
+        Bone { slot: Some("LeftLeg"), ..bone("calf_l", Some(2), (0.0, 22.5, 0.0)) },
+        Bone { slot: Some("LeftFoot"), ..bone("foot_l", Some(3), (0.0, 22.5, 0.0)) },
+        bone("ball_l", Some(4), (0.0, 0.0, 5.0)),
+    ])
+}
+
+fn direction(a: &ufbx::Matrix, b: &ufbx::Matrix) -> Vec3 {
+    let (a, b) = (translation(a), translation(b));
+    let d = Vec3 { x: b.x - a.x, y: b.y - a.y, z: b.z - a.z };
+    let len = (d.x * d.x + d.y * d.y + d.z * d.z).sqrt();
+    Vec3 { x: d.x / len, y: d.y / len, z: d.z / len }
+}
+
+// (source, target) node names of the leg chain
+const LEG: &[(&str, &str)] = &[
+    ("mixamorig:Hips", "pelvis"),
+    ("mixamorig:LeftUpLeg", "thigh_l"),
+    ("mixamorig:LeftLeg", "calf_l"),
+    ("mixamorig:LeftFoot", "foot_l"),
+];
+
+fn assert_retargeted(source: &ufbx::Scene, target: &ufbx::Scene, pose: &ufbx::ConstraintPose) {
+    let source_world = |name: &str| &source.nodes[node_index(source, name)].node_to_world;
+    let target_world = |name: &str| &pose.node_to_world[node_index(target, name)];
+
+    // Bones point the same way, the thigh rest rotation is compensated
+    for pair in LEG.windows(2) {
+        let s = direction(source_world(pair[0].0), source_world(pair[1].0));
+        let t = direction(target_world(pair[0].1), target_world(pair[1].1));
+        assert_near_vec3(s, t);
+    }
+    assert_near_vec3(direction(target_world("thigh_l"), target_world("calf_l")), Vec3 { x: -1.0, y: 0.0, z: 0.0 });
+
+    // Root offset from the rest pose is halved with the leg length
+    assert_near_vec3(translation(target_world("pelvis")), Vec3 { x: 0.0, y: 50.0, z: 15.0 });
+
+    // Unmapped bones follow with their original local transform
+    let ball = &target.nodes[node_index(target, "ball_l")];
+    assert_eq!(pose.local_transforms[ball.element.typed_id as usize].translation.z, 5.0);
+    assert_near_vec3(direction(target_world("foot_l"), target_world("ball_l")), Vec3 { x: 0.0, y: -1.0, z: 0.0 });
+}
+
+#[test]
+fn retarget_match_names() {
+    let source = load_source();
+    let target = load_skeleton(&[
+        bone("Hips", None, (0.0, 50.0, 0.0)),
+        bone("Spine", Some(0), (0.0, 5.0, 0.0)),
+        Bone { rotation: (0.0, 0.0, 180.0), ..bone("Left_Up_Leg", Some(0), (5.0, -2.5, 0.0)) },
+        bone("LeftLeg", Some(2), (0.0, 22.5, 0.0)),
+        bone("LeftFoot", Some(3), (0.0, 22.5, 0.0)),
+        bone("ball_l", Some(4), (0.0, 0.0, 5.0)),
+    ]);
+    // The skeleton and `pCube1` shared by both scenes
+    let map = BoneMap::match_names(&source, &target);
+    assert_eq!(map.pairs.len(), 6);
+
+    // Slots are recognized from HumanIK names with or without namespaces
+    let thigh = map.pairs.iter().find(|p| target.nodes[p.target as usize].element.name == "Left_Up_Leg").unwrap();
+    assert_eq!(source.nodes[thigh.source as usize].element.name, "mixamorig:LeftUpLeg");
+    assert_eq!(thigh.slot, Some(HumanoidSlot::LeftUpLeg));
+
+    let retargeter = Retargeter::new(&source, &target, &map, &RetargetOpts::default());
+    assert!((retargeter.root_scale() - 0.5).abs() < 1e-9);
+    let pose = retargeter.retarget(&source);
+    let calf = &pose.node_to_world[node_index(&target, "LeftLeg")];
+    assert_near_vec3(translation(calf), Vec3 { x: -22.5, y: 47.5, z: 10.0 });
+}
+
+#[test]
+fn retarget_characters() {
+    let (source, target) = (load_source(), load_target());
+    let map = BoneMap::from_characters(&source.characters[0].definition(), &target.characters[0].definition());
+    assert_eq!(map.pairs.len(), 5);
+    assert!(map.pairs.iter().all(|p| p.slot.is_some()));
+
+    let retargeter = Retargeter::new(&source, &target, &map, &RetargetOpts::default());
+    assert_eq!(retargeter.pairs()[0].slot, Some(HumanoidSlot::Hips));
+    assert!((retargeter.root_scale() - 0.5).abs() < 1e-9);
+    assert_retargeted(&source, &target, &retargeter.retarget(&source));
+}
+
+#[test]
+fn retarget_manual_names() {
+    let (source, target) = (load_source(), load_target());
+    let mut names = LEG.to_vec();
+    names.push(("missing", "spine_01"));
+    let map = BoneMap::from_names(&source, &target, &names);
+    assert_eq!(map.pairs.len(), 4);
+    assert_eq!(map.pairs[1].slot, Some(HumanoidSlot::LeftUpLeg));
+
+    let retargeter = Retargeter::new(&source, &target, &map, &RetargetOpts::default());
+    assert_retargeted(&source, &target, &retargeter.retarget(&source));
+
+    // Explicit root scale
+    let retargeter = Retargeter::new(&source, &target, &map, &RetargetOpts { root_scale: Some(2.0) });
+    let pose = retargeter.retarget(&source);
+    assert_near_vec3(translation(&pose.node_to_world[node_index(&target, "pelvis")]), Vec3 { x: 0.0, y: 50.0, z: 60.0 });
+}
+
+#[test]
+fn retarget_bake() {
+    // Drive the target pelvis with the animated cube
+    let source = ufbx::load_file("tests/data/cube_anim.fbx", ufbx::LoadOpts::default()).expect("expected to load scene");
+    let target = load_target();
+    let map = BoneMap::from_names(&source, &target, &[("pCube1", "pelvis")]);
+    let retargeter = Retargeter::new(&source, &target, &map, &RetargetOpts { root_scale: Some(0.5) });
+
+    let baked = ufbx::bake_anim(&source, &source.anim, ufbx::BakeOpts::default()).expect("expected to bake");
+    let result = retargeter.bake(&source.anim, &baked).expect("expected to retarget");
+    assert_eq!(result.len(), 1);
+    let pelvis = &target.nodes[node_index(&target, "pelvis")];
+    assert_eq!(result[0].typed_id, pelvis.element.typed_id);
+    assert_eq!(result[0].element_id, pelvis.element.element_id);
+    assert!(result[0].translation_keys.len() >= 2);
+
+    let cube = &source.nodes[node_index(&source, "pCube1")];
+    let rest = ufbx::matrix_to_transform(&cube.node_to_world);
+    let inverse_rest = ufbx::Quat { x: -rest.rotation.x, y: -rest.rotation.y, z: -rest.rotation.z, w: rest.rotation.w };
+    for (t, r) in result[0].translation_keys.iter().zip(&result[0].rotation_keys) {
+        let state = ufbx::evaluate_scene(&source, &source.anim, t.time, ufbx::EvaluateOpts::default()).unwrap();
+        let world = ufbx::matrix_to_transform(&state.nodes[cube.element.typed_id as usize].node_to_world);
+        let expected = Vec3 {
+            x: (world.translation.x - rest.translation.x) * 0.5,
+            y: 50.0 + (world.translation.y - rest.translation.y) * 0.5,
+            z: (world.translation.z - rest.translation.z) * 0.5,
+        };
+        assert_near_vec3(t.value, expected);
+        let q = ufbx::quat_mul(world.rotation, inverse_rest);
+        let dot = q.x * r.value.x + q.y * r.value.y + q.z * r.value.z + q.w * r.value.w;
+        assert!(dot.abs() > 1.0 - 1e-6);
+    }
+}
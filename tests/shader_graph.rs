@@ -0,0 +1,276 @@
+use ufbx;
+
+mod common;
+
+const SHADER_FBX: &str = r#"; FBX 7.4.0 project file
+FBXHeaderExtension:  {
+	FBXHeaderVersion: 1003
+	FBXVersion: 7400
+}
+Objects:  {
+	Material: 1000, "Material::mat", "" {
+		ShadingModel: "phong"
+	}
+	Texture: 2000, "Texture::select", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "MULTIOUTPUT_TO_OSLMap"
+			P: "3dsMax|parameters", "Compound", "", ""
+			P: "3dsMax|parameters|sourceMap", "Reference", "", "A"
+			P: "3dsMax|parameters|outputChannelIndex", "int", "Integer", "",2
+		}
+	}
+	Texture: 3000, "Texture::noise", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "OSLMap"
+			P: "3dsMax|params", "Compound", "", ""
+			P: "3dsMax|params|OSLShaderName", "KString", "", "", "Noise"
+			P: "3dsMax|params|OSLCode", "KString", "", "", "shader Noise() {}"
+			P: "3dsMax|parameters", "Compound", "", ""
+			P: "3dsMax|parameters|scale", "float", "Number", "",2.5
+			P: "3dsMax|parameters|mode", "int", "Integer", "",3
+			P: "3dsMax|parameters|color", "ColorRGB", "Color", "",1,0,0
+			P: "3dsMax|parameters|color_map", "Reference", "", "A"
+		}
+	}
+	LayeredTexture: 4000, "LayeredTexture::layers", "" {
+		BlendModes: 0,1
+		Alphas: 1,0.5
+	}
+	Texture: 5000, "Texture::wood", "" {
+		Type: "TextureVideoClip"
+		FileName: "wood.png"
+		RelativeFilename: "wood.png"
+	}
+	Texture: 5001, "Texture::dirt", "" {
+		Type: "TextureVideoClip"
+		FileName: "dirt.png"
+		RelativeFilename: "dirt.png"
+	}
+}
+Connections:  {
+	C: "OP",2000,1000, "DiffuseColor"
+	C: "OP",3000,2000, "3dsMax|parameters|sourceMap"
+	C: "OP",4000,3000, "3dsMax|parameters|color_map"
+	C: "OO",5000,4000
+	C: "OO",5001,4000
+	C: "OP",5000,1000, "NormalMap"
+}
+"#;
+
+#[test]
+fn shader_graph_network() {
+    let scene = ufbx::load_memory(SHADER_FBX.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let material = scene.materials.iter().find(|m| m.element.name == "mat").expect("expected to find 'mat'");
+
+    let graph = ufbx::ShaderGraph::from_material(material);
+    assert_eq!(graph.nodes.len(), 5);
+    assert_eq!(graph.nodes[graph.root].kind, ufbx::ShaderNodeKind::Material);
+
+    let find = |name: &str| graph.nodes.iter().position(|n| n.name == name).unwrap();
+    let (noise, layers, wood, dirt) = (find("noise"), find("layers"), find("wood"), find("dirt"));
+    assert_eq!(graph.nodes[noise].kind, ufbx::ShaderNodeKind::Shader);
+    assert_eq!(graph.nodes[noise].shader_name, "Noise");
+    assert_eq!(graph.nodes[layers].kind, ufbx::ShaderNodeKind::Layered);
+    assert_eq!(graph.nodes[wood].filename, "wood.png");
+
+    let has_edge = |from: usize, output_index: i64, to: usize, input: &str| graph.edges.iter()
+        .any(|e| e.from == from && e.output_index == output_index && e.to == to && e.input == input);
+    assert!(has_edge(noise, 2, graph.root, "DiffuseColor"));
+    assert!(has_edge(layers, 0, noise, "color"));
+    assert!(has_edge(wood, 0, layers, "layer0"));
+    assert!(has_edge(dirt, 0, layers, "layer1"));
+    assert!(has_edge(wood, 0, graph.root, "NormalMap"));
+
+    let order = graph.topological_order().expect("expected a DAG");
+    assert_eq!(order.len(), 5);
+    let pos = |node: usize| order.iter().position(|&n| n == node).unwrap();
+    for edge in &graph.edges {
+        assert!(pos(edge.from) < pos(edge.to));
+    }
+
+    let json = graph.to_json();
+    assert!(json.starts_with("{\"root\":0,\"nodes\":["));
+    assert!(json.contains("\"shader_source\":\"shader Noise() {}\""));
+    assert!(json.contains("{\"name\":\"mode\",\"value\":3}"));
+    assert!(json.contains(&format!("{{\"from\":{},\"output\":2,\"to\":0,\"input\":\"DiffuseColor\"}}", noise)));
+}
+
+#[test]
+fn shader_graph_plain() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let material = scene.materials.iter().find(|m| m.element.name == "Material")
+        .expect("expected to find 'Material'");
+
+    let graph = ufbx::ShaderGraph::from_material(material);
+    assert_eq!(graph.nodes.len(), 1);
+    assert!(graph.edges.is_empty());
+    assert_eq!(graph.nodes[0].shader_name, "FbxPhong");
+}
+
+// Two select-output maps using each other as the source
+const SELECT_CYCLE_FBX: &str = r#"; FBX 7.4.0 project file
+FBXHeaderExtension:  {
+	FBXHeaderVersion: 1003
+	FBXVersion: 7400
+}
+Objects:  {
+	Material: 1000, "Material::mat", "" {
+		ShadingModel: "phong"
+	}
+	Texture: 2000, "Texture::selectA", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "MULTIOUTPUT_TO_OSLMap"
+			P: "3dsMax|parameters", "Compound", "", ""
+			P: "3dsMax|parameters|sourceMap", "Reference", "", "A"
+			P: "3dsMax|parameters|outputChannelIndex", "int", "Integer", "",1
+		}
+	}
+	Texture: 2001, "Texture::selectB", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "MULTIOUTPUT_TO_OSLMap"
+			P: "3dsMax|parameters", "Compound", "", ""
+			P: "3dsMax|parameters|sourceMap", "Reference", "", "A"
+			P: "3dsMax|parameters|outputChannelIndex", "int", "Integer", "",2
+		}
+	}
+}
+Connections:  {
+	C: "OP",2000,1000, "DiffuseColor"
+	C: "OP",2001,2000, "3dsMax|parameters|sourceMap"
+	C: "OP",2000,2001, "3dsMax|parameters|sourceMap"
+}
+"#;
+
+#[test]
+fn shader_graph_select_cycle() {
+    let scene = ufbx::load_memory(SELECT_CYCLE_FBX.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let material = &scene.materials[0];
+
+    let graph = ufbx::ShaderGraph::from_material(material);
+    let select_b = graph.nodes.iter().position(|n| n.name == "selectB").expect("expected selectB node");
+    assert!(graph.edges.iter().any(|e| e.from == select_b && e.output_index == 1 && e.to == graph.root && e.input == "DiffuseColor"));
+    assert!(graph.to_json().starts_with("{\"root\":0,"));
+}
+
+// `noise.color_map` uses output 2 of `selectZero`, which itself selects output 0 of `source`
+const SELECT_ZERO_FBX: &str = r#"; FBX 7.4.0 project file
+FBXHeaderExtension:  {
+	FBXHeaderVersion: 1003
+	FBXVersion: 7400
+}
+Objects:  {
+	Material: 1000, "Material::mat", "" {
+		ShadingModel: "phong"
+	}
+	Texture: 2000, "Texture::noise", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "OSLMap"
+			P: "3dsMax|params", "Compound", "", ""
+			P: "3dsMax|params|OSLShaderName", "KString", "", "", "Noise"
+			P: "3dsMax|parameters", "Compound", "", ""
+			P: "3dsMax|parameters|color_map", "Reference", "", "A"
+		}
+	}
+	Texture: 3000, "Texture::selectTwo", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "MULTIOUTPUT_TO_OSLMap"
+			P: "3dsMax|parameters", "Compound", "", ""
+			P: "3dsMax|parameters|sourceMap", "Reference", "", "A"
+			P: "3dsMax|parameters|outputChannelIndex", "int", "Integer", "",2
+		}
+	}
+	Texture: 4000, "Texture::selectZero", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "MULTIOUTPUT_TO_OSLMap"
+			P: "3dsMax|parameters", "Compound", "", ""
+			P: "3dsMax|parameters|sourceMap", "Reference", "", "A"
+			P: "3dsMax|parameters|outputChannelIndex", "int", "Integer", "",0
+		}
+	}
+	Texture: 5000, "Texture::source", "" {
+		Type: "TextureVideoClip"
+		Properties70:  {
+			P: "3dsMax", "Compound", "", ""
+			P: "3dsMax|MaxTexture", "KString", "", "", "OSLMap"
+			P: "3dsMax|params", "Compound", "", ""
+			P: "3dsMax|params|OSLShaderName", "KString", "", "", "Source"
+		}
+	}
+}
+Connections:  {
+	C: "OP",2000,1000, "DiffuseColor"
+	C: "OP",3000,2000, "3dsMax|parameters|color_map"
+	C: "OP",4000,3000, "3dsMax|parameters|sourceMap"
+	C: "OP",5000,4000, "3dsMax|parameters|sourceMap"
+}
+"#;
+
+#[test]
+fn shader_graph_select_output_zero() {
+    let scene = ufbx::load_memory(SELECT_ZERO_FBX.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let graph = ufbx::ShaderGraph::from_material(&scene.materials[0]);
+
+    let find = |name: &str| graph.nodes.iter().position(|n| n.name == name).unwrap();
+    let (noise, source) = (find("noise"), find("source"));
+    let edge = graph.inputs_of(noise).find(|e| e.input == "color_map").expect("expected color_map edge");
+    assert_eq!(edge.from, source);
+    assert_eq!(edge.output_index, 0);
+}
+
+fn chain_graph(len: usize) -> ufbx::ShaderGraph {
+    let nodes = (0..len).map(|ix| ufbx::ShaderNode {
+        kind: if ix == 0 { ufbx::ShaderNodeKind::Material } else { ufbx::ShaderNodeKind::Shader },
+        element_id: ix as u32,
+        name: format!("node{}", ix),
+        shader_name: String::new(),
+        shader_type_id: 0,
+        shader_source: String::new(),
+        filename: String::new(),
+        inputs: Vec::new(),
+    }).collect();
+    let edges = (1..len).map(|ix| ufbx::ShaderEdge { from: ix, output_index: 0, to: ix - 1, input: "color".into() }).collect();
+    ufbx::ShaderGraph { root: 0, nodes, edges }
+}
+
+#[test]
+fn shader_graph_topological_order() {
+    let mut graph = chain_graph(100000);
+    let order = graph.topological_order().expect("expected a DAG");
+    assert_eq!(order.len(), 100000);
+    assert_eq!(order[0], 99999);
+    assert_eq!(order[99999], 0);
+
+    graph.edges.push(ufbx::ShaderEdge { from: 0, output_index: 0, to: 99999, input: "color".into() });
+    assert!(graph.topological_order().is_none());
+}
+
+#[test]
+fn shader_graph_deep_layers() {
+    let scene = ufbx::load_memory(common::nested_layers_source(5000).as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let graph = ufbx::ShaderGraph::from_material(&scene.materials[0]);
+    assert_eq!(graph.nodes.len(), 5002);
+    assert_eq!(graph.edges.len(), 5001);
+    assert_eq!(graph.nodes[1].name, "layer0");
+    assert_eq!(graph.nodes[5001].name, "leaf");
+    assert_eq!(graph.nodes[5001].kind, ufbx::ShaderNodeKind::File);
+    assert_eq!(graph.topological_order().expect("expected a DAG").len(), 5002);
+}
@@ -0,0 +1,126 @@
+use ufbx::{self, LodMesh, SimplifyOpts};
+
+mod common;
+use common::load_obj_lod;
+
+// Flat `n` by `n` quad grid, the left half uses material "A" and the right half "B".
+// With `seam` the right half uses its own UV island offset by 2.
+fn grid_obj(n: usize, seam: bool) -> String {
+    let mut s = String::new();
+    for y in 0..=n {
+        for x in 0..=n {
+            s += &format!("v {} {} 0\n", x, y);
+        }
+    }
+    for island in 0..2 {
+        for y in 0..=n {
+            for x in 0..=n {
+                s += &format!("vt {} {}\n", (x as f64 / n as f64) + island as f64 * 2.0, y as f64 / n as f64);
+            }
+        }
+    }
+    s += "vn 0 0 1\n";
+
+    let ix = |x: usize, y: usize| y * (n + 1) + x + 1;
+    for (material, range) in [("A", 0..n / 2), ("B", n / 2..n)].iter() {
+        if !seam {
+            s += &format!("usemtl {}\n", material);
+        }
+        let uv_base = if seam && *material == "B" { (n + 1) * (n + 1) } else { 0 };
+        for y in 0..n {
+            for x in range.clone() {
+                let corners = [ix(x, y), ix(x + 1, y), ix(x + 1, y + 1), ix(x, y + 1)];
+                s += "f";
+                for &c in &corners {
+                    s += &format!(" {}/{}/1", c, c + uv_base);
+                }
+                s += "\n";
+            }
+        }
+    }
+    s
+}
+
+fn load_grid(n: usize, seam: bool) -> LodMesh {
+    load_obj_lod(&grid_obj(n, seam))
+}
+
+fn part_triangles(lod: &LodMesh, part: usize) -> impl Iterator<Item = [&ufbx::LodVertex; 3]> {
+    let part = lod.parts[part];
+    lod.indices[part.index_begin..part.index_begin + part.num_triangles * 3]
+        .chunks_exact(3)
+        .map(move |t| [&lod.vertices[t[0] as usize], &lod.vertices[t[1] as usize], &lod.vertices[t[2] as usize]])
+}
+
+#[test]
+fn simplify_material_parts() {
+    let lod = load_grid(8, false);
+    assert_eq!(lod.num_triangles(), 128);
+    assert_eq!(lod.parts.len(), 2);
+
+    let simplified = lod.simplify(&SimplifyOpts::default());
+    assert_eq!(simplified.num_triangles(), 4);
+    assert!(simplified.error < 1e-6);
+    assert_eq!(simplified.parts.len(), 2);
+    assert_eq!(simplified.parts[0].index, lod.parts[0].index);
+    assert_eq!(simplified.parts[1].index, lod.parts[1].index);
+
+    // The boundary between the materials must stay in place
+    for tri in part_triangles(&simplified, 0) {
+        assert!(tri.iter().all(|v| v.position.x <= 4.0));
+    }
+    for tri in part_triangles(&simplified, 1) {
+        assert!(tri.iter().all(|v| v.position.x >= 4.0));
+    }
+    for v in &simplified.vertices {
+        assert_eq!(v.uv.x, v.position.x / 8.0);
+        assert_eq!(v.uv.y, v.position.y / 8.0);
+    }
+}
+
+#[test]
+fn simplify_uv_seam() {
+    let lod = load_grid(8, true);
+    assert_eq!(lod.parts.len(), 1);
+
+    let simplified = lod.simplify(&SimplifyOpts::default());
+    assert_eq!(simplified.num_triangles(), 4);
+    for tri in part_triangles(&simplified, 0) {
+        // Triangles must not span both UV islands
+        let island = |v: &ufbx::LodVertex| v.uv.x >= 1.5;
+        assert!(tri.iter().all(|v| island(v) == island(tri[0])));
+        for v in &tri {
+            let expected = if island(v) { v.position.x / 8.0 + 2.0 } else { v.position.x / 8.0 };
+            assert_eq!(v.uv.x, expected);
+        }
+    }
+}
+
+#[test]
+fn simplify_target_triangles() {
+    let scene = ufbx::load_file("tests/data/nurbs_saddle.obj", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let mesh = &scene.meshes[0];
+    let lod = LodMesh::from_mesh(mesh);
+    assert_eq!(lod.num_triangles(), 32);
+
+    let strict = lod.simplify(&SimplifyOpts { max_error: 1e-4, ..Default::default() });
+    assert_eq!(strict.num_triangles(), 32);
+
+    let lods = ufbx::generate_lods(mesh, &[
+        SimplifyOpts { target_triangles: 16, max_error: ufbx::Real::INFINITY, ..Default::default() },
+        SimplifyOpts { target_triangles: 8, max_error: ufbx::Real::INFINITY, ..Default::default() },
+    ]);
+    assert_eq!(lods.len(), 2);
+    assert!(lods[0].num_triangles() <= 16 && lods[0].num_triangles() > 8);
+    assert!(lods[1].num_triangles() <= 8 && lods[1].num_triangles() > 0);
+    assert!(lods[0].error > 0.0);
+    assert!(lods[1].error >= lods[0].error);
+    for lod in &lods {
+        for &ix in &lod.indices {
+            let v = &lod.vertices[ix as usize];
+            let p = mesh.vertices[v.vertex as usize];
+            assert_eq!((v.position.x, v.position.y, v.position.z), (p.x, p.y, p.z));
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use ufbx::{self, SurfaceMesh, SweepOpts, SweepProfile, Vec3};
+
+mod common;
+use common::{vec3, vec3_sub, vec3_dot, vec3_cross, vec3_length, vec3_distance};
+
+// Triangles wind counter-clockwise around the vertex normals
+fn check_winding(mesh: &SurfaceMesh) {
+    for tri in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[tri[i] as usize]);
+        let n = vec3_cross(vec3_sub(b.position, a.position), vec3_sub(c.position, a.position));
+        assert!(vec3_length(n) > 0.0);
+        for v in &[a, b, c] {
+            assert!(vec3_dot(n, v.normal) > 0.0);
+        }
+    }
+}
+
+#[test]
+fn sweep_straight_tube() {
+    let points = [vec3(0.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0), vec3(2.0, 0.0, 0.0)];
+    let opts = SweepOpts { radius: 0.5, radius_scale: vec![1.0, 0.5], ..Default::default() };
+    let mesh = ufbx::sweep_polyline(&points, false, &opts);
+
+    // Duplicate point is skipped: 3 rings of 9 vertices and two caps
+    assert_eq!(mesh.vertices.len(), 3 * 9 + 2 * 10);
+    assert_eq!(mesh.num_triangles(), 2 * 8 * 2 + 2 * 8);
+    check_winding(&mesh);
+
+    for v in &mesh.vertices[..27] {
+        let radius = 0.5 * (1.0 - 0.25 * v.position.x);
+        assert!(((v.position.y.powi(2) + v.position.z.powi(2)).sqrt() - radius).abs() < 1e-9);
+        assert!(v.normal.x.abs() < 1e-9);
+        assert!((v.uv.y - v.position.x).abs() < 1e-9);
+        assert!(v.uv.x >= 0.0 && v.uv.x <= 1.0);
+    }
+    let start_cap = mesh.vertices[27];
+    let end_cap = mesh.vertices[37];
+    assert_eq!((start_cap.normal.x, end_cap.normal.x), (-1.0, 1.0));
+}
+
+#[test]
+fn sweep_planar_ribbon() {
+    // Parallel transport keeps the normal fixed for a planar curve
+    let points: Vec<Vec3> = (0..=16).map(|i| {
+        let t = i as f64 / 16.0 * 3.0;
+        vec3(t.cos() * (1.0 + t), t.sin() * (1.0 + t), 0.0)
+    }).collect();
+    let opts = SweepOpts {
+        profile: SweepProfile::Ribbon,
+        radius: 0.1,
+        reference_normal: vec3(0.0, 0.0, 1.0),
+        ..Default::default()
+    };
+    let mesh = ufbx::sweep_polyline(&points, false, &opts);
+    assert_eq!(mesh.vertices.len(), points.len() * 2);
+    assert_eq!(mesh.num_triangles(), (points.len() - 1) * 2);
+    check_winding(&mesh);
+    for (ix, v) in mesh.vertices.iter().enumerate() {
+        assert!(v.position.z.abs() < 1e-9);
+        assert!((v.normal.z - 1.0).abs() < 1e-9);
+        let center = points[ix / 2];
+        assert!((vec3_length(vec3_sub(v.position, center)) - 0.1).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn sweep_closed_loop() {
+    // Non-planar loop that accumulates twist during transport
+    let n = 64;
+    let points: Vec<Vec3> = (0..n).map(|i| {
+        let t = i as f64 / n as f64 * std::f64::consts::TAU;
+        vec3(t.cos() * 2.0, t.sin() * 2.0, (3.0 * t).sin())
+    }).collect();
+    let opts = SweepOpts { radius: 0.2, sides: 6, ..Default::default() };
+    let mesh = ufbx::sweep_polyline(&points, true, &opts);
+
+    // No caps and one extra ring for the UV seam
+    assert_eq!(mesh.vertices.len(), (n + 1) * 7);
+    check_winding(&mesh);
+    let (first, last) = (&mesh.vertices[..7], &mesh.vertices[n * 7..]);
+    for (a, b) in first.iter().zip(last) {
+        assert!(vec3_length(vec3_sub(a.position, b.position)) < 1e-9);
+        assert!(vec3_length(vec3_sub(a.normal, b.normal)) < 1e-9);
+        assert_eq!(a.uv.x, b.uv.x);
+        assert!(b.uv.y > a.uv.y);
+    }
+}
+
+#[test]
+fn sweep_tessellated_curve() {
+    let source = std::fs::read_to_string("tests/data/nurbs_saddle.fbx").expect("expected to read file");
+    let w = std::f64::consts::FRAC_1_SQRT_2;
+    let circle = [
+        (1.0, 0.0, 1.0), (1.0, 1.0, w), (0.0, 1.0, 1.0), (-1.0, 1.0, w), (-1.0, 0.0, 1.0),
+        (-1.0, -1.0, w), (0.0, -1.0, 1.0), (1.0, -1.0, w), (1.0, 0.0, 1.0),
+    ];
+    let points: Vec<String> = circle.iter().map(|p| format!("{},{},0,{}", p.0, p.1, p.2)).collect();
+    let curve = format!(concat!(
+        "\tGeometry: 300, \"Geometry::\", \"NurbsCurve\" {{\n",
+        "\t\tType: \"NurbsCurve\"\n\t\tOrder: 3\n\t\tDimension: 3\n\t\tForm: \"Open\"\n",
+        "\t\tPoints: *{} {{\n\t\t\ta: {}\n\t\t}}\n",
+        "\t\tKnotVector: *12 {{\n\t\t\ta: 0,0,0,1,1,2,2,3,3,4,4,4\n\t\t}}\n\t}}\n"),
+        points.len() * 4, points.join(","));
+    let source = source.replace("\tModel: 2244692783312,", &(curve + "\tModel: 2244692783312,"));
+    let scene = ufbx::load_memory(source.as_bytes(), ufbx::LoadOpts::default()).expect("expected to load scene");
+
+    let line = ufbx::tessellate_nurbs_curve(&scene.nurbs_curves[0], ufbx::TessellateCurveOpts {
+        span_subdivision: 8,
+        ..Default::default()
+    }).expect("expected to tessellate");
+    let opts = SweepOpts { radius: 0.1, ..Default::default() };
+    let mesh = ufbx::sweep_line_curve(&line, &opts);
+    check_winding(&mesh);
+
+    // The curve ends at its start so it is swept as a loop without caps
+    assert!(mesh.vertices.len() % 9 == 0);
+    for v in &mesh.vertices {
+        assert!(((v.position.x.powi(2) + v.position.y.powi(2)).sqrt() - 1.0).abs() <= 0.1 + 1e-9);
+        assert!((vec3_distance(v.position, vec3(0.0, 0.0, 0.0)) - 1.0).abs() <= 0.1 + 1e-9);
+    }
+    let arc = mesh.vertices.last().unwrap().uv.y;
+    assert!((arc - std::f64::consts::TAU).abs() < 0.05);
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use ufbx::{self, AdaptiveTessellateOpts, SurfaceMesh, Vec3};
+
+mod common;
+use common::vec3_distance;
+
+fn load_saddle() -> ufbx::SceneRoot {
+    ufbx::load_file("tests/data/nurbs_saddle.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene")
+}
+
+// Every edge is shared by two triangles with opposite winding or lies on the domain boundary
+fn check_watertight(mesh: &SurfaceMesh) {
+    let mut edges: HashMap<(u32, u32), i32> = HashMap::new();
+    for tri in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[tri[i] as usize].uv);
+        assert!((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x) > 0.0);
+        for i in 0..3 {
+            let (a, b) = (tri[i], tri[(i + 1) % 3]);
+            *edges.entry((a.min(b), a.max(b))).or_insert(0) += if a < b { 1 } else { -1 };
+        }
+    }
+    for (&(a, b), &count) in &edges {
+        if count == 0 { continue }
+        assert_eq!(count.abs(), 1);
+        let (pa, pb) = (mesh.vertices[a as usize].uv, mesh.vertices[b as usize].uv);
+        let on_u = |x: f64| x == 0.0 || x == 1.0;
+        assert!((on_u(pa.x) && pa.x == pb.x) || (on_u(pa.y) && pa.y == pb.y));
+    }
+}
+
+#[test]
+fn tessellate_surface_adaptive() {
+    let scene = load_saddle();
+    let surface = &scene.nurbs_surfaces[0];
+
+    let coarse = ufbx::tessellate_nurbs_surface_adaptive(surface, &AdaptiveTessellateOpts { max_chordal_error: 1e-2, ..Default::default() });
+    let fine = ufbx::tessellate_nurbs_surface_adaptive(surface, &AdaptiveTessellateOpts { max_chordal_error: 1e-4, ..Default::default() });
+    let again = ufbx::tessellate_nurbs_surface_adaptive(surface, &AdaptiveTessellateOpts { max_chordal_error: 1e-4, ..Default::default() });
+    assert!(coarse.num_triangles() >= 2);
+    assert!(fine.num_triangles() > coarse.num_triangles());
+    assert_eq!(fine.num_triangles(), again.num_triangles());
+    assert_eq!(fine.vertices.len(), again.vertices.len());
+
+    for mesh in &[&coarse, &fine] {
+        check_watertight(mesh);
+        for v in &mesh.vertices {
+            let p = ufbx::evaluate_nurbs_surface(surface, v.uv.x, v.uv.y);
+            assert_eq!((v.position.x, v.position.y, v.position.z), (p.position.x, p.position.y, p.position.z));
+            assert!((v.normal.x.powi(2) + v.normal.y.powi(2) + v.normal.z.powi(2) - 1.0).abs() < 1e-9);
+        }
+    }
+
+    // Triangle centroids stay close to the surface
+    for tri in fine.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| fine.vertices[tri[i] as usize]);
+        let center = Vec3 {
+            x: (a.position.x + b.position.x + c.position.x) / 3.0,
+            y: (a.position.y + b.position.y + c.position.y) / 3.0,
+            z: (a.position.z + b.position.z + c.position.z) / 3.0,
+        };
+        let p = ufbx::evaluate_nurbs_surface(surface, (a.uv.x + b.uv.x + c.uv.x) / 3.0, (a.uv.y + b.uv.y + c.uv.y) / 3.0);
+        assert!(vec3_distance(center, p.position) < 1e-3);
+    }
+}
+
+#[test]
+fn tessellate_surface_edge_length() {
+    let scene = load_saddle();
+    let surface = &scene.nurbs_surfaces[0];
+    let max_edge_length = 0.05;
+    let opts = AdaptiveTessellateOpts { max_chordal_error: 0.0, max_normal_angle: 0.0, max_edge_length, ..Default::default() };
+    let mesh = ufbx::tessellate_nurbs_surface_adaptive(surface, &opts);
+    check_watertight(&mesh);
+
+    // Cell sides are bounded, diagonals and fans to the cell center are not longer than the cell
+    for tri in mesh.indices.chunks_exact(3) {
+        for i in 0..3 {
+            let (a, b) = (mesh.vertices[tri[i] as usize], mesh.vertices[tri[(i + 1) % 3] as usize]);
+            assert!(vec3_distance(a.position, b.position) < max_edge_length * 1.5);
+        }
+    }
+}
+
+#[test]
+fn tessellate_curve_adaptive() {
+    let source = std::fs::read_to_string("tests/data/nurbs_saddle.fbx").expect("expected to read file");
+    let w = std::f64::consts::FRAC_1_SQRT_2;
+    let circle = [
+        (1.0, 0.0, 1.0), (1.0, 1.0, w), (0.0, 1.0, 1.0), (-1.0, 1.0, w), (-1.0, 0.0, 1.0),
+        (-1.0, -1.0, w), (0.0, -1.0, 1.0), (1.0, -1.0, w), (1.0, 0.0, 1.0),
+    ];
+    let points: Vec<String> = circle.iter().map(|p| format!("{},{},0,{}", p.0, p.1, p.2)).collect();
+    let curve = format!(concat!(
+        "\tGeometry: 300, \"Geometry::\", \"NurbsCurve\" {{\n",
+        "\t\tType: \"NurbsCurve\"\n\t\tOrder: 3\n\t\tDimension: 3\n\t\tForm: \"Closed\"\n",
+        "\t\tPoints: *{} {{\n\t\t\ta: {}\n\t\t}}\n",
+        "\t\tKnotVector: *12 {{\n\t\t\ta: 0,0,0,1,1,2,2,3,3,4,4,4\n\t\t}}\n\t}}\n"),
+        points.len() * 4, points.join(","));
+    let source = source.replace("\tModel: 2244692783312,", &(curve + "\tModel: 2244692783312,"));
+    let scene = ufbx::load_memory(source.as_bytes(), ufbx::LoadOpts::default()).expect("expected to load scene");
+    let curve = &scene.nurbs_curves[0];
+
+    let coarse = ufbx::tessellate_nurbs_curve_adaptive(curve, &AdaptiveTessellateOpts { max_chordal_error: 1e-2, ..Default::default() });
+    let fine = ufbx::tessellate_nurbs_curve_adaptive(curve, &AdaptiveTessellateOpts { max_chordal_error: 1e-4, ..Default::default() });
+    assert!(fine.len() > coarse.len());
+    assert_eq!((fine[0], fine[fine.len() - 1]), (0.0, 4.0));
+    assert!(fine.windows(2).all(|w| w[0] < w[1]));
+
+    // Depths beyond the supported lattice resolution are clamped
+    let deep = |max_depth| ufbx::tessellate_nurbs_curve_adaptive(curve, &AdaptiveTessellateOpts { max_chordal_error: 1e-2, max_depth, ..Default::default() });
+    assert_eq!(deep(64), deep(30));
+
+    // Chord midpoints of a unit circle lie at most the chordal error inside
+    for params in &[&coarse, &fine] {
+        for w in params.windows(2) {
+            let (a, b) = (ufbx::evaluate_nurbs_curve(curve, w[0]).position, ufbx::evaluate_nurbs_curve(curve, w[1]).position);
+            let mid = Vec3 { x: (a.x + b.x) * 0.5, y: (a.y + b.y) * 0.5, z: 0.0 };
+            let angle = (a.x * b.x + a.y * b.y).clamp(-1.0, 1.0).acos();
+            assert!(angle < 10.0f64.to_radians() + 1e-9);
+            assert!(1.0 - vec3_distance(mid, Vec3::default()) < 1e-2);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use ufbx;
+
+const THUMBNAIL_FBX: &str = r#"; FBX 7.4.0 project file
+FBXHeaderExtension:  {
+	FBXHeaderVersion: 1003
+	FBXVersion: 7400
+	SceneInfo: "SceneInfo::GlobalInfo", "UserData" {
+		Type: "UserData"
+		Version: 100
+		Thumbnail:  {
+			Version: 100
+			Format: 0
+			Size: 2
+			ImageData: *12 {
+				a: 1,2,3,4,5,6,7,8,9,10,11,12
+			}
+		}
+	}
+}
+"#;
+
+#[test]
+fn thumbnail_rgba8() {
+    let scene = ufbx::load_memory(THUMBNAIL_FBX.as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let thumbnail = &scene.metadata.thumbnail;
+    assert_eq!(thumbnail.format, ufbx::ThumbnailFormat::Rgb24);
+
+    let rgba = thumbnail.to_rgba8().expect("expected thumbnail");
+    assert_eq!(rgba, [7, 8, 9, 255, 10, 11, 12, 255, 1, 2, 3, 255, 4, 5, 6, 255]);
+
+    let png = thumbnail.to_png().expect("expected thumbnail");
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x02\0\0\0\x02"));
+    assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+}
+
+#[test]
+fn thumbnail_missing() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    assert!(scene.metadata.thumbnail.to_rgba8().is_none());
+    assert!(scene.metadata.thumbnail.to_png().is_none());
+}
+
+#[test]
+fn thumbnail_encode_size_mismatch() {
+    assert!(ufbx::encode_png_rgba8(2, 2, &[0; 12]).is_none());
+    let png = ufbx::encode_png_rgba8(1, 1, &[1, 2, 3, 4]).expect("expected png");
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+}
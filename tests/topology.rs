@@ -0,0 +1,105 @@
+use ufbx::{self, MeshTopology};
+
+const OPEN_OBJ: &str = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 2 1 0
+v 2 2 0
+v 5 0 0
+v 6 0 0
+v 5 1 0
+f 1 2 3
+f 1 3 4
+f 3 5 6
+f 7 8 9
+";
+
+fn load_open_mesh() -> ufbx::SceneRoot {
+    let opts = ufbx::LoadOpts {
+        file_format: ufbx::FileFormat::Obj,
+        ..Default::default()
+    };
+    ufbx::load_memory(OPEN_OBJ.as_bytes(), opts).expect("expected to load scene")
+}
+
+fn vertex_at(mesh: &ufbx::Mesh, x: f64, y: f64) -> usize {
+    (0..mesh.num_vertices)
+        .find(|&v| mesh.vertices[v].x == x && mesh.vertices[v].y == y)
+        .expect("expected to find vertex")
+}
+
+#[test]
+fn closed_cube() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let node = scene.find_node("Cube").expect("expected to find 'Cube'");
+    let mesh = node.mesh.as_ref().expect("expected 'Cube' to have a mesh");
+
+    let topo = MeshTopology::new(mesh);
+    assert!(topo.is_closed());
+    assert!(topo.is_manifold());
+    assert_eq!(topo.boundary_loops().count(), 0);
+    assert_eq!(topo.connected_components().count(), 1);
+
+    for he in 0..topo.num_half_edges() {
+        let twin = topo.twin(he).expect("expected every edge to have a twin");
+        assert_eq!(topo.twin(twin), Some(he));
+        assert_eq!(topo.vertex(twin), topo.end_vertex(he));
+        assert!(!topo.is_boundary_edge(he));
+    }
+
+    for v in 0..topo.num_vertices() {
+        let mut neighbors: Vec<usize> = topo.vertex_neighbors(v).collect();
+        neighbors.sort();
+        neighbors.dedup();
+        assert_eq!(neighbors.len(), 3);
+        assert_eq!(topo.vertex_faces(v).count(), 3);
+
+        // Consecutive outgoing edges must be rotational neighbors
+        let edges: Vec<usize> = topo.vertex_edges(v).collect();
+        for i in 0..edges.len() {
+            let next = topo.next_vertex_edge(edges[i]).expect("expected closed fan");
+            assert_eq!(next, edges[(i + 1) % edges.len()]);
+        }
+    }
+}
+
+#[test]
+fn open_mesh() {
+    let scene = load_open_mesh();
+    let mesh = &scene.meshes[0];
+    let topo = MeshTopology::new(mesh);
+
+    assert!(!topo.is_closed());
+    assert_eq!(topo.num_faces(), 4);
+
+    let corner = vertex_at(mesh, 0.0, 0.0);
+    let mut neighbors: Vec<usize> = topo.vertex_neighbors(corner).collect();
+    neighbors.sort();
+    let mut expected = vec![vertex_at(mesh, 1.0, 0.0), vertex_at(mesh, 1.0, 1.0), vertex_at(mesh, 0.0, 1.0)];
+    expected.sort();
+    assert_eq!(neighbors, expected);
+    assert!(topo.is_boundary_vertex(corner));
+    assert!(!topo.is_non_manifold_vertex(corner));
+
+    let bowtie = vertex_at(mesh, 1.0, 1.0);
+    assert!(topo.is_non_manifold_vertex(bowtie));
+    assert_eq!(topo.vertex_faces(bowtie).count(), 3);
+    assert!(!topo.is_manifold());
+
+    let mut loops: Vec<usize> = topo.boundary_loops().map(|l| l.len()).collect();
+    loops.sort();
+    assert_eq!(loops, vec![3, 3, 4]);
+    for l in topo.boundary_loops() {
+        for (i, &he) in l.iter().enumerate() {
+            assert!(topo.is_boundary_edge(he));
+            assert_eq!(topo.end_vertex(he), topo.vertex(l[(i + 1) % l.len()]));
+        }
+    }
+
+    let mut components: Vec<usize> = topo.connected_components().map(|c| c.len()).collect();
+    components.sort();
+    assert_eq!(components, vec![1, 3]);
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use ufbx::{self, TrimmedSurface, TrimTessellateOpts};
+
+fn curve(id: u64, order: u32, points: &[(f64, f64, f64)], knots: &[f64]) -> String {
+    let points: Vec<String> = points.iter().map(|p| format!("{},{},0,{}", p.0, p.1, p.2)).collect();
+    let knots: Vec<String> = knots.iter().map(|k| k.to_string()).collect();
+    format!(concat!(
+        "\tGeometry: {}, \"Geometry::\", \"NurbsCurve\" {{\n",
+        "\t\tType: \"NurbsCurve\"\n\t\tOrder: {}\n\t\tDimension: 2\n\t\tForm: \"Open\"\n",
+        "\t\tPoints: *{} {{\n\t\t\ta: {}\n\t\t}}\n",
+        "\t\tKnotVector: *{} {{\n\t\t\ta: {}\n\t\t}}\n\t}}\n"),
+        id, order, points.len() * 4, points.join(","), knots.len(), knots.join(","))
+}
+
+fn boundary(id: u64, outer: bool) -> String {
+    format!(concat!(
+        "\tGeometry: {}, \"Geometry::\", \"Boundary\" {{\n\t\tType: \"Boundary\"\n",
+        "\t\tProperties70:  {{\n\t\t\tP: \"OuterFlag\", \"bool\", \"\", \"\",{}\n\t\t}}\n\t}}\n"),
+        id, if outer { 1 } else { 0 })
+}
+
+// Saddle surface trimmed by the domain boundary with a circular hole of radius 0.25
+fn trimmed_saddle() -> String {
+    let source = std::fs::read_to_string("tests/data/nurbs_saddle.fbx").expect("expected to read file");
+    let line = |a: (f64, f64), b: (f64, f64)| [(a.0, a.1, 1.0), (b.0, b.1, 1.0)];
+    let w = std::f64::consts::FRAC_1_SQRT_2;
+    let (c, r) = (0.5, 0.25);
+    let circle = [
+        (c + r, c, 1.0), (c + r, c + r, w), (c, c + r, 1.0), (c - r, c + r, w), (c - r, c, 1.0),
+        (c - r, c - r, w), (c, c - r, 1.0), (c + r, c - r, w), (c + r, c, 1.0),
+    ];
+
+    let mut objects = String::new();
+    objects += "\tGeometry: 100, \"Geometry::\", \"TrimNurbsSurface\" {\n\t\tType: \"TrimNurbsSurface\"\n\t}\n";
+    objects += &boundary(200, true);
+    objects += &boundary(201, false);
+    objects += &curve(300, 2, &line((0.0, 0.0), (1.0, 0.0)), &[0.0, 0.0, 1.0, 1.0]);
+    objects += &curve(301, 2, &line((1.0, 0.0), (1.0, 1.0)), &[0.0, 0.0, 1.0, 1.0]);
+    objects += &curve(302, 2, &line((1.0, 1.0), (0.0, 1.0)), &[0.0, 0.0, 1.0, 1.0]);
+    objects += &curve(303, 2, &line((0.0, 0.0), (0.0, 1.0)), &[0.0, 0.0, 1.0, 1.0]);
+    objects += &curve(310, 3, &circle, &[0.0, 0.0, 0.0, 1.0, 1.0, 2.0, 2.0, 3.0, 3.0, 4.0, 4.0, 4.0]);
+
+    let mut connections = String::new();
+    connections += "\tC: \"OO\",2244661497968,100\n\tC: \"OO\",100,2244692783312\n";
+    connections += "\tC: \"OO\",200,100\n\tC: \"OO\",201,100\n";
+    for id in 300..304 {
+        connections += &format!("\tC: \"OO\",{},200\n", id);
+    }
+    connections += "\tC: \"OO\",310,201\n";
+
+    source
+        .replace("\tModel: 2244692783312,", &(objects + "\tModel: 2244692783312,"))
+        .replace("\tC: \"OO\",2244661497968,2244692783312\n", &connections)
+}
+
+#[test]
+fn trim_loops() {
+    let scene = ufbx::load_memory(trimmed_saddle().as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let surfaces = ufbx::trimmed_surfaces(&scene);
+    assert_eq!(surfaces.len(), 1);
+    let trimmed = &surfaces[0];
+    assert_eq!(trimmed.surface.element.element_id, scene.nurbs_surfaces[0].element.element_id);
+    assert_eq!(trimmed.loops.len(), 2);
+
+    let outer = trimmed.loops.iter().find(|l| l.is_outer).expect("expected outer loop");
+    let hole = trimmed.loops.iter().find(|l| !l.is_outer).expect("expected hole loop");
+    assert_eq!(outer.curves.len(), 4);
+    assert_eq!(hole.curves.len(), 1);
+    assert!(!outer.clockwise);
+    assert!(!hole.clockwise);
+
+    // Reversed curves are chained into a single closed polyline
+    let outer_points = outer.sample(1);
+    assert_eq!(outer_points.len(), 4);
+    for p in hole.sample(4) {
+        assert!(((p.x - 0.5).hypot(p.y - 0.5) - 0.25).abs() < 1e-9);
+    }
+}
+
+fn check_trimmed_saddle(trimmed: &TrimmedSurface, mesh: &ufbx::SurfaceMesh) {
+    assert!(mesh.num_triangles() > 0);
+
+    let mut area = 0.0;
+    let mut edges: HashMap<(u32, u32), i32> = HashMap::new();
+    for tri in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[tri[i] as usize].uv);
+        let tri_area = ((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)) * 0.5;
+        assert!(tri_area > 0.0);
+        area += tri_area;
+        for i in 0..3 {
+            let (a, b) = (tri[i], tri[(i + 1) % 3]);
+            *edges.entry((a.min(b), a.max(b))).or_insert(0) += if a < b { 1 } else { -1 };
+        }
+        // Nothing inside the hole
+        let center = ((a.x + b.x + c.x) / 3.0, (a.y + b.y + c.y) / 3.0);
+        assert!((center.0 - 0.5).hypot(center.1 - 0.5) > 0.24);
+    }
+    let expected = 1.0 - std::f64::consts::PI * 0.25 * 0.25;
+    assert!((area - expected).abs() < 1e-2);
+
+    // Watertight: interior edges are used once in each direction, open edges
+    // only lie on the domain boundary or the hole
+    for (&(a, b), &count) in &edges {
+        if count == 0 { continue }
+        assert_eq!(count.abs(), 1);
+        let (pa, pb) = (mesh.vertices[a as usize].uv, mesh.vertices[b as usize].uv);
+        let on_hole = |p: ufbx::Vec2| ((p.x - 0.5).hypot(p.y - 0.5) - 0.25).abs() < 1e-2;
+        let on_domain = |p: ufbx::Vec2| p.x < 1e-6 || p.y < 1e-6 || p.x > 1.0 - 1e-6 || p.y > 1.0 - 1e-6;
+        assert!((on_hole(pa) && on_hole(pb)) || (on_domain(pa) && on_domain(pb)));
+    }
+
+    for v in &mesh.vertices {
+        let p = ufbx::evaluate_nurbs_surface(trimmed.surface, v.uv.x, v.uv.y);
+        assert_eq!((v.position.x, v.position.y, v.position.z), (p.position.x, p.position.y, p.position.z));
+        assert!((v.normal.x.powi(2) + v.normal.y.powi(2) + v.normal.z.powi(2) - 1.0).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn trim_tessellate() {
+    let scene = ufbx::load_memory(trimmed_saddle().as_bytes(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let trimmed = TrimmedSurface::new(&scene.nurbs_trim_surfaces[0]).expect("expected trimmed surface");
+    check_trimmed_saddle(&trimmed, &trimmed.tessellate(&TrimTessellateOpts::default()));
+
+    // The hole lies within a single cell and must be bridged to the cell boundary
+    let coarse = TrimTessellateOpts { span_subdivision_u: 1, span_subdivision_v: 1, ..Default::default() };
+    check_trimmed_saddle(&trimmed, &trimmed.tessellate(&coarse));
+}
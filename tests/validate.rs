@@ -0,0 +1,194 @@
+use std::fs;
+use ufbx;
+
+mod common;
+
+// Joint scaling and `(vertex, weight)` influences of a skin cluster
+type ClusterDesc<'a> = (&'a str, &'a [(usize, f64)]);
+
+// Skins the cube of `cube_anim.fbx` to a joint per cluster
+fn load_skinned(clusters: &[ClusterDesc]) -> ufbx::SceneRoot {
+    let mut objects = String::from("\tDeformer: 800, \"Deformer::skin\", \"Skin\" {\n\t\tVersion: 101\n\t}\n");
+    let mut connections = String::from("\tC: \"OO\",800,2245309148656\n");
+    for (ix, &(scaling, weights)) in clusters.iter().enumerate() {
+        let (cluster_id, joint_id) = (810 + ix, 830 + ix);
+        let indices: Vec<String> = weights.iter().map(|w| w.0.to_string()).collect();
+        let values: Vec<String> = weights.iter().map(|w| w.1.to_string()).collect();
+        objects += &format!(concat!(
+            "\tDeformer: {}, \"SubDeformer::cluster{}\", \"Cluster\" {{\n\t\tVersion: 100\n",
+            "\t\tIndexes: *{} {{\n\t\t\ta: {}\n\t\t}}\n\t\tWeights: *{} {{\n\t\t\ta: {}\n\t\t}}\n",
+            "\t\tTransform: *16 {{\n\t\t\ta: 1,0,0,0,0,1,0,0,0,0,1,0,0,0,0,1\n\t\t}}\n",
+            "\t\tTransformLink: *16 {{\n\t\t\ta: 1,0,0,0,0,1,0,0,0,0,1,0,0,0,0,1\n\t\t}}\n\t}}\n",
+            "\tModel: {}, \"Model::joint{}\", \"LimbNode\" {{\n\t\tVersion: 232\n\t\tProperties70:  {{\n",
+            "\t\t\tP: \"Lcl Scaling\", \"Lcl Scaling\", \"\", \"A\",{}\n\t\t}}\n\t}}\n"),
+            cluster_id, ix, indices.len(), indices.join(","), values.len(), values.join(","), joint_id, ix, scaling);
+        connections += &format!("\tC: \"OO\",{},800\n\tC: \"OO\",{},{}\n\tC: \"OO\",{},0\n", cluster_id, joint_id, cluster_id, joint_id);
+    }
+    common::load_cube_anim_with(&objects, &connections, ufbx::LoadOpts::default())
+}
+
+#[test]
+fn validate_clean() {
+    let scene = ufbx::load_file("tests/data/blender_default.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    assert!(report.findings.is_empty(), "expected no findings, got:\n{}", report);
+    assert_eq!(report.max_severity(), None);
+}
+
+#[test]
+fn validate_empty_material_slot() {
+    let scene = ufbx::load_file("tests/data/instanced_materials.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let node = scene.find_node("ConeBottom").expect("expected to find ConeBottom");
+    let mesh = node.mesh.as_ref().expect("expected ConeBottom to have mesh");
+
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    assert_eq!(report.count(ufbx::FindingKind::EmptyMaterialSlot), 1);
+    let finding = &report.findings[0];
+    assert_eq!(finding.element_id, mesh.element.element_id);
+    assert_eq!(finding.index, Some(1));
+    assert_eq!(finding.severity, ufbx::Severity::Warning);
+    assert!(!report.has_errors());
+
+    let rules = ufbx::Rules {
+        empty_material_slots: Some(ufbx::Severity::Error),
+        ..Default::default()
+    };
+    let report = ufbx::validate(&scene, &rules);
+    assert!(report.has_errors());
+}
+
+#[test]
+fn validate_non_planar() {
+    let scene = ufbx::load_file("tests/data/legacy_blob.fbx", ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    assert!(report.count(ufbx::FindingKind::NonPlanarFace) > 0);
+    assert_eq!(report.count(ufbx::FindingKind::NonManifoldEdge), 0);
+    assert_eq!(report.max_severity(), Some(ufbx::Severity::Warning));
+
+    let rules = ufbx::Rules {
+        planarity_tolerance: 1.0,
+        ..Default::default()
+    };
+    let report = ufbx::validate(&scene, &rules);
+    assert_eq!(report.count(ufbx::FindingKind::NonPlanarFace), 0);
+
+    let rules = ufbx::Rules {
+        non_planar_faces: None,
+        ..Default::default()
+    };
+    let report = ufbx::validate(&scene, &rules);
+    assert_eq!(report.count(ufbx::FindingKind::NonPlanarFace), 0);
+    assert!(report.count(ufbx::FindingKind::EmptyMaterialSlot) > 0);
+}
+
+#[test]
+fn validate_skin_influences() {
+    let scene = load_skinned(&[
+        ("1,1,1", &[(0, 0.2), (1, 0.5)]),
+        ("1,1,1", &[(0, 0.2), (1, 0.5)]),
+        ("1,1,1", &[(0, 0.2)]),
+        ("1,1,1", &[(0, 0.2)]),
+        ("1,1,1", &[(0, 0.2)]),
+    ]);
+    let skin = &scene.skin_deformers[0];
+
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    let findings: Vec<_> = report.findings.iter().filter(|f| f.kind == ufbx::FindingKind::TooManySkinInfluences).collect();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].element_id, skin.element.element_id);
+    assert_eq!(findings[0].index, Some(0));
+    assert_eq!(findings[0].severity, ufbx::Severity::Warning);
+
+    let rules = ufbx::Rules { max_skin_influences: 5, ..Default::default() };
+    let report = ufbx::validate(&scene, &rules);
+    assert_eq!(report.count(ufbx::FindingKind::TooManySkinInfluences), 0);
+}
+
+#[test]
+fn validate_skin_weights() {
+    let scene = load_skinned(&[
+        ("1,1,1", &[(0, 0.5), (1, 0.3), (2, 1.0)]),
+        ("1,1,1", &[(0, 0.5), (1, 0.3)]),
+    ]);
+
+    // Vertex 1 sums to 0.6, vertices without weights are not reported
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    let findings: Vec<_> = report.findings.iter().filter(|f| f.kind == ufbx::FindingKind::UnnormalizedSkinWeights).collect();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].index, Some(1));
+
+    let rules = ufbx::Rules { skin_weight_tolerance: 0.5, ..Default::default() };
+    let report = ufbx::validate(&scene, &rules);
+    assert_eq!(report.count(ufbx::FindingKind::UnnormalizedSkinWeights), 0);
+}
+
+#[test]
+fn validate_joint_scale() {
+    let scene = load_skinned(&[
+        ("1,2,1", &[(0, 1.0)]),
+        ("2,2,2", &[(1, 1.0)]),
+    ]);
+    let joint = scene.find_node("joint0").expect("expected to find joint0");
+
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    let findings: Vec<_> = report.findings.iter().filter(|f| f.kind == ufbx::FindingKind::NonUniformJointScale).collect();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].element_id, joint.element.element_id);
+}
+
+#[test]
+fn validate_negative_determinant() {
+    let scene = load_skinned(&[("-1,1,1", &[(0, 1.0)])]);
+    let joint = scene.find_node("joint0").expect("expected to find joint0");
+
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    let findings: Vec<_> = report.findings.iter().filter(|f| f.kind == ufbx::FindingKind::NegativeDeterminant).collect();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].element_id, joint.element.element_id);
+
+    let rules = ufbx::Rules { negative_determinant: None, ..Default::default() };
+    let report = ufbx::validate(&scene, &rules);
+    assert_eq!(report.count(ufbx::FindingKind::NegativeDeterminant), 0);
+}
+
+#[test]
+fn validate_non_manifold() {
+    // Three triangles sharing the edge 1-2
+    let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 -1 0\nv 0 0 1\nf 1 2 3\nf 2 1 4\nf 1 2 5\n";
+    let opts = ufbx::LoadOpts { file_format: ufbx::FileFormat::Obj, ..Default::default() };
+    let scene = ufbx::load_memory(source.as_bytes(), opts).expect("expected to load scene");
+
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    assert_eq!(report.count(ufbx::FindingKind::NonManifoldEdge), 1);
+    assert!(report.has_errors());
+}
+
+#[test]
+fn validate_missing_texture() {
+    // The relative filename is resolved next to the scene file, not the working directory
+    let dir = common::temp_dir("validate-texture");
+    let path = dir.join("scene.fbx");
+    fs::write(&path, common::textured_cube_source("C:/missing/diffuse.png", "textures\\Diffuse.png")).unwrap();
+
+    let scene = ufbx::load_file(path.to_str().unwrap(), ufbx::LoadOpts::default())
+        .expect("expected to load scene");
+    let texture = &scene.textures[0];
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    let findings: Vec<_> = report.findings.iter().filter(|f| f.kind == ufbx::FindingKind::MissingTextureFile).collect();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].element_id, texture.element.element_id);
+    assert_eq!(findings[0].severity, ufbx::Severity::Error);
+
+    fs::create_dir_all(dir.join("textures")).unwrap();
+    fs::write(dir.join("textures").join("Diffuse.png"), b"png").unwrap();
+    let report = ufbx::validate(&scene, &ufbx::Rules::default());
+    assert_eq!(report.count(ufbx::FindingKind::MissingTextureFile), 0);
+
+    fs::remove_dir_all(&dir).unwrap();
+}